@@ -0,0 +1,318 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::Result;
+use serde::Serialize;
+
+use crate::db::{BreakLog, Database, LifetimeTotals};
+use crate::time_utils;
+
+// ==================== 连续记录 ====================
+
+/// 某一类连续记录
+#[derive(Debug, Serialize, Clone)]
+pub struct Streak {
+    /// work_days / break_compliance / goal
+    pub kind: String,
+    pub label: String,
+    /// kind 为 goal 时对应的目标
+    pub goal_id: Option<i64>,
+    /// 截至今天（或昨天）的连续天数
+    pub current: i64,
+    pub longest: i64,
+    /// 最近一次满足条件的日期（YYYY-MM-DD）
+    pub last_date: Option<String>,
+}
+
+impl Streak {
+    fn from_days(kind: &str, label: String, goal_id: Option<i64>, days: &BTreeSet<NaiveDate>, today: NaiveDate) -> Self {
+        let mut longest = 0;
+        let mut run = 0;
+        let mut prev: Option<NaiveDate> = None;
+        // 只统计截至 today 的日期，便于计算过去某一时刻的连续记录
+        let days: Vec<&NaiveDate> = days.range(..=today).collect();
+        for day in &days {
+            run = match prev {
+                Some(p) if **day - p == Duration::days(1) => run + 1,
+                _ => 1,
+            };
+            longest = longest.max(run);
+            prev = Some(**day);
+        }
+
+        // 今天还没有记录时，昨天结束的连续记录仍然有效
+        let current = match days.last() {
+            Some(last) if **last >= today - Duration::days(1) => run,
+            _ => 0,
+        };
+
+        Streak {
+            kind: kind.to_string(),
+            label,
+            goal_id,
+            current,
+            longest,
+            last_date: days.last().map(|d| d.format("%Y-%m-%d").to_string()),
+        }
+    }
+}
+
+/// 当天的休息全部按计划完成（至少一次）才算达标
+fn compliant_days(logs: &[BreakLog]) -> BTreeSet<NaiveDate> {
+    let mut by_day: BTreeMap<NaiveDate, bool> = BTreeMap::new();
+    for log in logs {
+        let ok = by_day.entry(time_utils::record_date(log.end_time, log.tz_offset_minutes)).or_insert(true);
+        *ok &= log.outcome == "completed";
+    }
+    by_day.into_iter().filter(|(_, ok)| *ok).map(|(d, _)| d).collect()
+}
+
+/// 计算连续工作天数、连续休息达标天数以及每个每日目标的连续达成天数
+pub fn compute_streaks(db: &Database, user_id: i64, now: i64, include_manual: bool) -> Result<Vec<Streak>> {
+    let today = time_utils::local_date(now);
+    let mut streaks = Vec::new();
+
+    let work_days: BTreeSet<NaiveDate> = db
+        .get_work_end_times(user_id, include_manual)?
+        .into_iter()
+        .map(|(end_time, offset)| time_utils::record_date(end_time, offset))
+        .collect();
+    streaks.push(Streak::from_days("work_days", "连续工作".to_string(), None, &work_days, today));
+
+    let logs = db.get_break_logs(user_id, 0, i64::MAX)?;
+    streaks.push(Streak::from_days(
+        "break_compliance",
+        "连续按时休息".to_string(),
+        None,
+        &compliant_days(&logs),
+        today,
+    ));
+
+    // 目标的连续达成基于已保存的进度，总是包含手动补录的记录
+    for goal in db.get_goals(user_id, true)?.into_iter().filter(|g| g.period == "daily") {
+        let achieved_days: BTreeSet<NaiveDate> = db
+            .get_goal_history(user_id, Some(goal.id), 0, i64::MAX)?
+            .into_iter()
+            .filter(|p| p.achieved_at.is_some())
+            .map(|p| time_utils::local_date(p.period_start))
+            .collect();
+        streaks.push(Streak::from_days("goal", goal.title, Some(goal.id), &achieved_days, today));
+    }
+
+    Ok(streaks)
+}
+
+// ==================== 成就 ====================
+
+#[derive(Debug, Clone, Copy)]
+enum Metric {
+    Pomodoros,
+    FocusHours,
+    WorkStreak,
+    ComplianceStreak,
+}
+
+struct AchievementDef {
+    key: &'static str,
+    title: &'static str,
+    description: &'static str,
+    metric: Metric,
+    threshold: f64,
+}
+
+const ACHIEVEMENTS: &[AchievementDef] = &[
+    AchievementDef { key: "pomodoros_1", title: "初次专注", description: "完成第 1 个番茄", metric: Metric::Pomodoros, threshold: 1.0 },
+    AchievementDef { key: "pomodoros_10", title: "渐入佳境", description: "累计完成 10 个番茄", metric: Metric::Pomodoros, threshold: 10.0 },
+    AchievementDef { key: "pomodoros_100", title: "百番茄", description: "累计完成 100 个番茄", metric: Metric::Pomodoros, threshold: 100.0 },
+    AchievementDef { key: "pomodoros_1000", title: "千番茄", description: "累计完成 1000 个番茄", metric: Metric::Pomodoros, threshold: 1000.0 },
+    AchievementDef { key: "focus_hours_10", title: "十小时", description: "累计专注 10 小时", metric: Metric::FocusHours, threshold: 10.0 },
+    AchievementDef { key: "focus_hours_100", title: "百小时", description: "累计专注 100 小时", metric: Metric::FocusHours, threshold: 100.0 },
+    AchievementDef { key: "focus_hours_1000", title: "千小时", description: "累计专注 1000 小时", metric: Metric::FocusHours, threshold: 1000.0 },
+    AchievementDef { key: "work_streak_7", title: "一周不断", description: "连续 7 天有工作记录", metric: Metric::WorkStreak, threshold: 7.0 },
+    AchievementDef { key: "work_streak_30", title: "月度坚持", description: "连续 30 天有工作记录", metric: Metric::WorkStreak, threshold: 30.0 },
+    AchievementDef { key: "compliance_streak_7", title: "劳逸结合", description: "连续 7 天按时完成所有休息", metric: Metric::ComplianceStreak, threshold: 7.0 },
+];
+
+/// 成就定义及当前进度
+#[derive(Debug, Serialize, Clone)]
+pub struct AchievementStatus {
+    pub key: String,
+    pub title: String,
+    pub description: String,
+    pub threshold: f64,
+    pub progress: f64,
+    pub unlocked_at: Option<i64>,
+}
+
+fn metric_value(metric: Metric, totals: &LifetimeTotals, streaks: &[Streak]) -> f64 {
+    let longest = |kind: &str| {
+        streaks
+            .iter()
+            .find(|s| s.kind == kind)
+            .map(|s| s.longest as f64)
+            .unwrap_or(0.0)
+    };
+    match metric {
+        Metric::Pomodoros => totals.pomodoros as f64,
+        Metric::FocusHours => totals.total_work_ms as f64 / 3_600_000.0,
+        Metric::WorkStreak => longest("work_days"),
+        Metric::ComplianceStreak => longest("break_compliance"),
+    }
+}
+
+/// 所有成就的当前进度和已保存的解锁时间，只读，不解锁新成就
+pub fn achievement_status(db: &Database, user_id: i64, now: i64) -> Result<Vec<AchievementStatus>> {
+    let totals = db.get_lifetime_totals(user_id)?;
+    let streaks = compute_streaks(db, user_id, now, true)?;
+    statuses(db, user_id, |metric| metric_value(metric, &totals, &streaks))
+}
+
+/// 检查并解锁新达成的成就，返回所有成就的状态和本次新解锁的成就
+pub fn check_achievements(db: &Database, user_id: i64, now: i64) -> Result<(Vec<AchievementStatus>, Vec<AchievementStatus>)> {
+    let totals = db.get_lifetime_totals(user_id)?;
+    let streaks = compute_streaks(db, user_id, now, true)?;
+    unlock_reached(db, user_id, now, |metric| metric_value(metric, &totals, &streaks))
+}
+
+fn is_streak(metric: Metric) -> bool {
+    matches!(metric, Metric::WorkStreak | Metric::ComplianceStreak)
+}
+
+/// 记录或休息日志变化后的检查：连续记录只计算包含 day 的那一段，不读取全部历史
+/// 返回的进度对连续记录类成就只是下限，完整进度用 check_achievements 获取
+pub fn check_achievements_on(db: &Database, user_id: i64, now: i64, day: NaiveDate) -> Result<Vec<AchievementStatus>> {
+    let totals = db.get_lifetime_totals(user_id)?;
+    let unlocked: BTreeSet<String> = db.get_achievements(user_id)?.into_iter().map(|a| a.key).collect();
+    let pending_span = ACHIEVEMENTS
+        .iter()
+        .filter(|def| is_streak(def.metric) && !unlocked.contains(def.key))
+        .map(|def| def.threshold as i64)
+        .max();
+
+    // 只要某段连续记录达到阈值，day 前后各 span - 1 天内就足以判断
+    let (work_run, compliance_run) = match pending_span {
+        Some(span) => {
+            let first = day - Duration::days(span - 1);
+            let last = day + Duration::days(span - 1);
+            let (start, end) = time_utils::padded_date_range(first, last);
+            let work_days: BTreeSet<NaiveDate> = db
+                .get_timer_records_between(user_id, start, end)?
+                .into_iter()
+                .filter(|r| r.mode.as_deref() == Some("work"))
+                .map(|r| time_utils::record_date(r.end_time, r.tz_offset_minutes))
+                .collect();
+            let compliant = compliant_days(&db.get_break_logs(user_id, start, end)?);
+            (
+                run_containing(&work_days, day, first, last),
+                run_containing(&compliant, day, first, last),
+            )
+        }
+        None => (0, 0),
+    };
+
+    let (_, newly) = unlock_reached(db, user_id, now, |metric| match metric {
+        Metric::WorkStreak => work_run as f64,
+        Metric::ComplianceStreak => compliance_run as f64,
+        _ => metric_value(metric, &totals, &[]),
+    })?;
+    Ok(newly)
+}
+
+/// first..=last 范围内包含 day 的连续天数，day 本身不满足时为 0
+fn run_containing(days: &BTreeSet<NaiveDate>, day: NaiveDate, first: NaiveDate, last: NaiveDate) -> i64 {
+    if !days.contains(&day) {
+        return 0;
+    }
+    let before = days.range(first..day).rev().zip(1..).take_while(|(d, i)| **d == day - Duration::days(*i)).count();
+    let after = days
+        .range(day + Duration::days(1)..=last)
+        .zip(1..)
+        .take_while(|(d, i)| **d == day + Duration::days(*i))
+        .count();
+    (before + after + 1) as i64
+}
+
+/// 按 value 给出的进度生成所有成就的状态，解锁时间取已保存的值
+fn statuses(db: &Database, user_id: i64, value: impl Fn(Metric) -> f64) -> Result<Vec<AchievementStatus>> {
+    let unlocked: BTreeMap<String, i64> = db
+        .get_achievements(user_id)?
+        .into_iter()
+        .map(|a| (a.key, a.unlocked_at))
+        .collect();
+
+    Ok(ACHIEVEMENTS
+        .iter()
+        .map(|def| AchievementStatus {
+            key: def.key.to_string(),
+            title: def.title.to_string(),
+            description: def.description.to_string(),
+            threshold: def.threshold,
+            progress: value(def.metric),
+            unlocked_at: unlocked.get(def.key).copied(),
+        })
+        .collect())
+}
+
+/// 按 value 给出的进度解锁达到阈值的成就
+fn unlock_reached(
+    db: &Database,
+    user_id: i64,
+    now: i64,
+    value: impl Fn(Metric) -> f64,
+) -> Result<(Vec<AchievementStatus>, Vec<AchievementStatus>)> {
+    let mut all = statuses(db, user_id, value)?;
+    let mut newly = Vec::new();
+    for (status, def) in all.iter_mut().zip(ACHIEVEMENTS) {
+        if status.unlocked_at.is_none()
+            && status.progress >= def.threshold
+            && db.unlock_achievement(user_id, def.key, status.progress, now)?
+        {
+            status.unlocked_at = Some(now);
+            newly.push(status.clone());
+        }
+    }
+
+    Ok((all, newly))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn streak_ignores_days_after_today() {
+        let days: BTreeSet<NaiveDate> = ["2026-03-01", "2026-03-02", "2026-03-03", "2026-03-05", "2026-03-06", "2026-03-07", "2026-03-08"]
+            .into_iter()
+            .map(date)
+            .collect();
+
+        // 报告截止到 3 月 3 日时，之后的日期不影响当前和最长记录
+        let streak = Streak::from_days("work_days", String::new(), None, &days, date("2026-03-03"));
+        assert_eq!((streak.current, streak.longest), (3, 3));
+        assert_eq!(streak.last_date.as_deref(), Some("2026-03-03"));
+
+        // 3 月 4 日没有记录，截至当天仍按昨天结束的记录计算
+        let streak = Streak::from_days("work_days", String::new(), None, &days, date("2026-03-04"));
+        assert_eq!(streak.current, 3);
+
+        let streak = Streak::from_days("work_days", String::new(), None, &days, date("2026-03-10"));
+        assert_eq!((streak.current, streak.longest), (0, 4));
+    }
+
+    #[test]
+    fn run_containing_stops_at_gaps_and_window() {
+        let days: BTreeSet<NaiveDate> = ["2026-03-01", "2026-03-02", "2026-03-03", "2026-03-05"]
+            .into_iter()
+            .map(date)
+            .collect();
+        let (first, last) = (date("2026-02-01"), date("2026-03-31"));
+        assert_eq!(run_containing(&days, date("2026-03-02"), first, last), 3);
+        assert_eq!(run_containing(&days, date("2026-03-05"), first, last), 1);
+        assert_eq!(run_containing(&days, date("2026-03-04"), first, last), 0);
+        assert_eq!(run_containing(&days, date("2026-03-02"), date("2026-03-02"), last), 2);
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::BillableEntry;
+use crate::time_utils;
+
+const MINUTE_MS: i64 = 60_000;
+const HOUR_MS: f64 = 3_600_000.0;
+
+/// 计费时长取整方式
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    /// 不取整
+    #[default]
+    None,
+    /// 四舍五入到最近的单位
+    Nearest,
+    /// 向上取整
+    Up,
+    /// 向下取整
+    Down,
+}
+
+/// 取整规则，例如按 6 分钟（0.1 小时）或 15 分钟取整；按每条记录分别取整
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct RoundingRule {
+    #[serde(default)]
+    pub mode: RoundingMode,
+    #[serde(default = "default_increment")]
+    pub increment_minutes: i64,
+}
+
+fn default_increment() -> i64 {
+    6
+}
+
+impl Default for RoundingRule {
+    fn default() -> Self {
+        RoundingRule {
+            mode: RoundingMode::None,
+            increment_minutes: default_increment(),
+        }
+    }
+}
+
+impl RoundingRule {
+    pub fn apply(&self, duration_ms: i64) -> i64 {
+        let unit = self.increment_minutes.max(1) * MINUTE_MS;
+        let duration_ms = duration_ms.max(0);
+        match self.mode {
+            RoundingMode::None => duration_ms,
+            RoundingMode::Nearest => (duration_ms + unit / 2) / unit * unit,
+            RoundingMode::Up => (duration_ms + unit - 1) / unit * unit,
+            RoundingMode::Down => duration_ms / unit * unit,
+        }
+    }
+}
+
+/// 单个项目的计费汇总
+#[derive(Debug, Serialize, Clone)]
+pub struct ProjectBilling {
+    pub project_id: i64,
+    pub project_name: String,
+    pub hourly_rate: f64,
+    pub currency: String,
+    pub entries: i64,
+    /// 实际工作时长
+    pub raw_ms: i64,
+    /// 取整后的计费时长
+    pub billed_ms: i64,
+    pub hours: f64,
+    pub amount: f64,
+}
+
+/// 单个客户在某一币种下的计费汇总
+#[derive(Debug, Serialize, Clone)]
+pub struct ClientBilling {
+    /// 未设置客户的项目归入 None
+    pub client: Option<String>,
+    pub currency: String,
+    pub projects: Vec<ProjectBilling>,
+    pub raw_ms: i64,
+    pub billed_ms: i64,
+    pub hours: f64,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BillingReport {
+    pub start_time: i64,
+    pub end_time: i64,
+    pub rounding: RoundingRule,
+    pub clients: Vec<ClientBilling>,
+}
+
+fn round_money(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn ms_to_hours(ms: i64) -> f64 {
+    (ms as f64 / HOUR_MS * 100.0).round() / 100.0
+}
+
+/// 按客户、项目汇总计费时长与金额（同一客户不同币种分开统计）
+pub fn billing_report(entries: &[BillableEntry], start_time: i64, end_time: i64, rounding: RoundingRule) -> BillingReport {
+    let mut groups: BTreeMap<(Option<String>, String), BTreeMap<i64, ProjectBilling>> = BTreeMap::new();
+
+    for entry in entries {
+        let project = groups
+            .entry((entry.client.clone(), entry.currency.clone()))
+            .or_default()
+            .entry(entry.project_id)
+            .or_insert_with(|| ProjectBilling {
+                project_id: entry.project_id,
+                project_name: entry.project_name.clone(),
+                hourly_rate: entry.hourly_rate,
+                currency: entry.currency.clone(),
+                entries: 0,
+                raw_ms: 0,
+                billed_ms: 0,
+                hours: 0.0,
+                amount: 0.0,
+            });
+        project.entries += 1;
+        project.raw_ms += entry.duration;
+        project.billed_ms += rounding.apply(entry.duration);
+    }
+
+    let clients = groups
+        .into_iter()
+        .map(|((client, currency), projects)| {
+            let projects: Vec<ProjectBilling> = projects
+                .into_values()
+                .map(|mut p| {
+                    p.hours = ms_to_hours(p.billed_ms);
+                    p.amount = round_money(p.billed_ms as f64 / HOUR_MS * p.hourly_rate);
+                    p
+                })
+                .collect();
+            let raw_ms = projects.iter().map(|p| p.raw_ms).sum();
+            let billed_ms = projects.iter().map(|p| p.billed_ms).sum();
+            let amount = round_money(projects.iter().map(|p| p.amount).sum());
+            ClientBilling {
+                client,
+                currency,
+                projects,
+                raw_ms,
+                billed_ms,
+                hours: ms_to_hours(billed_ms),
+                amount,
+            }
+        })
+        .collect();
+
+    BillingReport {
+        start_time,
+        end_time,
+        rounding,
+        clients,
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 导出为 CSV，每个项目一行，另附每个客户的小计行
+pub fn billing_csv(report: &BillingReport) -> String {
+    let mut out = String::from("client,project,currency,hourly_rate,entries,raw_hours,billed_hours,amount\n");
+    let period = format!(
+        "{} ~ {}",
+        time_utils::day_key(report.start_time),
+        time_utils::day_key(report.end_time - 1)
+    );
+
+    for client in &report.clients {
+        let client_name = client.client.as_deref().unwrap_or("");
+        for p in &client.projects {
+            out.push_str(&format!(
+                "{},{},{},{:.2},{},{:.2},{:.2},{:.2}\n",
+                csv_field(client_name),
+                csv_field(&p.project_name),
+                csv_field(&p.currency),
+                p.hourly_rate,
+                p.entries,
+                p.raw_ms as f64 / HOUR_MS,
+                p.hours,
+                p.amount,
+            ));
+        }
+        out.push_str(&format!(
+            "{},{},{},,{},{:.2},{:.2},{:.2}\n",
+            csv_field(client_name),
+            csv_field(&format!("小计 ({})", period)),
+            csv_field(&client.currency),
+            client.projects.iter().map(|p| p.entries).sum::<i64>(),
+            client.raw_ms as f64 / HOUR_MS,
+            client.hours,
+            client.amount,
+        ));
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: i64 = MINUTE_MS;
+
+    fn rule(mode: RoundingMode, increment_minutes: i64) -> RoundingRule {
+        RoundingRule { mode, increment_minutes }
+    }
+
+    fn entry(project_id: i64, client: Option<&str>, currency: &str, rate: f64, minutes: i64) -> BillableEntry {
+        BillableEntry {
+            record_id: format!("r{}-{}", project_id, minutes),
+            project_id,
+            project_name: format!("p{}", project_id),
+            client: client.map(str::to_string),
+            hourly_rate: rate,
+            currency: currency.to_string(),
+            start_time: 0,
+            duration: minutes * MINUTE,
+        }
+    }
+
+    #[test]
+    fn rounding_modes_on_increment_boundaries() {
+        let six = |mode| rule(mode, 6);
+        assert_eq!(six(RoundingMode::None).apply(7 * MINUTE + 1), 7 * MINUTE + 1);
+
+        assert_eq!(six(RoundingMode::Nearest).apply(8 * MINUTE + 59_999), 6 * MINUTE);
+        assert_eq!(six(RoundingMode::Nearest).apply(9 * MINUTE), 12 * MINUTE);
+        assert_eq!(six(RoundingMode::Up).apply(6 * MINUTE), 6 * MINUTE);
+        assert_eq!(six(RoundingMode::Up).apply(6 * MINUTE + 1), 12 * MINUTE);
+        assert_eq!(six(RoundingMode::Down).apply(11 * MINUTE + 59_999), 6 * MINUTE);
+        assert_eq!(six(RoundingMode::Down).apply(0), 0);
+    }
+
+    #[test]
+    fn rounding_clamps_bad_input() {
+        // 增量小于 1 分钟按 1 分钟处理，负时长按 0 处理
+        assert_eq!(rule(RoundingMode::Up, 0).apply(30_001), MINUTE);
+        assert_eq!(rule(RoundingMode::Nearest, -5).apply(90_000), 2 * MINUTE);
+        assert_eq!(rule(RoundingMode::Up, 15).apply(-MINUTE), 0);
+        assert_eq!(rule(RoundingMode::None, 15).apply(-MINUTE), 0);
+    }
+
+    #[test]
+    fn report_rounds_each_entry_before_summing() {
+        let entries = [
+            entry(1, Some("acme"), "USD", 100.0, 7),
+            entry(1, Some("acme"), "USD", 100.0, 7),
+            entry(2, Some("acme"), "EUR", 80.0, 30),
+        ];
+        let report = billing_report(&entries, 0, 1, rule(RoundingMode::Up, 15));
+
+        // 同一客户不同币种分开统计
+        assert_eq!(report.clients.len(), 2);
+        let usd = report.clients.iter().find(|c| c.currency == "USD").unwrap();
+        // 每条 7 分钟分别取整为 15 分钟，而不是合计 14 分钟后取整
+        assert_eq!(usd.raw_ms, 14 * MINUTE);
+        assert_eq!(usd.billed_ms, 30 * MINUTE);
+        assert_eq!(usd.hours, 0.5);
+        assert_eq!(usd.amount, 50.0);
+
+        let eur = report.clients.iter().find(|c| c.currency == "EUR").unwrap();
+        assert_eq!(eur.billed_ms, 30 * MINUTE);
+        assert_eq!(eur.amount, 40.0);
+    }
+
+    #[test]
+    fn amounts_round_to_cents() {
+        let report = billing_report(&[entry(1, None, "USD", 33.33, 10)], 0, 1, RoundingRule::default());
+        let client = &report.clients[0];
+        assert_eq!(client.client, None);
+        assert_eq!(client.hours, 0.17);
+        assert_eq!(client.amount, 5.56);
+    }
+}
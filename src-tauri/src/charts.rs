@@ -0,0 +1,486 @@
+use std::f64::consts::PI;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use chrono::{Datelike, Duration, NaiveDate};
+use ab_glyph::{point, Font, FontVec, PxScale, ScaleFont};
+use image::{imageops, ImageFormat, Rgba, RgbaImage};
+
+use crate::db::Database;
+use crate::pdf::{self, PdfPage};
+use crate::stats::{self, CategoryRollup, YearHeatmap};
+use crate::time_utils;
+
+/// 分类没有设置颜色时依次使用的颜色
+const PALETTE: [&str; 8] = [
+    "#f59e0b", "#3b82f6", "#22c55e", "#ef4444", "#8b5cf6", "#14b8a6", "#ec4899", "#64748b",
+];
+/// 热力图 0-4 级的颜色
+const HEAT_COLORS: [&str; 5] = ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"];
+const BACKGROUND: &str = "#ffffff";
+const TEXT_COLOR: &str = "#1f2937";
+const MUTED_COLOR: &str = "#6b7280";
+const WEEKDAYS: [&str; 7] = ["周一", "周二", "周三", "周四", "周五", "周六", "周日"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+#[derive(Debug, Clone)]
+enum Shape {
+    Rect { x: f64, y: f64, w: f64, h: f64, color: String },
+    Polygon { points: Vec<(f64, f64)>, color: String },
+    Text { x: f64, y: f64, size: f64, text: String, color: String, anchor: Anchor },
+}
+
+/// 与输出格式无关的图形，SVG 和 PNG 使用同一份布局
+pub struct Chart {
+    width: u32,
+    height: u32,
+    shapes: Vec<Shape>,
+}
+
+impl Chart {
+    fn new(width: u32, height: u32) -> Self {
+        let mut chart = Chart { width, height, shapes: Vec::new() };
+        chart.rect(0.0, 0.0, width as f64, height as f64, BACKGROUND);
+        chart
+    }
+
+    fn rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: &str) {
+        self.shapes.push(Shape::Rect { x, y, w, h, color: color.to_string() });
+    }
+
+    fn text(&mut self, x: f64, y: f64, size: f64, text: impl Into<String>, color: &str, anchor: Anchor) {
+        self.shapes.push(Shape::Text { x, y, size, text: text.into(), color: color.to_string(), anchor });
+    }
+
+    /// 以 (cx, cy) 为圆心的扇形，角度从 12 点方向顺时针计算
+    fn wedge(&mut self, cx: f64, cy: f64, r: f64, from: f64, to: f64, color: &str) {
+        let steps = (((to - from) / (2.0 * PI)) * 120.0).ceil().max(2.0) as usize;
+        let mut points = vec![(cx, cy)];
+        for i in 0..=steps {
+            let a = from + (to - from) * i as f64 / steps as f64;
+            points.push((cx + r * a.sin(), cy - r * a.cos()));
+        }
+        self.shapes.push(Shape::Polygon { points, color: color.to_string() });
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">\n",
+            w = self.width,
+            h = self.height
+        );
+        for shape in &self.shapes {
+            match shape {
+                Shape::Rect { x, y, w, h, color } => svg.push_str(&format!(
+                    "  <rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>\n",
+                    x, y, w, h, color
+                )),
+                Shape::Polygon { points, color } => {
+                    let points: Vec<String> = points.iter().map(|(x, y)| format!("{:.1},{:.1}", x, y)).collect();
+                    svg.push_str(&format!("  <polygon points=\"{}\" fill=\"{}\"/>\n", points.join(" "), color));
+                }
+                Shape::Text { x, y, size, text, color, anchor } => {
+                    let anchor = match anchor {
+                        Anchor::Start => "start",
+                        Anchor::Middle => "middle",
+                        Anchor::End => "end",
+                    };
+                    svg.push_str(&format!(
+                        "  <text x=\"{:.1}\" y=\"{:.1}\" font-size=\"{:.0}\" fill=\"{}\" text-anchor=\"{}\">{}</text>\n",
+                        x, y, size, color, anchor, escape_xml(text)
+                    ));
+                }
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// 以两倍尺寸绘制后缩小，得到平滑的边缘；文字优先使用系统中文字体
+    pub fn to_png(&self) -> RgbaImage {
+        const SCALE: f64 = 2.0;
+        let mut img = RgbaImage::from_pixel(
+            self.width * SCALE as u32,
+            self.height * SCALE as u32,
+            parse_color(BACKGROUND),
+        );
+        for shape in &self.shapes {
+            match shape {
+                Shape::Rect { x, y, w, h, color } => {
+                    let points = [(*x, *y), (x + w, *y), (x + w, y + h), (*x, y + h)];
+                    fill_polygon(&mut img, &points.map(|(x, y)| (x * SCALE, y * SCALE)), parse_color(color));
+                }
+                Shape::Polygon { points, color } => {
+                    let points: Vec<(f64, f64)> = points.iter().map(|(x, y)| (x * SCALE, y * SCALE)).collect();
+                    fill_polygon(&mut img, &points, parse_color(color));
+                }
+                Shape::Text { x, y, size, text, color, anchor } => {
+                    draw_text(&mut img, x * SCALE, y * SCALE, size * SCALE, text, parse_color(color), *anchor);
+                }
+            }
+        }
+        imageops::resize(&img, self.width, self.height, imageops::FilterType::Triangle)
+    }
+
+    /// 以 (left, top) 为左上角、按 scale 缩放绘制到 PDF 页面上
+    pub fn draw_pdf(&self, page: &mut PdfPage, left: f64, top: f64, scale: f64) {
+        let map = |x: f64, y: f64| (left + x * scale, top + y * scale);
+        for shape in &self.shapes {
+            match shape {
+                Shape::Rect { x, y, w, h, color } => {
+                    let (x, y) = map(*x, *y);
+                    page.fill_rect(x, y, w * scale, h * scale, color);
+                }
+                Shape::Polygon { points, color } => {
+                    let points: Vec<(f64, f64)> = points.iter().map(|(x, y)| map(*x, *y)).collect();
+                    page.fill_polygon(&points, color);
+                }
+                Shape::Text { x, y, size, text, color, anchor } => {
+                    let (x, y) = map(*x, *y);
+                    let size = size * scale;
+                    let x = match anchor {
+                        Anchor::Start => x,
+                        Anchor::Middle => x - pdf::text_width(text, size) / 2.0,
+                        Anchor::End => x - pdf::text_width(text, size),
+                    };
+                    page.text(x, y, size, text, color, false);
+                }
+            }
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// 按扩展名保存为 .svg 或 .png
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("svg") => std::fs::write(path, self.to_svg()).map_err(|e| e.to_string()),
+            Some("png") => self
+                .to_png()
+                .save_with_format(path, ImageFormat::Png)
+                .map_err(|e| e.to_string()),
+            _ => Err("Chart path must end with .svg or .png".to_string()),
+        }
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn parse_color(color: &str) -> Rgba<u8> {
+    let hex = color.trim_start_matches('#');
+    let channel = |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok());
+    match (hex.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(r), Some(g), Some(b)) => Rgba([r, g, b, 255]),
+        _ => Rgba([128, 128, 128, 255]),
+    }
+}
+
+/// 扫描线填充多边形（奇偶规则），采样点取像素中心
+fn fill_polygon(img: &mut RgbaImage, points: &[(f64, f64)], color: Rgba<u8>) {
+    if points.len() < 3 {
+        return;
+    }
+    let min_y = points.iter().map(|p| p.1).fold(f64::MAX, f64::min).max(0.0).floor() as u32;
+    let max_y = points.iter().map(|p| p.1).fold(f64::MIN, f64::max).min(img.height() as f64).ceil() as u32;
+
+    for y in min_y..max_y {
+        let sy = y as f64 + 0.5;
+        let mut xs: Vec<f64> = Vec::new();
+        for i in 0..points.len() {
+            let (x0, y0) = points[i];
+            let (x1, y1) = points[(i + 1) % points.len()];
+            if (y0 <= sy && y1 > sy) || (y1 <= sy && y0 > sy) {
+                xs.push(x0 + (sy - y0) / (y1 - y0) * (x1 - x0));
+            }
+        }
+        xs.sort_by(|a, b| a.total_cmp(b));
+        for span in xs.chunks(2) {
+            if let [from, to] = span {
+                let from = (from - 0.5).ceil().max(0.0) as u32;
+                let to = ((to - 0.5).floor() + 1.0).min(img.width() as f64).max(0.0) as u32;
+                for x in from..to {
+                    img.put_pixel(x, y, color);
+                }
+            }
+        }
+    }
+}
+
+/// PNG 文字优先使用的系统字体（能显示中文），依次尝试 Windows、macOS 和 Linux 的常见路径
+const SYSTEM_FONTS: [&str; 12] = [
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "C:\\Windows\\Fonts\\simsun.ttc",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Light.ttc",
+    "/System/Library/Fonts/Hiragino Sans GB.ttc",
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+];
+
+/// 内置的西文字体，系统字体缺字或找不到时使用
+const FALLBACK_FONT: &[u8] = include_bytes!("../fonts/DejaVuSans.ttf");
+
+/// 按优先级排列的字体，最后一个总是内置字体
+fn fonts() -> &'static [FontVec] {
+    static FONTS: OnceLock<Vec<FontVec>> = OnceLock::new();
+    FONTS.get_or_init(|| {
+        let mut fonts: Vec<FontVec> = SYSTEM_FONTS
+            .iter()
+            .filter_map(|path| std::fs::read(path).ok())
+            .filter_map(|data| FontVec::try_from_vec_and_index(data, 0).ok())
+            .take(1)
+            .collect();
+        if let Ok(font) = FontVec::try_from_vec(FALLBACK_FONT.to_vec()) {
+            fonts.push(font);
+        }
+        fonts
+    })
+}
+
+/// 选择第一个包含该字符的字体，都没有时使用内置字体（显示为缺字符号）
+fn font_for(fonts: &[FontVec], c: char) -> Option<&FontVec> {
+    fonts.iter().find(|f| f.glyph_id(c).0 != 0).or_else(|| fonts.last())
+}
+
+/// size 为 em 大小（与 SVG 的 font-size 一致），换算成 ab_glyph 使用的行高缩放
+fn px_scale(font: &FontVec, size: f64) -> PxScale {
+    let units_per_em = font.units_per_em().unwrap_or(1000.0);
+    PxScale::from(size as f32 * font.height_unscaled() / units_per_em)
+}
+
+/// 按字体轮廓绘制文字，y 为基线位置
+fn draw_text(img: &mut RgbaImage, x: f64, y: f64, size: f64, text: &str, color: Rgba<u8>, anchor: Anchor) {
+    let fonts = fonts();
+    let glyphs: Vec<(&FontVec, PxScale, char)> = text
+        .chars()
+        .filter_map(|c| font_for(fonts, c).map(|f| (f, px_scale(f, size), c)))
+        .collect();
+    let width: f32 = glyphs
+        .iter()
+        .map(|(f, scale, c)| f.as_scaled(*scale).h_advance(f.glyph_id(*c)))
+        .sum();
+    let mut left = match anchor {
+        Anchor::Start => x as f32,
+        Anchor::Middle => x as f32 - width / 2.0,
+        Anchor::End => x as f32 - width,
+    };
+
+    for (font, scale, c) in glyphs {
+        let id = font.glyph_id(c);
+        let glyph = id.with_scale_and_position(scale, point(left, y as f32));
+        left += font.as_scaled(scale).h_advance(id);
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i64 + gx as i64;
+            let py = bounds.min.y as i64 + gy as i64;
+            if px >= 0 && py >= 0 && (px as u32) < img.width() && (py as u32) < img.height() {
+                blend(img.get_pixel_mut(px as u32, py as u32), color, coverage);
+            }
+        });
+    }
+}
+
+/// 按覆盖率把颜色混合到像素上
+fn blend(pixel: &mut Rgba<u8>, color: Rgba<u8>, coverage: f32) {
+    let alpha = coverage.clamp(0.0, 1.0);
+    for i in 0..3 {
+        pixel[i] = (pixel[i] as f32 * (1.0 - alpha) + color[i] as f32 * alpha).round() as u8;
+    }
+}
+
+fn format_minutes(minutes: f64) -> String {
+    if minutes >= 60.0 {
+        format!("{:.1}小时", minutes / 60.0)
+    } else {
+        format!("{:.0}分钟", minutes)
+    }
+}
+
+/// 顶级分类工作时长饼图，右侧为图例
+pub fn category_pie(title: &str, rollup: &[CategoryRollup]) -> Chart {
+    let slices: Vec<&CategoryRollup> = rollup.iter().filter(|c| c.depth == 0 && c.total_ms > 0).collect();
+    let total: i64 = slices.iter().map(|c| c.total_ms).sum();
+    let height = (90 + slices.len() as u32 * 26).max(360);
+    let mut chart = Chart::new(640, height);
+    chart.text(24.0, 36.0, 18.0, title, TEXT_COLOR, Anchor::Start);
+
+    if total == 0 {
+        chart.text(320.0, height as f64 / 2.0, 14.0, "暂无数据", MUTED_COLOR, Anchor::Middle);
+        return chart;
+    }
+
+    let (cx, cy, r) = (180.0, 60.0 + 140.0, 130.0);
+    let mut angle = 0.0;
+    for (i, slice) in slices.iter().enumerate() {
+        let color = slice.color.clone().unwrap_or_else(|| PALETTE[i % PALETTE.len()].to_string());
+        let sweep = slice.total_ms as f64 / total as f64 * 2.0 * PI;
+        chart.wedge(cx, cy, r, angle, angle + sweep, &color);
+        angle += sweep;
+
+        let y = 76.0 + i as f64 * 26.0;
+        chart.rect(350.0, y - 12.0, 14.0, 14.0, &color);
+        let minutes = slice.total_ms as f64 / 60_000.0;
+        let share = slice.total_ms as f64 / total as f64 * 100.0;
+        let share = if share < 0.5 { "<1".to_string() } else { format!("{:.0}", share) };
+        chart.text(372.0, y, 13.0, format!("{}  {}% ({})", slice.label, share, format_minutes(minutes)), TEXT_COLOR, Anchor::Start);
+    }
+    chart
+}
+
+/// 每日工作分钟数柱状图，labels 与 minutes 一一对应
+pub fn daily_bars(title: &str, labels: &[String], minutes: &[u32]) -> Chart {
+    let (width, height) = (640.0, 360.0);
+    let (left, right, top, bottom) = (56.0, 24.0, 60.0, 48.0);
+    let mut chart = Chart::new(width as u32, height as u32);
+    chart.text(24.0, 36.0, 18.0, title, TEXT_COLOR, Anchor::Start);
+
+    // 纵轴上限向上取整到整小时，分 4 格，每格为 15 分钟的整数倍
+    let max = minutes.iter().copied().max().unwrap_or(0).max(60) as f64;
+    let axis_max = (max / 60.0).ceil() * 60.0;
+    let plot_h = height - top - bottom;
+    for i in 0..=4 {
+        let value = axis_max * i as f64 / 4.0;
+        let y = top + plot_h - plot_h * i as f64 / 4.0;
+        chart.rect(left, y, width - left - right, 1.0, "#e5e7eb");
+        chart.text(left - 8.0, y + 4.0, 11.0, format_minutes(value), MUTED_COLOR, Anchor::End);
+    }
+
+    let n = minutes.len().max(1);
+    let slot = (width - left - right) / n as f64;
+    let label_every = n.div_ceil(14);
+    for (i, value) in minutes.iter().enumerate() {
+        let bar_h = plot_h * *value as f64 / axis_max;
+        let x = left + slot * i as f64 + slot * 0.15;
+        chart.rect(x, top + plot_h - bar_h, slot * 0.7, bar_h, PALETTE[0]);
+        if n <= 14 && *value > 0 {
+            chart.text(x + slot * 0.35, top + plot_h - bar_h - 6.0, 11.0, format_minutes(*value as f64), TEXT_COLOR, Anchor::Middle);
+        }
+        if i % label_every == 0 {
+            if let Some(label) = labels.get(i) {
+                chart.text(x + slot * 0.35, height - bottom + 18.0, 11.0, label.clone(), MUTED_COLOR, Anchor::Middle);
+            }
+        }
+    }
+    chart
+}
+
+/// 贡献图样式的年度热力图：每列一周（周一在上），颜色按当年最大值分为 4 级
+pub fn year_heatmap(title: &str, heatmap: &YearHeatmap) -> Chart {
+    let (cell, gap, left, top) = (11.0, 2.0, 40.0, 64.0);
+    let offset = heatmap.first_weekday as usize - 1;
+    let columns = (offset + heatmap.minutes.len()).div_ceil(7);
+    let width = left + columns as f64 * (cell + gap) + 48.0;
+    let mut chart = Chart::new(width as u32, 200);
+    chart.text(24.0, 32.0, 18.0, title, TEXT_COLOR, Anchor::Start);
+
+    for row in [0, 2, 4] {
+        chart.text(left - 6.0, top + row as f64 * (cell + gap) + 9.0, 10.0, WEEKDAYS[row], MUTED_COLOR, Anchor::End);
+    }
+
+    let jan1 = NaiveDate::from_ymd_opt(heatmap.year, 1, 1).unwrap_or_default();
+    let max = heatmap.max_minutes.max(1) as f64;
+    for (i, minutes) in heatmap.minutes.iter().enumerate() {
+        let (column, row) = ((offset + i) / 7, (offset + i) % 7);
+        let level = if *minutes == 0 {
+            0
+        } else {
+            ((*minutes as f64 / max * 4.0).ceil() as usize).clamp(1, 4)
+        };
+        let x = left + column as f64 * (cell + gap);
+        chart.rect(x, top + row as f64 * (cell + gap), cell, cell, HEAT_COLORS[level]);
+
+        let date = jan1 + Duration::days(i as i64);
+        if date.day() == 1 {
+            chart.text(x, top - 8.0, 10.0, format!("{}月", date.month()), MUTED_COLOR, Anchor::Start);
+        }
+    }
+
+    let legend_y = top + 7.0 * (cell + gap) + 20.0;
+    chart.text(left, legend_y + 9.0, 10.0, format!("有工作 {} 天，单日最多 {}", heatmap.active_days, format_minutes(heatmap.max_minutes as f64)), MUTED_COLOR, Anchor::Start);
+    let legend_x = width - 48.0 - 5.0 * (cell + gap);
+    chart.text(legend_x - 6.0, legend_y + 9.0, 10.0, "少", MUTED_COLOR, Anchor::End);
+    for (i, color) in HEAT_COLORS.iter().enumerate() {
+        chart.rect(legend_x + i as f64 * (cell + gap), legend_y, cell, cell, color);
+    }
+    chart.text(width - 44.0, legend_y + 9.0, 10.0, "多", MUTED_COLOR, Anchor::Start);
+    chart
+}
+
+fn range_title(prefix: &str, first: NaiveDate, last: NaiveDate) -> String {
+    format!("{} {} ~ {}", prefix, first.format("%Y-%m-%d"), last.format("%Y-%m-%d"))
+}
+
+/// 从数据库读取 [start_time, end_time) 内的分类时长并绘制饼图
+pub fn category_pie_chart(db: &Database, user_id: i64, start_time: i64, end_time: i64, include_manual: bool) -> rusqlite::Result<Chart> {
+    let categories = db.get_custom_categories(user_id)?;
+    let durations = db.get_category_durations(user_id, start_time, end_time, include_manual)?;
+    let rollup = stats::category_rollup(&categories, &durations);
+    let title = range_title(
+        "分类工作时长",
+        time_utils::local_date(start_time),
+        time_utils::local_date(end_time - 1),
+    );
+    Ok(category_pie(&title, &rollup))
+}
+
+/// 从数据库读取 first..=last 每天的工作时长并绘制柱状图
+pub fn daily_bars_chart(db: &Database, user_id: i64, first: NaiveDate, last: NaiveDate, include_manual: bool) -> rusqlite::Result<Chart> {
+    let (start, end) = time_utils::padded_date_range(first, last);
+    let entries = db.get_work_entries(user_id, start, end, include_manual)?;
+    let minutes = stats::trends(&entries, &[], first, last).work_minutes;
+    let labels = day_labels(first, minutes.len());
+    Ok(daily_bars(&range_title("每日工作时长", first, last), &labels, &minutes))
+}
+
+/// 柱状图横轴标签，一周以内时带上星期几
+pub fn day_labels(first: NaiveDate, days: usize) -> Vec<String> {
+    (0..days)
+        .map(|i| {
+            let date = first + Duration::days(i as i64);
+            if days <= 7 {
+                format!("{} {}", WEEKDAYS[date.weekday().num_days_from_monday() as usize], date.format("%m-%d"))
+            } else {
+                date.format("%m-%d").to_string()
+            }
+        })
+        .collect()
+}
+
+/// 从数据库读取某一年每天的工作时长并绘制热力图
+pub fn year_heatmap_chart(db: &Database, user_id: i64, year: i32, include_manual: bool) -> rusqlite::Result<Chart> {
+    let first = NaiveDate::from_ymd_opt(year, 1, 1).unwrap_or_default();
+    let last = NaiveDate::from_ymd_opt(year, 12, 31).unwrap_or_default();
+    let (start, end) = time_utils::padded_date_range(first, last);
+    let entries = db.get_work_entries(user_id, start, end, include_manual)?;
+    let heatmap = stats::yearly_heatmap(&entries, year);
+    Ok(year_heatmap(&format!("{} 年工作热力图", year), &heatmap))
+}
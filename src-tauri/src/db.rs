@@ -3,6 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Mutex;
 
+use crate::time_utils;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct User {
     pub id: i64,
@@ -33,434 +35,3663 @@ pub struct TimerRecord {
     pub end_time: i64,
     pub duration: i64,
     pub created_at: i64,
+    /// 正计时过程中触发的提醒次数
+    #[serde(default)]
+    pub reminder_count: i64,
+    /// 每次提醒触发时的时间戳（毫秒）
+    #[serde(default)]
+    pub reminder_times: Vec<i64>,
+    /// 备注（Markdown）
+    #[serde(default)]
+    pub note: Option<String>,
+    /// 专注度评分 1-5
+    #[serde(default)]
+    pub focus_rating: Option<i64>,
+    /// 关联的任务
+    #[serde(default)]
+    pub task_id: Option<i64>,
+    /// 关联的项目
+    #[serde(default)]
+    pub project_id: Option<i64>,
+    /// 是否计费
+    #[serde(default)]
+    pub billable: bool,
+    /// 标签名称
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 记录来源：timer（计时产生）/ manual（手动补录）
+    #[serde(default = "default_record_source")]
+    pub source: String,
+    /// 记录时本机相对 UTC 的偏移（分钟），保存时为空则按本机时区补上
+    #[serde(default)]
+    pub tz_offset_minutes: Option<i32>,
+    /// 拆分出的后半段指向最初的记录；统计番茄数时只计最初的记录
+    #[serde(default)]
+    pub split_from: Option<String>,
+    /// 记录所属的日期（YYYY-MM-DD），按记录的时区和一天开始的时刻计算，只读
+    #[serde(default)]
+    pub day: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct CustomCategory {
-    pub id: i64,
-    pub user_id: i64,
-    pub value: String,
-    pub label: String,
-    pub icon: String,
-    pub created_at: i64,
+fn default_record_source() -> String {
+    "timer".to_string()
 }
 
-pub struct Database {
-    conn: Mutex<Connection>,
+/// 手动补录的一段时间，end_time 与 duration 二选一
+#[derive(Debug, Deserialize, Clone)]
+pub struct ManualEntry {
+    #[serde(default = "default_manual_mode")]
+    pub mode: String,
+    pub name: Option<String>,
+    pub category: Option<String>,
+    pub start_time: i64,
+    pub end_time: Option<i64>,
+    pub duration: Option<i64>,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub focus_rating: Option<i64>,
+    #[serde(default)]
+    pub task_id: Option<i64>,
+    #[serde(default)]
+    pub project_id: Option<i64>,
+    #[serde(default)]
+    pub billable: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 与已有记录时间重叠时仍然保存
+    #[serde(default)]
+    pub force: bool,
 }
 
-impl Database {
-    pub fn new(db_path: PathBuf) -> Result<Self> {
-        let conn = Connection::open(db_path)?;
-        let db = Database {
-            conn: Mutex::new(conn),
-        };
-        db.init_tables()?;
-        Ok(db)
+fn default_manual_mode() -> String {
+    "work".to_string()
+}
+
+/// 手动补录的结果；存在时间冲突且未强制保存时 record 为 None
+#[derive(Debug, Serialize, Clone)]
+pub struct ManualEntryResult {
+    pub record: Option<TimerRecord>,
+    pub conflicts: Vec<TimerRecord>,
+}
+
+/// 计时记录的可编辑字段，None 表示不修改；note / focus_rating 为 Some(None) 时清空
+#[derive(Debug, Default, Clone)]
+pub struct TimerRecordUpdate {
+    pub name: Option<String>,
+    pub category: Option<String>,
+    pub note: Option<Option<String>>,
+    pub focus_rating: Option<Option<i64>>,
+    pub task_id: Option<Option<i64>>,
+    pub project_id: Option<Option<i64>>,
+    pub billable: Option<bool>,
+    /// 新的 (start_time, end_time)，duration 随之重新计算
+    pub times: Option<(i64, i64)>,
+}
+
+/// 拆分记录时每一段的名称和分类，None 表示沿用原记录
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct RecordPart {
+    pub name: Option<String>,
+    pub category: Option<String>,
+}
+
+impl RecordPart {
+    fn apply(self, record: &mut TimerRecord) {
+        if let Some(name) = self.name {
+            record.name = Some(name);
+        }
+        if let Some(category) = self.category {
+            record.category = Some(category);
+        }
     }
+}
 
-    fn init_tables(&self) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+/// 修改计时记录的结果；存在时间冲突且未强制保存时 updated 为 false
+#[derive(Debug, Serialize, Clone)]
+pub struct TimerRecordUpdateResult {
+    pub updated: bool,
+    /// 与新时间段重叠的同类记录
+    pub conflicts: Vec<TimerRecord>,
+}
 
-        // 创建用户表
+/// 计时记录查询使用的统一列顺序，与 `map_timer_record` 对应
+const TIMER_RECORD_COLUMNS: &str =
+    "id, user_id, record_type, mode, name, category, start_time, end_time, duration, created_at, reminder_count, reminder_times, note, focus_rating, task_id, project_id, billable, source, tz_offset_minutes, split_from, 
+     (SELECT json_group_array(t.name) FROM record_tags rt JOIN tags t ON t.id = rt.tag_id WHERE rt.record_id = timer_records.id) AS tags";
+
+fn map_timer_record(row: &rusqlite::Row) -> Result<TimerRecord> {
+    let reminder_times: Option<String> = row.get(11)?;
+    let end_time: i64 = row.get(7)?;
+    let tz_offset_minutes: Option<i32> = row.get(18)?;
+    Ok(TimerRecord {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        record_type: row.get(2)?,
+        mode: row.get(3)?,
+        name: row.get(4)?,
+        category: row.get(5)?,
+        start_time: row.get(6)?,
+        end_time,
+        duration: row.get(8)?,
+        created_at: row.get(9)?,
+        reminder_count: row.get::<_, Option<i64>>(10)?.unwrap_or(0),
+        reminder_times: reminder_times
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        note: row.get(12)?,
+        focus_rating: row.get(13)?,
+        task_id: row.get(14)?,
+        project_id: row.get(15)?,
+        billable: row.get::<_, Option<bool>>(16)?.unwrap_or(false),
+        source: row.get::<_, Option<String>>(17)?.unwrap_or_else(default_record_source),
+        tags: row
+            .get::<_, Option<String>>(20)?
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        tz_offset_minutes,
+        split_from: row.get(19)?,
+        day: time_utils::record_day_key(end_time, tz_offset_minutes),
+    })
+}
+
+fn select_timer_record(conn: &Connection, user_id: i64, record_id: &str) -> Result<Option<TimerRecord>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM timer_records WHERE id = ?1 AND user_id = ?2 AND deleted_at IS NULL",
+        TIMER_RECORD_COLUMNS
+    ))?;
+
+    let mut rows = stmt.query_map(params![record_id, user_id], map_timer_record)?;
+    rows.next().transpose()
+}
+
+/// 分类查询使用的统一列顺序，与 `map_custom_category` 对应
+const CUSTOM_CATEGORY_COLUMNS: &str =
+    "id, user_id, value, label, icon, created_at, parent_value, color, sort_order, pinned, archived";
+
+fn map_custom_category(row: &rusqlite::Row) -> Result<CustomCategory> {
+    Ok(CustomCategory {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        value: row.get(2)?,
+        label: row.get(3)?,
+        icon: row.get(4)?,
+        created_at: row.get(5)?,
+        parent_value: row.get(6)?,
+        color: row.get(7)?,
+        sort_order: row.get(8)?,
+        pinned: row.get(9)?,
+        archived: row.get(10)?,
+    })
+}
+
+/// 写入一条计时记录及其标签，并关联期间发生的打断
+fn insert_timer_record(conn: &Connection, record: &TimerRecord) -> Result<()> {
+    let reminder_times = if record.reminder_times.is_empty() {
+        None
+    } else {
+        serde_json::to_string(&record.reminder_times).ok()
+    };
+
+    // 回收站中同 id 的记录（例如重新导入已删除的记录）直接清除，避免违反主键约束
+    purge_record(conn, record.user_id, &record.id)?;
+
+    conn.execute(
+        "INSERT INTO timer_records 
+         (id, user_id, record_type, mode, name, category, start_time, end_time, duration, created_at, reminder_count, reminder_times, note, focus_rating, task_id, project_id, billable, source, tz_offset_minutes, split_from) 
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20)",
+        params![
+            record.id,
+            record.user_id,
+            record.record_type,
+            record.mode,
+            record.name,
+            record.category,
+            record.start_time,
+            record.end_time,
+            record.duration,
+            record.created_at,
+            record.reminder_count,
+            reminder_times,
+            record.note,
+            record.focus_rating,
+            record.task_id,
+            record.project_id,
+            record.billable,
+            record.source,
+            record
+                .tz_offset_minutes
+                .unwrap_or_else(|| time_utils::local_offset_minutes(record.end_time)),
+            record.split_from,
+        ],
+    )?;
+
+    if !record.tags.is_empty() {
+        attach_tags(conn, record.user_id, &record.id, &record.tags, record.created_at)?;
+    }
+
+    // 将本次工作期间记录的打断关联到该记录（手动补录的记录不关联）
+    if record.mode.as_deref() == Some("work") && record.source != "manual" {
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS users (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                device_id TEXT UNIQUE NOT NULL,
-                phone TEXT,
-                created_at INTEGER NOT NULL,
-                updated_at INTEGER NOT NULL
-            )",
-            [],
+            "UPDATE interruptions SET record_id = ?1 
+             WHERE user_id = ?2 AND record_id IS NULL AND occurred_at >= ?3 AND occurred_at <= ?4",
+            params![record.id, record.user_id, record.start_time, record.end_time],
         )?;
+    }
 
-        // 创建设置表
+    Ok(())
+}
+
+/// 按名称为记录添加标签，不存在的标签会自动创建
+fn attach_tags(conn: &Connection, user_id: i64, record_id: &str, names: &[String], now: i64) -> Result<()> {
+    for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS settings (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                user_id INTEGER NOT NULL,
-                key TEXT NOT NULL,
-                value TEXT NOT NULL,
-                updated_at INTEGER NOT NULL,
-                FOREIGN KEY (user_id) REFERENCES users(id),
-                UNIQUE(user_id, key)
-            )",
-            [],
+            "INSERT OR IGNORE INTO tags (user_id, name, created_at) VALUES (?1, ?2, ?3)",
+            params![user_id, name, now],
         )?;
-
-        // 创建计时记录表
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS timer_records (
-                id TEXT PRIMARY KEY,
-                user_id INTEGER NOT NULL,
-                record_type TEXT NOT NULL,
-                mode TEXT,
-                name TEXT,
-                category TEXT,
-                start_time INTEGER NOT NULL,
-                end_time INTEGER NOT NULL,
-                duration INTEGER NOT NULL,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY (user_id) REFERENCES users(id)
-            )",
-            [],
+            "INSERT OR IGNORE INTO record_tags (record_id, tag_id) 
+             SELECT ?1, id FROM tags WHERE user_id = ?2 AND name = ?3",
+            params![record_id, user_id, name],
         )?;
+    }
+    Ok(())
+}
 
-        // 为已存在的表添加 category 列（如果不存在）
-        let _ = conn.execute(
-            "ALTER TABLE timer_records ADD COLUMN category TEXT",
-            [],
-        );
-
-        // 创建自定义分类表
+/// 把引用分类 from 的计时记录、任务、目标和子分类改为 to，返回受影响的记录数
+fn reassign_category(conn: &Connection, user_id: i64, from: &str, to: Option<&str>) -> Result<usize> {
+    let affected = conn.execute(
+        "UPDATE timer_records SET category = ?1 WHERE user_id = ?2 AND category = ?3",
+        params![to, user_id, from],
+    )?;
+    conn.execute(
+        "UPDATE tasks SET category = ?1 WHERE user_id = ?2 AND category = ?3",
+        params![to, user_id, from],
+    )?;
+    // 目标的分类为空表示统计所有分类，没有新分类时保持原值
+    if to.is_some() {
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS custom_categories (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                user_id INTEGER NOT NULL,
-                value TEXT NOT NULL,
-                label TEXT NOT NULL,
-                icon TEXT NOT NULL,
-                created_at INTEGER NOT NULL,
-                FOREIGN KEY (user_id) REFERENCES users(id),
-                UNIQUE(user_id, value)
-            )",
-            [],
+            "UPDATE goals SET category = ?1 WHERE user_id = ?2 AND category = ?3",
+            params![to, user_id, from],
         )?;
+    }
+    conn.execute(
+        "UPDATE custom_categories SET parent_value = ?1 WHERE user_id = ?2 AND parent_value = ?3",
+        params![to, user_id, from],
+    )?;
+    Ok(affected)
+}
 
-        // 创建索引
+/// 把 categoryReminderRules 中分类 from 的提醒规则移到 to 名下
+/// replace 为 false 时 to 已有的规则优先（合并），为 true 时覆盖（改名）
+fn move_category_reminder_rules(conn: &Connection, user_id: i64, from: &str, to: &str, replace: bool) -> Result<()> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM settings WHERE user_id = ?1 AND key = 'categoryReminderRules'",
+            params![user_id],
+            |row| row.get(0),
+        )
+        .map(Some)
+        .or_else(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Ok(None),
+            e => Err(e),
+        })?;
+    let Some(serde_json::Value::Object(mut rules)) = value.and_then(|v| serde_json::from_str(&v).ok()) else {
+        return Ok(());
+    };
+    let Some(moved) = rules.remove(from) else {
+        return Ok(());
+    };
+    if replace || !rules.contains_key(to) {
+        rules.insert(to.to_string(), moved);
+    }
+    conn.execute(
+        "UPDATE settings SET value = ?1, updated_at = ?2 WHERE user_id = ?3 AND key = 'categoryReminderRules'",
+        params![serde_json::Value::Object(rules).to_string(), time_utils::now_ms(), user_id],
+    )?;
+    Ok(())
+}
+
+/// 彻底删除回收站中的一条记录及其标签关联
+fn purge_record(conn: &Connection, user_id: i64, record_id: &str) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM record_tags WHERE record_id IN 
+         (SELECT id FROM timer_records WHERE id = ?1 AND user_id = ?2 AND deleted_at IS NOT NULL)",
+        params![record_id, user_id],
+    )?;
+    conn.execute(
+        "DELETE FROM timer_records WHERE id = ?1 AND user_id = ?2 AND deleted_at IS NOT NULL",
+        params![record_id, user_id],
+    )
+}
+
+/// 彻底删除回收站中的分类，引用它的记录变为未分类
+fn purge_category(conn: &Connection, user_id: i64, value: &str) -> Result<usize> {
+    let purged = conn.execute(
+        "DELETE FROM custom_categories WHERE user_id = ?1 AND value = ?2 AND deleted_at IS NOT NULL",
+        params![user_id, value],
+    )?;
+    if purged > 0 {
+        reassign_category(conn, user_id, value, None)?;
+    }
+    Ok(purged)
+}
+
+/// 为 AUDIT_TABLES 创建临时触发器，把每次增删改连同新旧值写入 audit_log
+/// 触发命令从临时表 audit_context 读取，由 Database::audit_scope 设置
+fn create_audit_triggers(conn: &Connection) -> Result<()> {
+    conn.execute(
+        "CREATE TEMP TABLE IF NOT EXISTS audit_context (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            command TEXT
+        )",
+        [],
+    )?;
+
+    for (table, key) in AUDIT_TABLES {
+        let columns: Vec<String> = {
+            let mut stmt = conn.prepare(&format!("PRAGMA main.table_info({})", table))?;
+            let columns = stmt
+                .query_map([], |row| row.get(1))?
+                .collect::<Result<Vec<_>>>()?;
+            columns
+        };
+        let json_of = |alias: &str| {
+            let pairs: Vec<String> = columns
+                .iter()
+                .map(|c| format!("'{}', {}.{}", c, alias, c))
+                .collect();
+            format!("json_object({})", pairs.join(", "))
+        };
+        let insert_log = |row: &str, action: &str, old_values: &str, new_values: &str| {
+            format!(
+                "INSERT INTO audit_log (user_id, table_name, row_key, action, old_values, new_values, command, created_at) 
+                 VALUES ({row}.user_id, '{table}', CAST({row}.{key} AS TEXT), '{action}', {old_values}, {new_values}, 
+                         COALESCE((SELECT command FROM audit_context WHERE id = 1), 'system'), 
+                         CAST((julianday('now') - 2440587.5) * 86400000 AS INTEGER));"
+            )
+        };
+        let (old_json, new_json) = (json_of("OLD"), json_of("NEW"));
+
+        // 表结构可能因迁移而变化，先删除旧触发器再创建
+        for action in ["insert", "update", "delete"] {
+            conn.execute(&format!("DROP TRIGGER IF EXISTS temp.audit_{}_{}", table, action), [])?;
+        }
         conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_timer_records_user_id 
-             ON timer_records(user_id)",
+            &format!(
+                "CREATE TEMP TRIGGER audit_{table}_insert AFTER INSERT ON main.{table} BEGIN {} END",
+                insert_log("NEW", "insert", "NULL", &new_json)
+            ),
             [],
         )?;
-
         conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_timer_records_end_time 
-             ON timer_records(end_time DESC)",
+            &format!(
+                "CREATE TEMP TRIGGER audit_{table}_update AFTER UPDATE ON main.{table} 
+                 WHEN {old_json} IS NOT {new_json} BEGIN {} END",
+                insert_log("NEW", "update", &old_json, &new_json)
+            ),
             [],
         )?;
-
         conn.execute(
-            "CREATE INDEX IF NOT EXISTS idx_custom_categories_user_id 
-             ON custom_categories(user_id)",
+            &format!(
+                "CREATE TEMP TRIGGER audit_{table}_delete AFTER DELETE ON main.{table} BEGIN {} END",
+                insert_log("OLD", "delete", &old_json, "NULL")
+            ),
             [],
         )?;
+    }
 
-        println!("✓ Database tables initialized");
-        Ok(())
+    Ok(())
+}
+
+/// 在 text 中找到 term（不区分大小写）并截取前后 context 个字符，匹配内容加上高亮标记
+fn highlight_snippet(text: &str, term: &str, context: usize) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().flat_map(|c| c.to_lowercase()).collect();
+    let needle: Vec<char> = term.chars().flat_map(|c| c.to_lowercase()).collect();
+    // 小写后长度变化的字符很少见，此时无法对应原文位置，直接放弃高亮
+    if needle.is_empty() || lower.len() != chars.len() {
+        return None;
     }
+    let pos = lower.windows(needle.len()).position(|w| w == needle.as_slice())?;
+    let start = pos.saturating_sub(context);
+    let end = (pos + needle.len() + context).min(chars.len());
 
-    // 用户相关操作
-    pub fn get_or_create_user(&self, device_id: &str) -> Result<User> {
-        let conn = self.conn.lock().unwrap();
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    snippet.extend(&chars[start..pos]);
+    snippet.push_str(SEARCH_HIGHLIGHT.0);
+    snippet.extend(&chars[pos..pos + needle.len()]);
+    snippet.push_str(SEARCH_HIGHLIGHT.1);
+    snippet.extend(&chars[pos + needle.len()..end]);
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
 
-        // 尝试查找现有用户
-        let mut stmt = conn.prepare("SELECT id, device_id, phone, created_at, updated_at FROM users WHERE device_id = ?1")?;
-        let user_result = stmt.query_row(params![device_id], |row| {
-            Ok(User {
-                id: row.get(0)?,
-                device_id: row.get(1)?,
-                phone: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-            })
-        });
+/// 校验撤销日志中的表名和列名，返回该表是否按 user_id 隔离
+fn undo_scope(table: &str, key: &str) -> Result<bool> {
+    let valid_key = !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    UNDO_TABLES
+        .iter()
+        .find(|(name, _)| *name == table)
+        .filter(|_| valid_key)
+        .map(|(_, user_scoped)| *user_scoped)
+        .ok_or_else(|| rusqlite::Error::InvalidParameterName(format!("{}.{}", table, key)))
+}
+
+fn json_to_sql(value: &serde_json::Value) -> rusqlite::types::Value {
+    use rusqlite::types::Value;
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => Value::Integer(*b as i64),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => Value::Real(n.as_f64().unwrap_or(0.0)),
+        },
+        serde_json::Value::String(s) => Value::Text(s.clone()),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+/// 读取 table 中 key 列取值为 ids 的所有行
+fn select_undo_rows(
+    conn: &Connection,
+    user_id: i64,
+    table: &str,
+    key: &str,
+    ids: &[serde_json::Value],
+) -> Result<Vec<serde_json::Map<String, serde_json::Value>>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let user_scoped = undo_scope(table, key)?;
+
+    let mut params_list: Vec<rusqlite::types::Value> = ids.iter().map(json_to_sql).collect();
+    let mut sql = format!(
+        "SELECT * FROM {} WHERE {} IN ({})",
+        table,
+        key,
+        vec!["?"; ids.len()].join(", ")
+    );
+    if user_scoped {
+        sql.push_str(" AND user_id = ?");
+        params_list.push(rusqlite::types::Value::Integer(user_id));
+    }
+
+    let mut stmt = conn.prepare(&sql)?;
+    let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(params_list), |row| {
+            let mut map = serde_json::Map::new();
+            for (i, column) in columns.iter().enumerate() {
+                let value = match row.get_ref(i)? {
+                    rusqlite::types::ValueRef::Integer(v) => serde_json::Value::from(v),
+                    rusqlite::types::ValueRef::Real(v) => serde_json::Value::from(v),
+                    rusqlite::types::ValueRef::Text(v) => {
+                        serde_json::Value::from(String::from_utf8_lossy(v).into_owned())
+                    }
+                    _ => serde_json::Value::Null,
+                };
+                map.insert(column.clone(), value);
+            }
+            Ok(map)
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(rows)
+}
+
+/// 两组快照是否包含相同的行（不考虑顺序）
+fn same_undo_rows(
+    a: &[serde_json::Map<String, serde_json::Value>],
+    b: &[serde_json::Map<String, serde_json::Value>],
+) -> bool {
+    let normalize = |rows: &[serde_json::Map<String, serde_json::Value>]| {
+        let mut rows: Vec<String> = rows
+            .iter()
+            .map(|row| serde_json::Value::Object(row.clone()).to_string())
+            .collect();
+        rows.sort();
+        rows
+    };
+    a.len() == b.len() && normalize(a) == normalize(b)
+}
+
+/// 把 key 列取值为 ids 的行恢复为 rows
+fn write_undo_rows(
+    conn: &Connection,
+    user_id: i64,
+    change: &UndoChange,
+    rows: &[serde_json::Map<String, serde_json::Value>],
+) -> Result<()> {
+    if change.ids.is_empty() {
+        return Ok(());
+    }
+    let user_scoped = undo_scope(&change.table, &change.key)?;
+
+    let mut params_list: Vec<rusqlite::types::Value> = change.ids.iter().map(json_to_sql).collect();
+    let mut sql = format!(
+        "DELETE FROM {} WHERE {} IN ({})",
+        change.table,
+        change.key,
+        vec!["?"; change.ids.len()].join(", ")
+    );
+    if user_scoped {
+        sql.push_str(" AND user_id = ?");
+        params_list.push(rusqlite::types::Value::Integer(user_id));
+    }
+    conn.execute(&sql, rusqlite::params_from_iter(params_list))?;
+
+    for row in rows {
+        if user_scoped && row.get("user_id").and_then(|v| v.as_i64()) != Some(user_id) {
+            continue;
+        }
+        let columns: Vec<&String> = row.keys().collect();
+        if let Some(column) = columns.iter().find(|c| !c.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_')) {
+            return Err(rusqlite::Error::InvalidColumnName(column.to_string()));
+        }
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            change.table,
+            columns.iter().map(|c| c.as_str()).collect::<Vec<_>>().join(", "),
+            vec!["?"; columns.len()].join(", ")
+        );
+        conn.execute(&sql, rusqlite::params_from_iter(row.values().map(json_to_sql)))?;
+    }
+
+    Ok(())
+}
+
+/// 计时记录查询条件，所有字段均可选
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordFilter {
+    /// 结束时间下限（含）
+    pub start_time: Option<i64>,
+    /// 结束时间上限（不含）
+    pub end_time: Option<i64>,
+    pub category: Option<String>,
+    pub record_type: Option<String>,
+    pub mode: Option<String>,
+    /// 名称模糊匹配
+    pub name_pattern: Option<String>,
+    pub tag_ids: Vec<i64>,
+    /// true 时需包含全部标签，否则包含任一标签即可
+    pub match_all_tags: bool,
+    /// timer / manual
+    pub source: Option<String>,
+}
+
+impl RecordFilter {
+    /// 生成针对 timer_records 的 WHERE 子句及参数
+    fn where_clause(&self, user_id: i64) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+        let mut conditions = vec!["user_id = ?".to_string(), "deleted_at IS NULL".to_string()];
+        let mut params_list: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(user_id)];
+
+        if let Some(start) = self.start_time {
+            conditions.push("end_time >= ?".to_string());
+            params_list.push(Box::new(start));
+        }
+        if let Some(end) = self.end_time {
+            conditions.push("end_time < ?".to_string());
+            params_list.push(Box::new(end));
+        }
+        if let Some(category) = &self.category {
+            conditions.push("category = ?".to_string());
+            params_list.push(Box::new(category.clone()));
+        }
+        if let Some(record_type) = &self.record_type {
+            conditions.push("record_type = ?".to_string());
+            params_list.push(Box::new(record_type.clone()));
+        }
+        if let Some(mode) = &self.mode {
+            conditions.push("mode = ?".to_string());
+            params_list.push(Box::new(mode.clone()));
+        }
+        if let Some(source) = &self.source {
+            conditions.push("source = ?".to_string());
+            params_list.push(Box::new(source.clone()));
+        }
+        if let Some(pattern) = self.name_pattern.as_deref().filter(|p| !p.is_empty()) {
+            conditions.push("name LIKE ? ESCAPE '\\'".to_string());
+            let escaped = pattern.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            params_list.push(Box::new(format!("%{}%", escaped)));
+        }
+        if !self.tag_ids.is_empty() {
+            let placeholders = vec!["?"; self.tag_ids.len()].join(", ");
+            if self.match_all_tags {
+                conditions.push(format!(
+                    "id IN (SELECT record_id FROM record_tags WHERE tag_id IN ({}) GROUP BY record_id HAVING COUNT(DISTINCT tag_id) = ?)",
+                    placeholders
+                ));
+            } else {
+                conditions.push(format!(
+                    "id IN (SELECT record_id FROM record_tags WHERE tag_id IN ({}))",
+                    placeholders
+                ));
+            }
+            for tag_id in &self.tag_ids {
+                params_list.push(Box::new(*tag_id));
+            }
+            if self.match_all_tags {
+                let mut unique = self.tag_ids.clone();
+                unique.sort_unstable();
+                unique.dedup();
+                params_list.push(Box::new(unique.len() as i64));
+            }
+        }
+
+        (conditions.join(" AND "), params_list)
+    }
+}
+
+/// 记录的标签名（空格分隔），用于全文索引
+fn record_tag_names(record_id: &str) -> String {
+    format!(
+        "(SELECT group_concat(t.name, ' ') FROM record_tags rt JOIN tags t ON t.id = rt.tag_id WHERE rt.record_id = {})",
+        record_id
+    )
+}
+
+/// 全文搜索中高亮匹配内容的标记
+const SEARCH_HIGHLIGHT: (&str, &str) = ("<mark>", "</mark>");
+
+/// 全文搜索的一条结果
+#[derive(Debug, Serialize, Clone)]
+pub struct RecordSearchHit {
+    pub record: TimerRecord,
+    /// 匹配位置附近的片段，匹配内容用 <mark></mark> 包裹（原文未转义）
+    pub snippet: String,
+    /// 相关度，越小越相关；只含短关键词时按时间排序，为 0
+    pub rank: f64,
+}
+
+/// 写入审计日志的表及其行标识列
+const AUDIT_TABLES: &[(&str, &str)] = &[
+    ("timer_records", "id"),
+    ("settings", "key"),
+    ("custom_categories", "value"),
+];
+
+/// 审计命令的作用域，见 Database::audit_scope
+pub struct AuditScope<'a> {
+    db: &'a Database,
+}
+
+impl Drop for AuditScope<'_> {
+    fn drop(&mut self) {
+        let _ = self.db.set_audit_command(None);
+    }
+}
+
+/// 审计日志中的一条变更
+#[derive(Debug, Serialize, Clone)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub table_name: String,
+    pub row_key: String,
+    /// insert / update / delete
+    pub action: String,
+    pub old_values: Option<serde_json::Value>,
+    pub new_values: Option<serde_json::Value>,
+    /// 触发变更的命令，后台任务产生的变更为 "system"
+    pub command: String,
+    pub created_at: i64,
+}
+
+/// 可撤销的表及其是否按 user_id 隔离
+const UNDO_TABLES: &[(&str, bool)] = &[
+    ("timer_records", true),
+    ("record_tags", false),
+    ("custom_categories", true),
+    ("tasks", true),
+    ("goals", true),
+    ("settings", true),
+];
+
+/// 一张表中受影响的行：按 key 列取值为 ids 的范围，保存修改前后的完整行
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UndoChange {
+    pub table: String,
+    pub key: String,
+    pub ids: Vec<serde_json::Value>,
+    pub before: Vec<serde_json::Map<String, serde_json::Value>>,
+    pub after: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// 撤销 / 重做后返回的操作说明
+#[derive(Debug, Serialize, Clone)]
+pub struct UndoEntry {
+    pub id: i64,
+    pub label: String,
+    pub created_at: i64,
+    /// 受影响的表，便于前端刷新对应数据
+    pub tables: Vec<String>,
+}
+
+/// 撤销 / 重做的结果
+#[derive(Debug, Clone)]
+pub enum UndoOutcome {
+    Applied(UndoEntry),
+    /// 受影响的行在这次操作之后又被修改过，恢复会覆盖之后的修改；该条记录已被丢弃
+    Stale(String),
+}
+
+/// 当前可撤销 / 可重做的操作名称
+#[derive(Debug, Serialize, Clone)]
+pub struct UndoState {
+    pub undo: Option<String>,
+    pub redo: Option<String>,
+}
+
+/// 回收站中的记录
+#[derive(Debug, Serialize, Clone)]
+pub struct TrashedRecord {
+    pub record: TimerRecord,
+    pub deleted_at: i64,
+}
+
+/// 回收站中的分类
+#[derive(Debug, Serialize, Clone)]
+pub struct TrashedCategory {
+    pub category: CustomCategory,
+    pub deleted_at: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Trash {
+    pub records: Vec<TrashedRecord>,
+    pub categories: Vec<TrashedCategory>,
+}
+
+/// 回收站中要恢复或清除的条目
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct TrashSelection {
+    pub record_ids: Vec<String>,
+    pub category_values: Vec<String>,
+}
+
+/// 按查询条件批量修改记录的操作
+#[derive(Debug, Deserialize, Clone)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum BulkOperation {
+    /// 修改分类，None 表示设为未分类
+    Recategorize { category: Option<String> },
+    Rename { name: Option<String> },
+    /// 添加或移除标签
+    Tag {
+        #[serde(default)]
+        add: Vec<i64>,
+        #[serde(default)]
+        remove: Vec<i64>,
+    },
+    Delete,
+}
+
+/// 标签
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Tag {
+    pub id: i64,
+    pub user_id: i64,
+    pub name: String,
+    pub color: Option<String>,
+    pub created_at: i64,
+}
+
+/// 某个标签下的工作时长汇总
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagStat {
+    pub tag_id: i64,
+    pub name: String,
+    pub color: Option<String>,
+    pub session_count: i64,
+    pub total_work_ms: i64,
+}
+
+/// 项目（归属于某个客户，按小时计费）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Project {
+    #[serde(default)]
+    pub id: i64,
+    #[serde(default)]
+    pub user_id: i64,
+    pub name: String,
+    pub client: Option<String>,
+    /// 每小时费率
+    pub hourly_rate: f64,
+    /// 币种代码，例如 CNY / USD
+    pub currency: String,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+fn map_project(row: &rusqlite::Row) -> Result<Project> {
+    Ok(Project {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        name: row.get(2)?,
+        client: row.get(3)?,
+        hourly_rate: row.get(4)?,
+        currency: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+/// 一条计费工作记录及其项目信息
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BillableEntry {
+    pub record_id: String,
+    pub project_id: i64,
+    pub project_name: String,
+    pub client: Option<String>,
+    pub hourly_rate: f64,
+    pub currency: String,
+    pub start_time: i64,
+    pub duration: i64,
+}
+
+/// 任务
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Task {
+    #[serde(default)]
+    pub id: i64,
+    #[serde(default)]
+    pub user_id: i64,
+    pub title: String,
+    pub category: Option<String>,
+    /// 预估番茄数
+    pub estimated_pomodoros: i64,
+    /// todo / doing / done
+    pub status: String,
+    /// 截止日期（毫秒时间戳）
+    pub due_date: Option<i64>,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+/// 任务的预估与实际投入对比
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TaskProgress {
+    pub task: Task,
+    /// 完成的倒计时工作轮数
+    pub completed_pomodoros: i64,
+    pub session_count: i64,
+    pub total_work_ms: i64,
+}
+
+fn map_task(row: &rusqlite::Row) -> Result<Task> {
+    Ok(Task {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        title: row.get(2)?,
+        category: row.get(3)?,
+        estimated_pomodoros: row.get(4)?,
+        status: row.get(5)?,
+        due_date: row.get(6)?,
+        created_at: row.get(7)?,
+        updated_at: row.get(8)?,
+    })
+}
+
+/// 每日 / 每周目标
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Goal {
+    #[serde(default)]
+    pub id: i64,
+    #[serde(default)]
+    pub user_id: i64,
+    pub title: String,
+    /// 统计的分类，None 表示所有分类（仅对工作类指标生效）
+    pub category: Option<String>,
+    /// work_minutes / work_sessions / break_count
+    pub metric: String,
+    /// daily / weekly
+    pub period: String,
+    pub target: f64,
+    /// 生效的星期（1 = 周一 … 7 = 周日），为空表示每天；仅对每日目标生效
+    #[serde(default)]
+    pub weekdays: Vec<u32>,
+    /// 只统计在该本地时间（HH:MM）之前结束的记录，例如「午饭前」
+    #[serde(default)]
+    pub before_time: Option<String>,
+    #[serde(default = "default_true")]
+    pub active: bool,
+    #[serde(default)]
+    pub created_at: i64,
+    #[serde(default)]
+    pub updated_at: i64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn map_goal(row: &rusqlite::Row) -> Result<Goal> {
+    let weekdays: Option<String> = row.get(7)?;
+    Ok(Goal {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        title: row.get(2)?,
+        category: row.get(3)?,
+        metric: row.get(4)?,
+        period: row.get(5)?,
+        target: row.get(6)?,
+        weekdays: weekdays
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default(),
+        before_time: row.get(8)?,
+        active: row.get(9)?,
+        created_at: row.get(10)?,
+        updated_at: row.get(11)?,
+    })
+}
+
+const GOAL_COLUMNS: &str =
+    "id, user_id, title, category, metric, period, target, weekdays, before_time, active, created_at, updated_at";
+
+/// 目标在某个周期内的进度
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GoalProgress {
+    #[serde(default)]
+    pub id: i64,
+    pub goal_id: i64,
+    pub user_id: i64,
+    /// 周期键：每日目标为 YYYY-MM-DD，每周目标为该周周一
+    pub period_key: String,
+    pub period_start: i64,
+    pub period_end: i64,
+    pub value: f64,
+    pub target: f64,
+    /// 首次达成的时间
+    pub achieved_at: Option<i64>,
+    pub updated_at: i64,
+}
+
+fn map_goal_progress(row: &rusqlite::Row) -> Result<GoalProgress> {
+    Ok(GoalProgress {
+        id: row.get(0)?,
+        goal_id: row.get(1)?,
+        user_id: row.get(2)?,
+        period_key: row.get(3)?,
+        period_start: row.get(4)?,
+        period_end: row.get(5)?,
+        value: row.get(6)?,
+        target: row.get(7)?,
+        achieved_at: row.get(8)?,
+        updated_at: row.get(9)?,
+    })
+}
+
+const GOAL_PROGRESS_COLUMNS: &str =
+    "id, goal_id, user_id, period_key, period_start, period_end, value, target, achieved_at, updated_at";
+
+/// 已解锁的成就
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Achievement {
+    pub id: i64,
+    pub user_id: i64,
+    /// 成就定义的键，例如 pomodoros_100
+    pub key: String,
+    /// 解锁时对应指标的数值
+    pub value: f64,
+    pub unlocked_at: i64,
+}
+
+/// 用于长期趋势统计的一条工作记录
+#[derive(Debug, Clone)]
+pub struct WorkEntry {
+    pub end_time: i64,
+    pub tz_offset_minutes: Option<i32>,
+    pub duration: i64,
+    pub category: Option<String>,
+}
+
+/// 一条带专注度评分的工作记录
+#[derive(Debug, Clone)]
+pub struct FocusRating {
+    pub category: Option<String>,
+    pub start_time: i64,
+    pub tz_offset_minutes: Option<i32>,
+    pub rating: i64,
+}
+
+/// 累计工作量
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LifetimeTotals {
+    /// 完成的倒计时工作轮数
+    pub pomodoros: i64,
+    pub work_sessions: i64,
+    pub total_work_ms: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CustomCategory {
+    pub id: i64,
+    pub user_id: i64,
+    pub value: String,
+    pub label: String,
+    pub icon: String,
+    pub created_at: i64,
+    /// 父分类的 value，None 表示顶级分类
+    #[serde(default)]
+    pub parent_value: Option<String>,
+    /// 图表颜色（#RRGGBB）
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub sort_order: i64,
+    #[serde(default)]
+    pub pinned: bool,
+    /// 归档后不再出现在分类选择中，但保留在历史统计里
+    #[serde(default)]
+    pub archived: bool,
+}
+
+/// 分类的层级与展示属性，None 表示不修改；parent_value / color 为 Some(None) 时清空
+#[derive(Debug, Default, Clone)]
+pub struct CategoryMetaUpdate {
+    pub parent_value: Option<Option<String>>,
+    pub color: Option<Option<String>>,
+    pub pinned: Option<bool>,
+    pub archived: Option<bool>,
+}
+
+/// 休息执行情况记录
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BreakLog {
+    #[serde(default)]
+    pub id: i64,
+    #[serde(default)]
+    pub user_id: i64,
+    /// 计时模式：countdown / stopwatch
+    pub timer_mode: String,
+    pub planned_ms: i64,
+    pub actual_ms: i64,
+    /// 超出计划时长的部分（对应前端的 breakElapsedMs 超时）
+    pub overtime_ms: i64,
+    /// completed / cut_short / skipped / snoozed
+    pub outcome: String,
+    pub start_time: i64,
+    pub end_time: i64,
+    /// 记录时本机相对 UTC 的偏移（分钟），保存时为空则按本机时区补上
+    #[serde(default)]
+    pub tz_offset_minutes: Option<i32>,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+/// 工作过程中的一次打断
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Interruption {
+    pub id: i64,
+    pub user_id: i64,
+    /// 所属工作记录，工作尚未结束时为 None，保存记录时自动关联
+    pub record_id: Option<String>,
+    /// internal（内部，自己分心）/ external（外部，他人打扰）
+    pub kind: String,
+    pub reason: Option<String>,
+    pub occurred_at: i64,
+    pub created_at: i64,
+}
+
+/// 单次工作中的打断次数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionInterruptions {
+    pub record_id: String,
+    pub name: Option<String>,
+    pub category: Option<String>,
+    pub start_time: i64,
+    pub duration: i64,
+    pub internal: i64,
+    pub external: i64,
+}
+
+/// 某分类下某个打断原因的出现次数
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InterruptionReasonCount {
+    pub category: Option<String>,
+    pub reason: String,
+    pub count: i64,
+}
+
+pub struct Database {
+    conn: Mutex<Connection>,
+}
+
+impl Database {
+    pub fn new(db_path: PathBuf) -> Result<Self> {
+        let conn = Connection::open(db_path)?;
+        let db = Database {
+            conn: Mutex::new(conn),
+        };
+        db.init_tables()?;
+        Ok(db)
+    }
+
+    fn init_tables(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        // 创建用户表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS users (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                device_id TEXT UNIQUE NOT NULL,
+                phone TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // 创建设置表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS settings (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at INTEGER NOT NULL,
+                FOREIGN KEY (user_id) REFERENCES users(id),
+                UNIQUE(user_id, key)
+            )",
+            [],
+        )?;
+
+        // 创建计时记录表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS timer_records (
+                id TEXT PRIMARY KEY,
+                user_id INTEGER NOT NULL,
+                record_type TEXT NOT NULL,
+                mode TEXT,
+                name TEXT,
+                category TEXT,
+                start_time INTEGER NOT NULL,
+                end_time INTEGER NOT NULL,
+                duration INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY (user_id) REFERENCES users(id)
+            )",
+            [],
+        )?;
+
+        // 创建任务表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS tasks (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                category TEXT,
+                estimated_pomodoros INTEGER NOT NULL DEFAULT 0,
+                status TEXT NOT NULL DEFAULT 'todo',
+                due_date INTEGER,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                FOREIGN KEY (user_id) REFERENCES users(id)
+            )",
+            [],
+        )?;
+
+        // 创建项目表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS projects (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                client TEXT,
+                hourly_rate REAL NOT NULL DEFAULT 0,
+                currency TEXT NOT NULL DEFAULT 'CNY',
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                FOREIGN KEY (user_id) REFERENCES users(id)
+            )",
+            [],
+        )?;
+
+        // 创建目标表与目标进度表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS goals (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                category TEXT,
+                metric TEXT NOT NULL,
+                period TEXT NOT NULL,
+                target REAL NOT NULL,
+                weekdays TEXT,
+                before_time TEXT,
+                active INTEGER NOT NULL DEFAULT 1,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL,
+                FOREIGN KEY (user_id) REFERENCES users(id)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS goal_progress (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                goal_id INTEGER NOT NULL,
+                user_id INTEGER NOT NULL,
+                period_key TEXT NOT NULL,
+                period_start INTEGER NOT NULL,
+                period_end INTEGER NOT NULL,
+                value REAL NOT NULL DEFAULT 0,
+                target REAL NOT NULL,
+                achieved_at INTEGER,
+                updated_at INTEGER NOT NULL,
+                UNIQUE(goal_id, period_key),
+                FOREIGN KEY (goal_id) REFERENCES goals(id),
+                FOREIGN KEY (user_id) REFERENCES users(id)
+            )",
+            [],
+        )?;
+
+        // 创建成就表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS achievements (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                key TEXT NOT NULL,
+                value REAL NOT NULL DEFAULT 0,
+                unlocked_at INTEGER NOT NULL,
+                UNIQUE(user_id, key),
+                FOREIGN KEY (user_id) REFERENCES users(id)
+            )",
+            [],
+        )?;
+
+        // 创建标签表与记录-标签关联表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS tags (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                color TEXT,
+                created_at INTEGER NOT NULL,
+                UNIQUE(user_id, name),
+                FOREIGN KEY (user_id) REFERENCES users(id)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS record_tags (
+                record_id TEXT NOT NULL,
+                tag_id INTEGER NOT NULL,
+                PRIMARY KEY (record_id, tag_id),
+                FOREIGN KEY (record_id) REFERENCES timer_records(id),
+                FOREIGN KEY (tag_id) REFERENCES tags(id)
+            )",
+            [],
+        )?;
+
+        // 创建撤销日志表，changes 保存受影响行修改前后的快照（JSON）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS undo_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                label TEXT NOT NULL,
+                changes TEXT NOT NULL,
+                undone INTEGER NOT NULL DEFAULT 0,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY (user_id) REFERENCES users(id)
+            )",
+            [],
+        )?;
+
+        // 为已存在的表添加 category 列（如果不存在）
+        let _ = conn.execute(
+            "ALTER TABLE timer_records ADD COLUMN category TEXT",
+            [],
+        );
+
+        // 正计时重复提醒：记录提醒次数和每次提醒的时间
+        let _ = conn.execute(
+            "ALTER TABLE timer_records ADD COLUMN reminder_count INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE timer_records ADD COLUMN reminder_times TEXT",
+            [],
+        );
+
+        // 备注与专注度评分
+        let _ = conn.execute(
+            "ALTER TABLE timer_records ADD COLUMN note TEXT",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE timer_records ADD COLUMN focus_rating INTEGER",
+            [],
+        );
+
+        // 关联任务
+        let _ = conn.execute(
+            "ALTER TABLE timer_records ADD COLUMN task_id INTEGER REFERENCES tasks(id)",
+            [],
+        );
+
+        // 关联项目与计费标记
+        let _ = conn.execute(
+            "ALTER TABLE timer_records ADD COLUMN project_id INTEGER REFERENCES projects(id)",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE timer_records ADD COLUMN billable INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+
+        // 记录来源（计时 / 手动补录）
+        let _ = conn.execute(
+            "ALTER TABLE timer_records ADD COLUMN source TEXT NOT NULL DEFAULT 'timer'",
+            [],
+        );
+
+        // 记录时的时区偏移（旧记录为空，按本机时区计算日期）
+        let _ = conn.execute(
+            "ALTER TABLE timer_records ADD COLUMN tz_offset_minutes INTEGER",
+            [],
+        );
+
+        // 拆分出的记录指向最初的记录
+        let _ = conn.execute(
+            "ALTER TABLE timer_records ADD COLUMN split_from TEXT",
+            [],
+        );
+
+        // 回收站：删除时间不为空的记录视为已删除
+        let _ = conn.execute(
+            "ALTER TABLE timer_records ADD COLUMN deleted_at INTEGER",
+            [],
+        );
+
+        // 创建自定义分类表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS custom_categories (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                value TEXT NOT NULL,
+                label TEXT NOT NULL,
+                icon TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY (user_id) REFERENCES users(id),
+                UNIQUE(user_id, value)
+            )",
+            [],
+        )?;
+
+        // 创建休息执行记录表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS break_logs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                timer_mode TEXT NOT NULL,
+                planned_ms INTEGER NOT NULL,
+                actual_ms INTEGER NOT NULL,
+                overtime_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL,
+                start_time INTEGER NOT NULL,
+                end_time INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY (user_id) REFERENCES users(id)
+            )",
+            [],
+        )?;
+
+        // 休息记录时的时区偏移（旧记录为空，按本机时区计算日期）
+        let _ = conn.execute(
+            "ALTER TABLE break_logs ADD COLUMN tz_offset_minutes INTEGER",
+            [],
+        );
+
+        // 创建打断记录表
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS interruptions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                record_id TEXT,
+                kind TEXT NOT NULL,
+                reason TEXT,
+                occurred_at INTEGER NOT NULL,
+                created_at INTEGER NOT NULL,
+                FOREIGN KEY (user_id) REFERENCES users(id)
+            )",
+            [],
+        )?;
+
+        // 创建索引
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_timer_records_user_id 
+             ON timer_records(user_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_timer_records_end_time 
+             ON timer_records(end_time DESC)",
+            [],
+        )?;
+
+        // 分类层级、颜色、排序、置顶与归档
+        let _ = conn.execute(
+            "ALTER TABLE custom_categories ADD COLUMN parent_value TEXT",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE custom_categories ADD COLUMN color TEXT",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE custom_categories ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE custom_categories ADD COLUMN pinned INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE custom_categories ADD COLUMN archived INTEGER NOT NULL DEFAULT 0",
+            [],
+        );
+        let _ = conn.execute(
+            "ALTER TABLE custom_categories ADD COLUMN deleted_at INTEGER",
+            [],
+        );
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_custom_categories_user_id 
+             ON custom_categories(user_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_break_logs_user_end 
+             ON break_logs(user_id, end_time)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_interruptions_record_id 
+             ON interruptions(record_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_timer_records_task_id 
+             ON timer_records(task_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_tasks_user_id 
+             ON tasks(user_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_timer_records_project_id 
+             ON timer_records(project_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_projects_user_id 
+             ON projects(user_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_record_tags_tag_id 
+             ON record_tags(tag_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_goal_progress_user_start 
+             ON goal_progress(user_id, period_start)",
+            [],
+        )?;
+
+        // 创建记录全文索引（名称、备注、标签），trigram 分词以支持中文子串搜索
+        let search_exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE name = 'record_search')",
+            [],
+            |row| row.get(0),
+        )?;
+        conn.execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS record_search USING fts5(
+                record_id UNINDEXED,
+                name,
+                note,
+                tags,
+                tokenize = 'trigram'
+            )",
+            [],
+        )?;
+        // 索引按 record_id 关联记录：timer_records 的主键是文本，rowid 可能在 VACUUM 后变化
+        // 旧版本的触发器按 rowid 关联，先删除再重新创建
+        for trigger in ["insert", "update", "delete", "tag_insert", "tag_update", "tag_delete"] {
+            conn.execute(&format!("DROP TRIGGER IF EXISTS record_search_{}", trigger), [])?;
+        }
+        conn.execute_batch(&format!(
+            "CREATE TRIGGER IF NOT EXISTS record_search_insert AFTER INSERT ON timer_records BEGIN
+                INSERT INTO record_search (record_id, name, note, tags) 
+                VALUES (NEW.id, NEW.name, NEW.note, {record_tags});
+             END;
+             CREATE TRIGGER IF NOT EXISTS record_search_update AFTER UPDATE OF name, note ON timer_records BEGIN
+                UPDATE record_search SET name = NEW.name, note = NEW.note WHERE record_id = NEW.id;
+             END;
+             CREATE TRIGGER IF NOT EXISTS record_search_delete AFTER DELETE ON timer_records BEGIN
+                DELETE FROM record_search WHERE record_id = OLD.id;
+             END;
+             CREATE TRIGGER IF NOT EXISTS record_search_tag_insert AFTER INSERT ON record_tags BEGIN
+                UPDATE record_search SET tags = {new_tags} WHERE record_id = NEW.record_id;
+             END;
+             CREATE TRIGGER IF NOT EXISTS record_search_tag_update AFTER UPDATE ON record_tags BEGIN
+                UPDATE record_search SET tags = {old_tags} WHERE record_id = OLD.record_id;
+                UPDATE record_search SET tags = {new_tags} WHERE record_id = NEW.record_id;
+             END;
+             CREATE TRIGGER IF NOT EXISTS record_search_tag_delete AFTER DELETE ON record_tags BEGIN
+                UPDATE record_search SET tags = {old_tags} WHERE record_id = OLD.record_id;
+             END;
+             CREATE TRIGGER IF NOT EXISTS record_search_tag_rename AFTER UPDATE OF name ON tags BEGIN
+                UPDATE record_search SET tags = {search_tags} WHERE record_id IN (SELECT record_id FROM record_tags WHERE tag_id = NEW.id);
+             END;",
+            record_tags = record_tag_names("NEW.id"),
+            new_tags = record_tag_names("NEW.record_id"),
+            old_tags = record_tag_names("OLD.record_id"),
+            search_tags = record_tag_names("record_search.record_id"),
+        ))?;
+        // 首次创建索引时导入已有记录
+        if !search_exists {
+            conn.execute(
+                &format!(
+                    "INSERT INTO record_search (record_id, name, note, tags) 
+                     SELECT id, name, note, {} FROM timer_records",
+                    record_tag_names("timer_records.id")
+                ),
+                [],
+            )?;
+        }
+
+        // 创建审计日志表（只允许追加）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER,
+                table_name TEXT NOT NULL,
+                row_key TEXT NOT NULL,
+                action TEXT NOT NULL,
+                old_values TEXT,
+                new_values TEXT,
+                command TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_audit_log_row 
+             ON audit_log(table_name, row_key)",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS audit_log_no_update BEFORE UPDATE ON audit_log 
+             BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS audit_log_no_delete BEFORE DELETE ON audit_log 
+             BEGIN SELECT RAISE(ABORT, 'audit_log is append-only'); END",
+            [],
+        )?;
+
+        // 审计触发器放在临时库中，每次打开连接时按当前表结构重建
+        create_audit_triggers(&conn)?;
+
+        println!("✓ Database tables initialized");
+        Ok(())
+    }
+
+    // 用户相关操作
+    pub fn get_or_create_user(&self, device_id: &str) -> Result<User> {
+        let conn = self.conn.lock().unwrap();
+
+        // 尝试查找现有用户
+        let mut stmt = conn.prepare("SELECT id, device_id, phone, created_at, updated_at FROM users WHERE device_id = ?1")?;
+        let user_result = stmt.query_row(params![device_id], |row| {
+            Ok(User {
+                id: row.get(0)?,
+                device_id: row.get(1)?,
+                phone: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        });
+
+        match user_result {
+            Ok(user) => Ok(user),
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                // 创建新用户
+                let now = time_utils::now_ms();
+
+                conn.execute(
+                    "INSERT INTO users (device_id, created_at, updated_at) VALUES (?1, ?2, ?3)",
+                    params![device_id, now, now],
+                )?;
+
+                let user_id = conn.last_insert_rowid();
+                println!("✓ New user created with ID: {}", user_id);
+
+                Ok(User {
+                    id: user_id,
+                    device_id: device_id.to_string(),
+                    phone: None,
+                    created_at: now,
+                    updated_at: now,
+                })
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn update_user_phone(&self, user_id: i64, phone: Option<String>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+
+        conn.execute(
+            "UPDATE users SET phone = ?1, updated_at = ?2 WHERE id = ?3",
+            params![phone, now, user_id],
+        )?;
+
+        println!("✓ User phone updated");
+        Ok(())
+    }
+
+    pub fn get_user_by_id(&self, user_id: i64) -> Result<User> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, device_id, phone, created_at, updated_at FROM users WHERE id = ?1"
+        )?;
+        
+        stmt.query_row(params![user_id], |row| {
+            Ok(User {
+                id: row.get(0)?,
+                device_id: row.get(1)?,
+                phone: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+            })
+        })
+    }
+
+    // 设置相关操作
+    pub fn get_settings(&self, user_id: i64) -> Result<Vec<Setting>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, user_id, key, value, updated_at FROM settings WHERE user_id = ?1"
+        )?;
+
+        let settings = stmt
+            .query_map(params![user_id], |row| {
+                Ok(Setting {
+                    id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    key: row.get(2)?,
+                    value: row.get(3)?,
+                    updated_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(settings)
+    }
+
+    pub fn save_setting(&self, user_id: i64, key: &str, value: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+
+        conn.execute(
+            "INSERT INTO settings (user_id, key, value, updated_at) VALUES (?1, ?2, ?3, ?4) 
+                 ON CONFLICT(user_id, key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+            params![user_id, key, value, now],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn save_settings_batch(&self, user_id: i64, settings: Vec<(String, String)>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+
+        for (key, value) in settings {
+            conn.execute(
+                "INSERT INTO settings (user_id, key, value, updated_at) VALUES (?1, ?2, ?3, ?4) 
+                 ON CONFLICT(user_id, key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+                params![user_id, &key, &value, now],
+            )?;
+        }
+
+        println!("✓ Settings batch saved");
+        Ok(())
+    }
+
+    /// 读取单个设置并按 JSON 解析（设置值由前端以 JSON 字符串保存）
+    pub fn get_setting_json(&self, user_id: i64, key: &str) -> Result<Option<serde_json::Value>> {
+        let conn = self.conn.lock().unwrap();
+        let value: Option<String> = conn
+            .query_row(
+                "SELECT value FROM settings WHERE user_id = ?1 AND key = ?2",
+                params![user_id, key],
+                |row| row.get(0),
+            )
+            .map(Some)
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                e => Err(e),
+            })?;
+
+        Ok(value.and_then(|v| serde_json::from_str(&v).ok()))
+    }
+
+    // 计时记录相关操作
+    pub fn get_timer_records(&self, user_id: i64, limit: i64) -> Result<Vec<TimerRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} 
+             FROM timer_records 
+             WHERE user_id = ?1 AND deleted_at IS NULL 
+             ORDER BY end_time DESC 
+             LIMIT ?2",
+            TIMER_RECORD_COLUMNS
+        ))?;
+
+        let records = stmt
+            .query_map(params![user_id, limit], map_timer_record)?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(records)
+    }
+
+    /// 按条件查询记录，按结束时间降序
+    pub fn query_timer_records(&self, user_id: i64, filter: &RecordFilter, limit: Option<i64>) -> Result<Vec<TimerRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let (where_sql, mut params_list) = filter.where_clause(user_id);
+        params_list.push(Box::new(limit.unwrap_or(-1)));
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} 
+             FROM timer_records 
+             WHERE {} 
+             ORDER BY end_time DESC 
+             LIMIT ?",
+            TIMER_RECORD_COLUMNS, where_sql
+        ))?;
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_list.iter().map(|p| p.as_ref()).collect();
+        let records = stmt
+            .query_map(params_refs.as_slice(), map_timer_record)?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(records)
+    }
+
+    /// 符合条件的记录数，用于批量操作前的预览
+    pub fn count_timer_records(&self, user_id: i64, filter: &RecordFilter) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let (where_sql, params_list) = filter.where_clause(user_id);
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_list.iter().map(|p| p.as_ref()).collect();
+        conn.query_row(
+            &format!("SELECT COUNT(*) FROM timer_records WHERE {}", where_sql),
+            params_refs.as_slice(),
+            |row| row.get(0),
+        )
+    }
+
+    /// 在一个事务中对符合条件的记录执行批量操作，返回受影响的记录数
+    pub fn bulk_update_records(&self, user_id: i64, filter: &RecordFilter, operation: &BulkOperation) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let now = time_utils::now_ms();
+
+        // 先固定目标记录，避免修改分类、标签后条件不再匹配
+        let ids: Vec<String> = {
+            let (where_sql, params_list) = filter.where_clause(user_id);
+            let params_refs: Vec<&dyn rusqlite::ToSql> = params_list.iter().map(|p| p.as_ref()).collect();
+            let mut stmt = tx.prepare(&format!("SELECT id FROM timer_records WHERE {}", where_sql))?;
+            let ids = stmt
+                .query_map(params_refs.as_slice(), |row| row.get(0))?
+                .collect::<Result<Vec<_>>>()?;
+            ids
+        };
+
+        for id in &ids {
+            match operation {
+                BulkOperation::Recategorize { category } => {
+                    tx.execute(
+                        "UPDATE timer_records SET category = ?1 WHERE id = ?2 AND user_id = ?3",
+                        params![category, id, user_id],
+                    )?;
+                }
+                BulkOperation::Rename { name } => {
+                    tx.execute(
+                        "UPDATE timer_records SET name = ?1 WHERE id = ?2 AND user_id = ?3",
+                        params![name, id, user_id],
+                    )?;
+                }
+                BulkOperation::Tag { add, remove } => {
+                    for tag_id in add {
+                        tx.execute(
+                            "INSERT OR IGNORE INTO record_tags (record_id, tag_id) 
+                             SELECT ?1, id FROM tags WHERE id = ?2 AND user_id = ?3",
+                            params![id, tag_id, user_id],
+                        )?;
+                    }
+                    for tag_id in remove {
+                        tx.execute(
+                            "DELETE FROM record_tags WHERE record_id = ?1 AND tag_id = ?2",
+                            params![id, tag_id],
+                        )?;
+                    }
+                }
+                BulkOperation::Delete => {
+                    tx.execute(
+                        "UPDATE timer_records SET deleted_at = ?1 WHERE id = ?2 AND user_id = ?3",
+                        params![now, id, user_id],
+                    )?;
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(ids.len())
+    }
+
+    /// 结束时间落在 [start, end) 内的记录，按结束时间升序
+    pub fn get_timer_records_between(&self, user_id: i64, start: i64, end: i64) -> Result<Vec<TimerRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} 
+             FROM timer_records 
+             WHERE user_id = ?1 AND end_time >= ?2 AND end_time < ?3 AND deleted_at IS NULL 
+             ORDER BY end_time ASC",
+            TIMER_RECORD_COLUMNS
+        ))?;
+
+        let records = stmt
+            .query_map(params![user_id, start, end], map_timer_record)?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(records)
+    }
+
+    pub fn get_timer_record(&self, user_id: i64, record_id: &str) -> Result<Option<TimerRecord>> {
+        let conn = self.conn.lock().unwrap();
+        select_timer_record(&conn, user_id, record_id)
+    }
+
+    /// 同一模式下与 [start, end) 重叠的记录，exclude_id 用于排除正在修改的记录
+    pub fn find_overlapping_records(
+        &self,
+        user_id: i64,
+        mode: Option<&str>,
+        start: i64,
+        end: i64,
+        exclude_id: Option<&str>,
+    ) -> Result<Vec<TimerRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM timer_records 
+             WHERE user_id = ?1 AND mode IS ?2 AND start_time < ?4 AND end_time > ?3 
+               AND (?5 IS NULL OR id != ?5) AND deleted_at IS NULL 
+             ORDER BY start_time ASC",
+            TIMER_RECORD_COLUMNS
+        ))?;
+
+        let records = stmt
+            .query_map(params![user_id, mode, start, end, exclude_id], map_timer_record)?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(records)
+    }
+
+    /// 统计结束时间落在 [start, end) 内的工作时长总和
+    pub fn sum_work_duration(&self, user_id: i64, start: i64, end: i64, include_manual: bool) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            "SELECT COALESCE(SUM(duration), 0) FROM timer_records 
+             WHERE user_id = ?1 AND mode = 'work' AND end_time >= ?2 AND end_time < ?3 
+               AND (?4 OR source != 'manual') AND deleted_at IS NULL",
+            params![user_id, start, end, include_manual],
+            |row| row.get(0),
+        )
+    }
+
+    pub fn add_timer_record(&self, record: &TimerRecord) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        insert_timer_record(&conn, record)
+    }
+
+    pub fn update_timer_record(&self, user_id: i64, record_id: &str, update: TimerRecordUpdate) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        // 构建动态 SQL
+        let mut updates = Vec::new();
+        let mut params_list: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(n) = update.name {
+            updates.push("name = ?");
+            params_list.push(Box::new(n));
+        }
+
+        if let Some(c) = update.category {
+            updates.push("category = ?");
+            params_list.push(Box::new(c));
+        }
+
+        if let Some(note) = update.note {
+            updates.push("note = ?");
+            params_list.push(Box::new(note));
+        }
+
+        if let Some(rating) = update.focus_rating {
+            updates.push("focus_rating = ?");
+            params_list.push(Box::new(rating));
+        }
+
+        if let Some(task_id) = update.task_id {
+            updates.push("task_id = ?");
+            params_list.push(Box::new(task_id));
+        }
+
+        if let Some(project_id) = update.project_id {
+            updates.push("project_id = ?");
+            params_list.push(Box::new(project_id));
+        }
+
+        if let Some(billable) = update.billable {
+            updates.push("billable = ?");
+            params_list.push(Box::new(billable));
+        }
+
+        if let Some((start_time, end_time)) = update.times {
+            updates.push("start_time = ?");
+            params_list.push(Box::new(start_time));
+            updates.push("end_time = ?");
+            params_list.push(Box::new(end_time));
+            updates.push("duration = ?");
+            params_list.push(Box::new(end_time - start_time));
+        }
+
+        if updates.is_empty() {
+            return Ok(()); // 无需更新
+        }
+
+        // 添加 record_id 和 user_id 到参数列表
+        params_list.push(Box::new(record_id.to_string()));
+        params_list.push(Box::new(user_id));
+
+        let sql = format!(
+            "UPDATE timer_records SET {} WHERE id = ? AND user_id = ?",
+            updates.join(", ")
+        );
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_list.iter().map(|p| p.as_ref()).collect();
+        conn.execute(&sql, params_refs.as_slice())?;
+
+        Ok(())
+    }
+
+    /// 在 at 处把记录拆成两条：原记录保留前半段，new_id 为后半段
+    /// 备注、标签等字段复制到两条记录，时长按时间比例分配，at 之后的打断和提醒归入后半段
+    pub fn split_timer_record(
+        &self,
+        user_id: i64,
+        record_id: &str,
+        at: i64,
+        new_id: &str,
+        first: RecordPart,
+        second: RecordPart,
+    ) -> Result<(TimerRecord, TimerRecord)> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let original = select_timer_record(&tx, user_id, record_id)?
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+        let span = (original.end_time - original.start_time).max(1);
+        let first_duration = (original.duration as f64 * (at - original.start_time) as f64 / span as f64).round() as i64;
+
+        let (first_reminders, second_reminders): (Vec<i64>, Vec<i64>) =
+            original.reminder_times.iter().partition(|t| **t < at);
+
+        let mut head = original.clone();
+        head.end_time = at;
+        head.duration = first_duration;
+        head.reminder_times = first_reminders;
+        head.reminder_count = head.reminder_times.len() as i64;
+        first.apply(&mut head);
+
+        // 后半段不再计为一个番茄
+        let mut tail = original;
+        tail.split_from = tail.split_from.take().or_else(|| Some(tail.id.clone()));
+        tail.id = new_id.to_string();
+        tail.start_time = at;
+        tail.duration -= first_duration;
+        tail.reminder_times = second_reminders;
+        tail.reminder_count = tail.reminder_times.len() as i64;
+        second.apply(&mut tail);
+
+        tx.execute(
+            "UPDATE timer_records 
+             SET name = ?1, category = ?2, end_time = ?3, duration = ?4, reminder_count = ?5, reminder_times = ?6 
+             WHERE id = ?7 AND user_id = ?8",
+            params![
+                head.name,
+                head.category,
+                head.end_time,
+                head.duration,
+                head.reminder_count,
+                serde_json::to_string(&head.reminder_times).ok(),
+                head.id,
+                user_id,
+            ],
+        )?;
+        insert_timer_record(&tx, &tail)?;
+        tx.execute(
+            "UPDATE interruptions SET record_id = ?1 
+             WHERE user_id = ?2 AND record_id = ?3 AND occurred_at >= ?4",
+            params![tail.id, user_id, head.id, at],
+        )?;
+
+        tx.commit()?;
+        Ok((head, tail))
+    }
+
+    /// 把多条记录合并到最早的一条：时间取并集，时长相加，备注拼接，标签取并集；其余记录移入回收站
+    /// 调用方需保证记录属于同一模式且相邻
+    pub fn merge_timer_records(&self, user_id: i64, record_ids: &[String]) -> Result<TimerRecord> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let now = time_utils::now_ms();
+
+        let mut records = Vec::with_capacity(record_ids.len());
+        for id in record_ids {
+            records.push(select_timer_record(&tx, user_id, id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)?);
+        }
+        records.sort_by_key(|r| r.start_time);
+
+        let mut rest = records.split_off(1);
+        let mut merged = records.remove(0);
+        let ratings: Vec<i64> = std::iter::once(&merged)
+            .chain(rest.iter())
+            .filter_map(|r| r.focus_rating)
+            .collect();
+
+        for r in &mut rest {
+            merged.end_time = merged.end_time.max(r.end_time);
+            merged.duration += r.duration;
+            merged.reminder_count += r.reminder_count;
+            merged.reminder_times.append(&mut r.reminder_times);
+            merged.name = merged.name.take().or(r.name.take());
+            merged.category = merged.category.take().or(r.category.take());
+            merged.task_id = merged.task_id.or(r.task_id);
+            merged.project_id = merged.project_id.or(r.project_id);
+            merged.billable |= r.billable;
+            if r.source != "manual" {
+                merged.source = r.source.clone();
+            }
+            if let Some(note) = r.note.take().filter(|n| !n.trim().is_empty()) {
+                merged.note = Some(match merged.note.take().filter(|n| !n.trim().is_empty()) {
+                    Some(existing) if existing != note => format!("{}\n\n{}", existing, note),
+                    Some(existing) => existing,
+                    None => note,
+                });
+            }
+            for tag in r.tags.drain(..) {
+                if !merged.tags.contains(&tag) {
+                    merged.tags.push(tag);
+                }
+            }
+        }
+        merged.reminder_times.sort_unstable();
+        if !ratings.is_empty() {
+            merged.focus_rating = Some((ratings.iter().sum::<i64>() as f64 / ratings.len() as f64).round() as i64);
+        }
+
+        tx.execute(
+            "UPDATE timer_records 
+             SET name = ?1, category = ?2, end_time = ?3, duration = ?4, reminder_count = ?5, reminder_times = ?6, 
+                 note = ?7, focus_rating = ?8, task_id = ?9, project_id = ?10, billable = ?11, source = ?12 
+             WHERE id = ?13 AND user_id = ?14",
+            params![
+                merged.name,
+                merged.category,
+                merged.end_time,
+                merged.duration,
+                merged.reminder_count,
+                serde_json::to_string(&merged.reminder_times).ok(),
+                merged.note,
+                merged.focus_rating,
+                merged.task_id,
+                merged.project_id,
+                merged.billable,
+                merged.source,
+                merged.id,
+                user_id,
+            ],
+        )?;
+        for r in &rest {
+            tx.execute(
+                "UPDATE interruptions SET record_id = ?1 WHERE user_id = ?2 AND record_id = ?3",
+                params![merged.id, user_id, r.id],
+            )?;
+            tx.execute(
+                "INSERT OR IGNORE INTO record_tags (record_id, tag_id) 
+                 SELECT ?1, tag_id FROM record_tags WHERE record_id = ?2",
+                params![merged.id, r.id],
+            )?;
+            // 被合并的记录移入回收站，保留原标签以便恢复
+            tx.execute(
+                "UPDATE timer_records SET deleted_at = ?1 WHERE id = ?2 AND user_id = ?3",
+                params![now, r.id, user_id],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(merged)
+    }
+
+    /// 移入回收站
+    pub fn delete_timer_record(&self, user_id: i64, record_id: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+
+        conn.execute(
+            "UPDATE timer_records SET deleted_at = ?1 
+             WHERE id = ?2 AND user_id = ?3 AND deleted_at IS NULL",
+            params![now, record_id, user_id],
+        )?;
+
+        Ok(())
+    }
+
+    /// 将所有记录移入回收站
+    pub fn clear_timer_records(&self, user_id: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+
+        conn.execute(
+            "UPDATE timer_records SET deleted_at = ?1 WHERE user_id = ?2 AND deleted_at IS NULL",
+            params![now, user_id],
+        )?;
+
+        println!("✓ All timer records moved to trash for user {}", user_id);
+        Ok(())
+    }
+
+    // ==================== 自定义分类 CRUD ====================
+
+    /// 所有分类（包括已归档，不含回收站），按置顶、排序值、创建时间排列
+    pub fn get_custom_categories(&self, user_id: i64) -> Result<Vec<CustomCategory>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} 
+             FROM custom_categories 
+             WHERE user_id = ?1 AND deleted_at IS NULL 
+             ORDER BY pinned DESC, sort_order ASC, created_at ASC",
+            CUSTOM_CATEGORY_COLUMNS
+        ))?;
+
+        let categories = stmt.query_map(params![user_id], map_custom_category)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(categories)
+    }
+
+    pub fn add_custom_category(&self, user_id: i64, value: String, label: String, icon: String) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+
+        // 回收站中同值的分类直接清除，避免违反唯一约束
+        conn.execute(
+            "DELETE FROM custom_categories WHERE user_id = ?1 AND value = ?2 AND deleted_at IS NOT NULL",
+            params![user_id, value],
+        )?;
+
+        // 新分类排在末尾
+        conn.execute(
+            "INSERT INTO custom_categories (user_id, value, label, icon, created_at, sort_order) 
+             VALUES (?1, ?2, ?3, ?4, ?5, 
+                     (SELECT COALESCE(MAX(sort_order), 0) + 1 FROM custom_categories WHERE user_id = ?1))",
+            params![user_id, value, label, icon, now],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn update_custom_category(&self, user_id: i64, value: &str, label: String, icon: String) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "UPDATE custom_categories 
+             SET label = ?1, icon = ?2 
+             WHERE user_id = ?3 AND value = ?4",
+            params![label, icon, user_id, value],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn update_category_meta(&self, user_id: i64, value: &str, update: CategoryMetaUpdate) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut updates = Vec::new();
+        let mut params_list: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(parent) = update.parent_value {
+            updates.push("parent_value = ?");
+            params_list.push(Box::new(parent));
+        }
+
+        if let Some(color) = update.color {
+            updates.push("color = ?");
+            params_list.push(Box::new(color));
+        }
+
+        if let Some(pinned) = update.pinned {
+            updates.push("pinned = ?");
+            params_list.push(Box::new(pinned));
+        }
+
+        if let Some(archived) = update.archived {
+            updates.push("archived = ?");
+            params_list.push(Box::new(archived));
+        }
+
+        if updates.is_empty() {
+            return Ok(());
+        }
+
+        params_list.push(Box::new(user_id));
+        params_list.push(Box::new(value.to_string()));
+
+        let sql = format!(
+            "UPDATE custom_categories SET {} WHERE user_id = ? AND value = ?",
+            updates.join(", ")
+        );
+
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_list.iter().map(|p| p.as_ref()).collect();
+        conn.execute(&sql, params_refs.as_slice())?;
+
+        Ok(())
+    }
+
+    /// 按给定顺序重写排序值，未列出的分类保持原值
+    pub fn reorder_custom_categories(&self, user_id: i64, values: &[String]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        for (index, value) in values.iter().enumerate() {
+            tx.execute(
+                "UPDATE custom_categories SET sort_order = ?1 WHERE user_id = ?2 AND value = ?3",
+                params![index as i64, user_id, value],
+            )?;
+        }
+
+        tx.commit()
+    }
+
+    /// 按分类汇总结束时间落在 [start, end) 内的工作时长
+    pub fn get_category_durations(&self, user_id: i64, start: i64, end: i64, include_manual: bool) -> Result<Vec<(Option<String>, i64)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT category, SUM(duration) 
+             FROM timer_records 
+             WHERE user_id = ?1 AND mode = 'work' AND end_time >= ?2 AND end_time < ?3 
+               AND (?4 OR source != 'manual') AND deleted_at IS NULL 
+             GROUP BY category"
+        )?;
+
+        let durations = stmt
+            .query_map(params![user_id, start, end, include_manual], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(durations)
+    }
+
+    /// 结束时间在 [start, end) 内的工作记录
+    pub fn get_work_entries(&self, user_id: i64, start: i64, end: i64, include_manual: bool) -> Result<Vec<WorkEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT end_time, tz_offset_minutes, duration, category 
+             FROM timer_records 
+             WHERE user_id = ?1 AND mode = 'work' AND end_time >= ?2 AND end_time < ?3 
+               AND (?4 OR source != 'manual') AND deleted_at IS NULL 
+             ORDER BY end_time ASC"
+        )?;
+
+        let entries = stmt
+            .query_map(params![user_id, start, end, include_manual], |row| {
+                Ok(WorkEntry {
+                    end_time: row.get(0)?,
+                    tz_offset_minutes: row.get(1)?,
+                    duration: row.get(2)?,
+                    category: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    /// 修改分类的值，同步更新计时记录、任务、目标和子分类，返回受影响的记录数
+    pub fn rename_custom_category(&self, user_id: i64, old_value: &str, new_value: &str) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "UPDATE custom_categories SET value = ?1 WHERE user_id = ?2 AND value = ?3",
+            params![new_value, user_id, old_value],
+        )?;
+        let affected = reassign_category(&tx, user_id, old_value, Some(new_value))?;
+        move_category_reminder_rules(&tx, user_id, old_value, new_value, true)?;
+
+        tx.commit()?;
+        Ok(affected)
+    }
+
+    /// 将 source 合并到 target 并把 source 移入回收站，返回受影响的记录数
+    pub fn merge_custom_categories(&self, user_id: i64, source: &str, target: &str) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        // target 原本是 source 的子分类时，改挂到 source 的父分类下，避免自环
+        tx.execute(
+            "UPDATE custom_categories 
+             SET parent_value = (SELECT parent_value FROM custom_categories WHERE user_id = ?1 AND value = ?2) 
+             WHERE user_id = ?1 AND value = ?3 AND parent_value = ?2",
+            params![user_id, source, target],
+        )?;
+        let affected = reassign_category(&tx, user_id, source, Some(target))?;
+        move_category_reminder_rules(&tx, user_id, source, target, false)?;
+        // source 移入回收站，可以恢复或彻底清除
+        tx.execute(
+            "UPDATE custom_categories SET deleted_at = ?1 
+             WHERE user_id = ?2 AND value = ?3 AND deleted_at IS NULL",
+            params![time_utils::now_ms(), user_id, source],
+        )?;
+
+        tx.commit()?;
+        Ok(affected)
+    }
+
+    /// 删除分类并移入回收站；指定 reassign_to 时等同于合并，返回引用该分类的记录数
+    /// 回收站中的分类被彻底清除时，相关记录才变为未分类、子分类提升为顶级分类
+    pub fn delete_custom_category(&self, user_id: i64, value: &str, reassign_to: Option<&str>) -> Result<usize> {
+        if let Some(target) = reassign_to {
+            return self.merge_custom_categories(user_id, value, target);
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+        conn.execute(
+            "UPDATE custom_categories SET deleted_at = ?1 
+             WHERE user_id = ?2 AND value = ?3 AND deleted_at IS NULL",
+            params![now, user_id, value],
+        )?;
+
+        conn.query_row(
+            "SELECT COUNT(*) FROM timer_records WHERE user_id = ?1 AND category = ?2 AND deleted_at IS NULL",
+            params![user_id, value],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|count| count as usize)
+    }
+
+    // ==================== 回收站 ====================
+
+    pub fn get_trash(&self, user_id: i64) -> Result<Trash> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, deleted_at FROM timer_records 
+             WHERE user_id = ?1 AND deleted_at IS NOT NULL 
+             ORDER BY deleted_at DESC, end_time DESC",
+            TIMER_RECORD_COLUMNS
+        ))?;
+        let records = stmt
+            .query_map(params![user_id], |row| {
+                Ok(TrashedRecord {
+                    record: map_timer_record(row)?,
+                    deleted_at: row.get(21)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {}, deleted_at FROM custom_categories 
+             WHERE user_id = ?1 AND deleted_at IS NOT NULL 
+             ORDER BY deleted_at DESC",
+            CUSTOM_CATEGORY_COLUMNS
+        ))?;
+        let categories = stmt
+            .query_map(params![user_id], |row| {
+                Ok(TrashedCategory {
+                    category: map_custom_category(row)?,
+                    deleted_at: row.get(11)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Trash { records, categories })
+    }
+
+    /// 从回收站恢复，返回恢复的条目数
+    pub fn restore_from_trash(&self, user_id: i64, selection: &TrashSelection) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut restored = 0;
+        for id in &selection.record_ids {
+            restored += tx.execute(
+                "UPDATE timer_records SET deleted_at = NULL WHERE id = ?1 AND user_id = ?2 AND deleted_at IS NOT NULL",
+                params![id, user_id],
+            )?;
+        }
+        for value in &selection.category_values {
+            restored += tx.execute(
+                "UPDATE custom_categories SET deleted_at = NULL WHERE value = ?1 AND user_id = ?2 AND deleted_at IS NOT NULL",
+                params![value, user_id],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(restored)
+    }
+
+    /// 彻底删除回收站中选中的条目，返回删除的条目数
+    pub fn purge_from_trash(&self, user_id: i64, selection: &TrashSelection) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let mut purged = 0;
+        for id in &selection.record_ids {
+            purged += purge_record(&tx, user_id, id)?;
+        }
+        for value in &selection.category_values {
+            purged += purge_category(&tx, user_id, value)?;
+        }
+
+        tx.commit()?;
+        Ok(purged)
+    }
+
+    /// 彻底删除在 before 之前移入回收站的条目，返回删除的条目数
+    pub fn purge_trash_before(&self, user_id: i64, before: i64) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let record_ids: Vec<String> = {
+            let mut stmt = tx.prepare(
+                "SELECT id FROM timer_records WHERE user_id = ?1 AND deleted_at IS NOT NULL AND deleted_at < ?2"
+            )?;
+            let ids = stmt.query_map(params![user_id, before], |row| row.get(0))?
+                .collect::<Result<Vec<_>>>()?;
+            ids
+        };
+        let category_values: Vec<String> = {
+            let mut stmt = tx.prepare(
+                "SELECT value FROM custom_categories WHERE user_id = ?1 AND deleted_at IS NOT NULL AND deleted_at < ?2"
+            )?;
+            let values = stmt.query_map(params![user_id, before], |row| row.get(0))?
+                .collect::<Result<Vec<_>>>()?;
+            values
+        };
+
+        let mut purged = 0;
+        for id in &record_ids {
+            purged += purge_record(&tx, user_id, id)?;
+        }
+        for value in &category_values {
+            purged += purge_category(&tx, user_id, value)?;
+        }
+
+        tx.commit()?;
+        Ok(purged)
+    }
+
+    // ==================== 标签 ====================
+
+    pub fn get_tags(&self, user_id: i64) -> Result<Vec<Tag>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, user_id, name, color, created_at 
+             FROM tags 
+             WHERE user_id = ?1 
+             ORDER BY name ASC"
+        )?;
+
+        let tags = stmt.query_map(params![user_id], |row| {
+            Ok(Tag {
+                id: row.get(0)?,
+                user_id: row.get(1)?,
+                name: row.get(2)?,
+                color: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+        Ok(tags)
+    }
+
+    pub fn add_tag(&self, user_id: i64, name: &str, color: Option<String>) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+
+        conn.execute(
+            "INSERT INTO tags (user_id, name, color, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![user_id, name, color, now],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn update_tag(&self, user_id: i64, tag_id: i64, name: &str, color: Option<String>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.execute(
+            "UPDATE tags SET name = ?1, color = ?2 WHERE id = ?3 AND user_id = ?4",
+            params![name, color, tag_id, user_id],
+        )?;
+
+        Ok(())
+    }
+
+    /// 删除标签及其与记录的关联
+    pub fn delete_tag(&self, user_id: i64, tag_id: i64) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "DELETE FROM record_tags WHERE tag_id IN (SELECT id FROM tags WHERE id = ?1 AND user_id = ?2)",
+            params![tag_id, user_id],
+        )?;
+        tx.execute(
+            "DELETE FROM tags WHERE id = ?1 AND user_id = ?2",
+            params![tag_id, user_id],
+        )?;
+
+        tx.commit()
+    }
+
+    /// 替换记录的全部标签
+    pub fn set_record_tags(&self, user_id: i64, record_id: &str, tag_ids: &[i64]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "DELETE FROM record_tags WHERE record_id IN 
+             (SELECT id FROM timer_records WHERE id = ?1 AND user_id = ?2)",
+            params![record_id, user_id],
+        )?;
+        for tag_id in tag_ids {
+            // 只允许关联本用户的记录和标签
+            tx.execute(
+                "INSERT OR IGNORE INTO record_tags (record_id, tag_id) 
+                 SELECT r.id, t.id FROM timer_records r, tags t 
+                 WHERE r.id = ?1 AND r.user_id = ?3 AND t.id = ?2 AND t.user_id = ?3",
+                params![record_id, tag_id, user_id],
+            )?;
+        }
+
+        tx.commit()
+    }
+
+    /// 按标签汇总结束时间落在 [start, end) 内的工作记录
+    pub fn get_tag_stats(&self, user_id: i64, start: i64, end: i64, include_manual: bool) -> Result<Vec<TagStat>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.name, t.color, COUNT(r.id), COALESCE(SUM(r.duration), 0) 
+             FROM tags t 
+             JOIN record_tags rt ON rt.tag_id = t.id 
+             JOIN timer_records r ON r.id = rt.record_id 
+             WHERE t.user_id = ?1 AND r.mode = 'work' AND r.end_time >= ?2 AND r.end_time < ?3 
+               AND (?4 OR r.source != 'manual') AND r.deleted_at IS NULL 
+             GROUP BY t.id 
+             ORDER BY 5 DESC"
+        )?;
+
+        let stats = stmt
+            .query_map(params![user_id, start, end, include_manual], |row| {
+                Ok(TagStat {
+                    tag_id: row.get(0)?,
+                    name: row.get(1)?,
+                    color: row.get(2)?,
+                    session_count: row.get(3)?,
+                    total_work_ms: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(stats)
+    }
+
+    // ==================== 任务 CRUD ====================
+
+    pub fn get_tasks(&self, user_id: i64, include_done: bool) -> Result<Vec<Task>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, user_id, title, category, estimated_pomodoros, status, due_date, created_at, updated_at 
+             FROM tasks 
+             WHERE user_id = ?1 AND (?2 OR status != 'done') 
+             ORDER BY CASE status WHEN 'doing' THEN 0 WHEN 'todo' THEN 1 ELSE 2 END, 
+                      due_date IS NULL, due_date ASC, created_at ASC"
+        )?;
+
+        let tasks = stmt
+            .query_map(params![user_id, include_done], map_task)?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(tasks)
+    }
+
+    pub fn add_task(&self, user_id: i64, task: &Task) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+
+        conn.execute(
+            "INSERT INTO tasks (user_id, title, category, estimated_pomodoros, status, due_date, created_at, updated_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                user_id,
+                task.title,
+                task.category,
+                task.estimated_pomodoros,
+                task.status,
+                task.due_date,
+                now,
+                now,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn update_task(&self, user_id: i64, task: &Task) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+
+        conn.execute(
+            "UPDATE tasks 
+             SET title = ?1, category = ?2, estimated_pomodoros = ?3, status = ?4, due_date = ?5, updated_at = ?6 
+             WHERE id = ?7 AND user_id = ?8",
+            params![
+                task.title,
+                task.category,
+                task.estimated_pomodoros,
+                task.status,
+                task.due_date,
+                now,
+                task.id,
+                user_id,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// 删除任务，已关联的计时记录保留但解除关联
+    pub fn delete_task(&self, user_id: i64, task_id: i64) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "UPDATE timer_records SET task_id = NULL WHERE user_id = ?1 AND task_id = ?2",
+            params![user_id, task_id],
+        )?;
+        tx.execute(
+            "DELETE FROM tasks WHERE id = ?1 AND user_id = ?2",
+            params![task_id, user_id],
+        )?;
+
+        tx.commit()
+    }
+
+    /// 每个任务的预估番茄数与实际投入
+    pub fn get_task_progress(&self, user_id: i64, include_done: bool) -> Result<Vec<TaskProgress>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.user_id, t.title, t.category, t.estimated_pomodoros, t.status, t.due_date, t.created_at, t.updated_at, 
+                    COALESCE(SUM(CASE WHEN r.record_type = 'countdown' AND r.split_from IS NULL THEN 1 ELSE 0 END), 0), 
+                    COUNT(r.id), 
+                    COALESCE(SUM(r.duration), 0) 
+             FROM tasks t 
+             LEFT JOIN timer_records r ON r.task_id = t.id AND r.mode = 'work' AND r.deleted_at IS NULL 
+             WHERE t.user_id = ?1 AND (?2 OR t.status != 'done') 
+             GROUP BY t.id 
+             ORDER BY t.created_at ASC"
+        )?;
+
+        let progress = stmt
+            .query_map(params![user_id, include_done], |row| {
+                Ok(TaskProgress {
+                    task: map_task(row)?,
+                    completed_pomodoros: row.get(9)?,
+                    session_count: row.get(10)?,
+                    total_work_ms: row.get(11)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(progress)
+    }
+
+    // ==================== 项目 CRUD ====================
+
+    pub fn get_projects(&self, user_id: i64) -> Result<Vec<Project>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, user_id, name, client, hourly_rate, currency, created_at, updated_at 
+             FROM projects 
+             WHERE user_id = ?1 
+             ORDER BY client IS NULL, client ASC, name ASC"
+        )?;
+
+        let projects = stmt
+            .query_map(params![user_id], map_project)?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(projects)
+    }
+
+    pub fn add_project(&self, user_id: i64, project: &Project) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+
+        conn.execute(
+            "INSERT INTO projects (user_id, name, client, hourly_rate, currency, created_at, updated_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                user_id,
+                project.name,
+                project.client,
+                project.hourly_rate,
+                project.currency,
+                now,
+                now,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn update_project(&self, user_id: i64, project: &Project) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+
+        conn.execute(
+            "UPDATE projects 
+             SET name = ?1, client = ?2, hourly_rate = ?3, currency = ?4, updated_at = ?5 
+             WHERE id = ?6 AND user_id = ?7",
+            params![
+                project.name,
+                project.client,
+                project.hourly_rate,
+                project.currency,
+                now,
+                project.id,
+                user_id,
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// 删除项目，已关联的计时记录保留但解除关联
+    pub fn delete_project(&self, user_id: i64, project_id: i64) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "UPDATE timer_records SET project_id = NULL WHERE user_id = ?1 AND project_id = ?2",
+            params![user_id, project_id],
+        )?;
+        tx.execute(
+            "DELETE FROM projects WHERE id = ?1 AND user_id = ?2",
+            params![project_id, user_id],
+        )?;
+
+        tx.commit()
+    }
+
+    /// 时间范围内（按开始时间）所有关联项目的计费工作记录
+    pub fn get_billable_entries(&self, user_id: i64, start: i64, end: i64) -> Result<Vec<BillableEntry>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT r.id, p.id, p.name, p.client, p.hourly_rate, p.currency, r.start_time, r.duration 
+             FROM timer_records r 
+             JOIN projects p ON p.id = r.project_id 
+             WHERE r.user_id = ?1 AND r.mode = 'work' AND r.billable = 1 AND r.deleted_at IS NULL 
+               AND r.start_time >= ?2 AND r.start_time < ?3 
+             ORDER BY r.start_time ASC"
+        )?;
+
+        let entries = stmt
+            .query_map(params![user_id, start, end], |row| {
+                Ok(BillableEntry {
+                    record_id: row.get(0)?,
+                    project_id: row.get(1)?,
+                    project_name: row.get(2)?,
+                    client: row.get(3)?,
+                    hourly_rate: row.get(4)?,
+                    currency: row.get(5)?,
+                    start_time: row.get(6)?,
+                    duration: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    // ==================== 目标 ====================
+
+    pub fn get_goals(&self, user_id: i64, active_only: bool) -> Result<Vec<Goal>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} 
+             FROM goals 
+             WHERE user_id = ?1 AND (?2 = 0 OR active = 1) 
+             ORDER BY created_at ASC",
+            GOAL_COLUMNS
+        ))?;
+
+        let goals = stmt
+            .query_map(params![user_id, active_only], map_goal)?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(goals)
+    }
+
+    pub fn add_goal(&self, user_id: i64, goal: &Goal) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+        let weekdays = serde_json::to_string(&goal.weekdays).ok();
+
+        conn.execute(
+            "INSERT INTO goals (user_id, title, category, metric, period, target, weekdays, before_time, active, created_at, updated_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                user_id,
+                goal.title,
+                goal.category,
+                goal.metric,
+                goal.period,
+                goal.target,
+                weekdays,
+                goal.before_time,
+                goal.active,
+                now,
+                now,
+            ],
+        )?;
+
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn update_goal(&self, user_id: i64, goal: &Goal) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+        let weekdays = serde_json::to_string(&goal.weekdays).ok();
+
+        conn.execute(
+            "UPDATE goals 
+             SET title = ?1, category = ?2, metric = ?3, period = ?4, target = ?5, weekdays = ?6, before_time = ?7, active = ?8, updated_at = ?9 
+             WHERE id = ?10 AND user_id = ?11",
+            params![
+                goal.title,
+                goal.category,
+                goal.metric,
+                goal.period,
+                goal.target,
+                weekdays,
+                goal.before_time,
+                goal.active,
+                now,
+                goal.id,
+                user_id,
+            ],
+        )?;
 
-        match user_result {
-            Ok(user) => Ok(user),
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
-                // 创建新用户
-                let now = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis() as i64;
+        Ok(())
+    }
 
-                conn.execute(
-                    "INSERT INTO users (device_id, created_at, updated_at) VALUES (?1, ?2, ?3)",
-                    params![device_id, now, now],
-                )?;
+    /// 删除目标及其进度历史
+    pub fn delete_goal(&self, user_id: i64, goal_id: i64) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
 
-                let user_id = conn.last_insert_rowid();
-                println!("✓ New user created with ID: {}", user_id);
+        tx.execute(
+            "DELETE FROM goal_progress WHERE goal_id = ?1 AND user_id = ?2",
+            params![goal_id, user_id],
+        )?;
+        tx.execute(
+            "DELETE FROM goals WHERE id = ?1 AND user_id = ?2",
+            params![goal_id, user_id],
+        )?;
 
-                Ok(User {
-                    id: user_id,
-                    device_id: device_id.to_string(),
-                    phone: None,
-                    created_at: now,
-                    updated_at: now,
-                })
-            }
-            Err(e) => Err(e),
-        }
+        tx.commit()
     }
 
-    pub fn update_user_phone(&self, user_id: i64, phone: Option<String>) -> Result<()> {
+    pub fn get_goal_progress(&self, goal_id: i64, period_key: &str) -> Result<Option<GoalProgress>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM goal_progress WHERE goal_id = ?1 AND period_key = ?2",
+            GOAL_PROGRESS_COLUMNS
+        ))?;
+
+        let mut rows = stmt.query_map(params![goal_id, period_key], map_goal_progress)?;
+        rows.next().transpose()
+    }
+
+    /// 写入或更新某个周期的目标进度
+    pub fn save_goal_progress(&self, progress: &GoalProgress) -> Result<()> {
         let conn = self.conn.lock().unwrap();
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64;
 
         conn.execute(
-            "UPDATE users SET phone = ?1, updated_at = ?2 WHERE id = ?3",
-            params![phone, now, user_id],
+            "INSERT INTO goal_progress (goal_id, user_id, period_key, period_start, period_end, value, target, achieved_at, updated_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9) 
+             ON CONFLICT(goal_id, period_key) DO UPDATE SET 
+                period_start = excluded.period_start, 
+                period_end = excluded.period_end, 
+                value = excluded.value, 
+                target = excluded.target, 
+                achieved_at = excluded.achieved_at, 
+                updated_at = excluded.updated_at",
+            params![
+                progress.goal_id,
+                progress.user_id,
+                progress.period_key,
+                progress.period_start,
+                progress.period_end,
+                progress.value,
+                progress.target,
+                progress.achieved_at,
+                progress.updated_at,
+            ],
         )?;
 
-        println!("✓ User phone updated");
         Ok(())
     }
 
-    pub fn get_user_by_id(&self, user_id: i64) -> Result<User> {
+    /// 周期开始时间落在 [start, end) 内的目标进度历史，goal_id 为 None 时返回所有目标
+    pub fn get_goal_history(&self, user_id: i64, goal_id: Option<i64>, start: i64, end: i64) -> Result<Vec<GoalProgress>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} 
+             FROM goal_progress 
+             WHERE user_id = ?1 AND (?2 IS NULL OR goal_id = ?2) 
+               AND period_start >= ?3 AND period_start < ?4 
+             ORDER BY period_start ASC, goal_id ASC",
+            GOAL_PROGRESS_COLUMNS
+        ))?;
+
+        let history = stmt
+            .query_map(params![user_id, goal_id, start, end], map_goal_progress)?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(history)
+    }
+
+    // ==================== 连续记录与成就 ====================
+
+    /// 所有工作记录的结束时间及记录时的时区偏移，用于计算连续工作天数
+    pub fn get_work_end_times(&self, user_id: i64, include_manual: bool) -> Result<Vec<(i64, Option<i32>)>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, device_id, phone, created_at, updated_at FROM users WHERE id = ?1"
+            "SELECT end_time, tz_offset_minutes FROM timer_records 
+             WHERE user_id = ?1 AND mode = 'work' AND (?2 OR source != 'manual') AND deleted_at IS NULL 
+             ORDER BY end_time ASC"
         )?;
-        
-        stmt.query_row(params![user_id], |row| {
-            Ok(User {
-                id: row.get(0)?,
-                device_id: row.get(1)?,
-                phone: row.get(2)?,
-                created_at: row.get(3)?,
-                updated_at: row.get(4)?,
-            })
-        })
+
+        let times = stmt
+            .query_map(params![user_id, include_manual], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(times)
     }
 
-    // 设置相关操作
-    pub fn get_settings(&self, user_id: i64) -> Result<Vec<Setting>> {
+    pub fn get_lifetime_totals(&self, user_id: i64) -> Result<LifetimeTotals> {
+        let conn = self.conn.lock().unwrap();
+
+        conn.query_row(
+            "SELECT COALESCE(SUM(CASE WHEN record_type = 'countdown' AND split_from IS NULL THEN 1 ELSE 0 END), 0), 
+                    COUNT(*), 
+                    COALESCE(SUM(duration), 0) 
+             FROM timer_records 
+             WHERE user_id = ?1 AND mode = 'work' AND deleted_at IS NULL",
+            params![user_id],
+            |row| {
+                Ok(LifetimeTotals {
+                    pomodoros: row.get(0)?,
+                    work_sessions: row.get(1)?,
+                    total_work_ms: row.get(2)?,
+                })
+            },
+        )
+    }
+
+    pub fn get_achievements(&self, user_id: i64) -> Result<Vec<Achievement>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, user_id, key, value, updated_at FROM settings WHERE user_id = ?1"
+            "SELECT id, user_id, key, value, unlocked_at 
+             FROM achievements 
+             WHERE user_id = ?1 
+             ORDER BY unlocked_at ASC"
         )?;
 
-        let settings = stmt
+        let achievements = stmt
             .query_map(params![user_id], |row| {
-                Ok(Setting {
+                Ok(Achievement {
                     id: row.get(0)?,
                     user_id: row.get(1)?,
                     key: row.get(2)?,
                     value: row.get(3)?,
-                    updated_at: row.get(4)?,
+                    unlocked_at: row.get(4)?,
                 })
             })?
             .collect::<Result<Vec<_>>>()?;
 
-        Ok(settings)
+        Ok(achievements)
     }
 
-    pub fn save_setting(&self, user_id: i64, key: &str, value: &str) -> Result<()> {
+    /// 解锁成就，已解锁过时返回 false
+    pub fn unlock_achievement(&self, user_id: i64, key: &str, value: f64, unlocked_at: i64) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO achievements (user_id, key, value, unlocked_at) 
+             VALUES (?1, ?2, ?3, ?4)",
+            params![user_id, key, value, unlocked_at],
+        )?;
+
+        Ok(inserted > 0)
+    }
+
+    // ==================== 休息执行记录 ====================
+
+    pub fn add_break_log(&self, user_id: i64, log: &BreakLog) -> Result<i64> {
         let conn = self.conn.lock().unwrap();
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64;
+        let now = time_utils::now_ms();
 
         conn.execute(
-            "INSERT OR REPLACE INTO settings (user_id, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)",
-            params![user_id, key, value, now],
+            "INSERT INTO break_logs 
+             (user_id, timer_mode, planned_ms, actual_ms, overtime_ms, outcome, start_time, end_time, created_at, tz_offset_minutes) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                user_id,
+                log.timer_mode,
+                log.planned_ms,
+                log.actual_ms,
+                log.overtime_ms,
+                log.outcome,
+                log.start_time,
+                log.end_time,
+                now,
+                log.tz_offset_minutes
+                    .unwrap_or_else(|| time_utils::local_offset_minutes(log.end_time)),
+            ],
         )?;
 
-        Ok(())
+        Ok(conn.last_insert_rowid())
     }
 
-    pub fn save_settings_batch(&self, user_id: i64, settings: Vec<(String, String)>) -> Result<()> {
+    pub fn get_break_logs(&self, user_id: i64, start: i64, end: i64) -> Result<Vec<BreakLog>> {
         let conn = self.conn.lock().unwrap();
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64;
+        let mut stmt = conn.prepare(
+            "SELECT id, user_id, timer_mode, planned_ms, actual_ms, overtime_ms, outcome, start_time, end_time, created_at, tz_offset_minutes 
+             FROM break_logs 
+             WHERE user_id = ?1 AND end_time >= ?2 AND end_time < ?3 
+             ORDER BY end_time ASC"
+        )?;
 
-        for (key, value) in settings {
-            conn.execute(
-                "INSERT OR REPLACE INTO settings (user_id, key, value, updated_at) VALUES (?1, ?2, ?3, ?4)",
-                params![user_id, &key, &value, now],
-            )?;
-        }
+        let logs = stmt
+            .query_map(params![user_id, start, end], |row| {
+                Ok(BreakLog {
+                    id: row.get(0)?,
+                    user_id: row.get(1)?,
+                    timer_mode: row.get(2)?,
+                    planned_ms: row.get(3)?,
+                    actual_ms: row.get(4)?,
+                    overtime_ms: row.get(5)?,
+                    outcome: row.get(6)?,
+                    start_time: row.get(7)?,
+                    end_time: row.get(8)?,
+                    created_at: row.get(9)?,
+                    tz_offset_minutes: row.get(10)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
 
-        println!("✓ Settings batch saved");
-        Ok(())
+        Ok(logs)
     }
 
-    // 计时记录相关操作
-    pub fn get_timer_records(&self, user_id: i64, limit: i64) -> Result<Vec<TimerRecord>> {
+    // ==================== 打断记录 ====================
+
+    pub fn log_interruption(&self, user_id: i64, kind: &str, reason: Option<String>, occurred_at: i64) -> Result<Interruption> {
+        let conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+
+        conn.execute(
+            "INSERT INTO interruptions (user_id, kind, reason, occurred_at, created_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![user_id, kind, reason, occurred_at, now],
+        )?;
+
+        Ok(Interruption {
+            id: conn.last_insert_rowid(),
+            user_id,
+            record_id: None,
+            kind: kind.to_string(),
+            reason,
+            occurred_at,
+            created_at: now,
+        })
+    }
+
+    /// 获取打断记录：指定 record_id 时返回该记录的打断，否则返回时间范围内的全部打断
+    pub fn get_interruptions(&self, user_id: i64, record_id: Option<&str>, start: i64, end: i64) -> Result<Vec<Interruption>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, user_id, record_type, mode, name, category, start_time, end_time, duration, created_at 
-             FROM timer_records 
+            "SELECT id, user_id, record_id, kind, reason, occurred_at, created_at 
+             FROM interruptions 
              WHERE user_id = ?1 
-             ORDER BY end_time DESC 
-             LIMIT ?2"
+               AND (?2 IS NULL OR record_id = ?2) 
+               AND occurred_at >= ?3 AND occurred_at < ?4 
+             ORDER BY occurred_at ASC"
         )?;
 
-        let records = stmt
-            .query_map(params![user_id, limit], |row| {
-                Ok(TimerRecord {
+        let interruptions = stmt
+            .query_map(params![user_id, record_id, start, end], |row| {
+                Ok(Interruption {
                     id: row.get(0)?,
                     user_id: row.get(1)?,
-                    record_type: row.get(2)?,
-                    mode: row.get(3)?,
-                    name: row.get(4)?,
-                    category: row.get(5)?,
-                    start_time: row.get(6)?,
-                    end_time: row.get(7)?,
-                    duration: row.get(8)?,
-                    created_at: row.get(9)?,
+                    record_id: row.get(2)?,
+                    kind: row.get(3)?,
+                    reason: row.get(4)?,
+                    occurred_at: row.get(5)?,
+                    created_at: row.get(6)?,
                 })
             })?
             .collect::<Result<Vec<_>>>()?;
 
-        Ok(records)
+        Ok(interruptions)
     }
 
-    pub fn add_timer_record(&self, record: &TimerRecord) -> Result<()> {
+    pub fn delete_interruption(&self, user_id: i64, id: i64) -> Result<()> {
         let conn = self.conn.lock().unwrap();
 
         conn.execute(
-            "INSERT INTO timer_records 
-             (id, user_id, record_type, mode, name, category, start_time, end_time, duration, created_at) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            params![
-                record.id,
-                record.user_id,
-                record.record_type,
-                record.mode,
-                record.name,
-                record.category,
-                record.start_time,
-                record.end_time,
-                record.duration,
-                record.created_at,
-            ],
+            "DELETE FROM interruptions WHERE id = ?1 AND user_id = ?2",
+            params![id, user_id],
         )?;
 
         Ok(())
     }
 
-    pub fn update_timer_record(&self, user_id: i64, record_id: &str, name: Option<String>, category: Option<String>) -> Result<()> {
+    /// 时间范围内每次工作的打断次数
+    pub fn get_session_interruptions(&self, user_id: i64, start: i64, end: i64, include_manual: bool) -> Result<Vec<SessionInterruptions>> {
         let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT r.id, r.name, r.category, r.start_time, r.duration, 
+                    COALESCE(SUM(CASE WHEN i.kind = 'internal' THEN 1 ELSE 0 END), 0), 
+                    COALESCE(SUM(CASE WHEN i.kind = 'external' THEN 1 ELSE 0 END), 0) 
+             FROM timer_records r 
+             LEFT JOIN interruptions i ON i.record_id = r.id 
+             WHERE r.user_id = ?1 AND r.mode = 'work' AND r.end_time >= ?2 AND r.end_time < ?3 
+               AND (?4 OR r.source != 'manual') AND r.deleted_at IS NULL 
+             GROUP BY r.id 
+             ORDER BY r.end_time ASC"
+        )?;
 
-        // 构建动态 SQL
-        let mut updates = Vec::new();
-        let mut params_list: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        let sessions = stmt
+            .query_map(params![user_id, start, end, include_manual], |row| {
+                Ok(SessionInterruptions {
+                    record_id: row.get(0)?,
+                    name: row.get(1)?,
+                    category: row.get(2)?,
+                    start_time: row.get(3)?,
+                    duration: row.get(4)?,
+                    internal: row.get(5)?,
+                    external: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
 
-        if let Some(n) = name {
-            updates.push("name = ?");
-            params_list.push(Box::new(n));
-        }
+        Ok(sessions)
+    }
 
-        if let Some(c) = category {
-            updates.push("category = ?");
-            params_list.push(Box::new(c));
-        }
+    /// 时间范围内按分类统计的打断原因次数（未填写原因的不计入）
+    pub fn get_interruption_reason_counts(&self, user_id: i64, start: i64, end: i64, include_manual: bool) -> Result<Vec<InterruptionReasonCount>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT r.category, TRIM(i.reason), COUNT(*) 
+             FROM interruptions i 
+             JOIN timer_records r ON r.id = i.record_id 
+             WHERE i.user_id = ?1 AND i.occurred_at >= ?2 AND i.occurred_at < ?3 AND r.deleted_at IS NULL 
+               AND (?4 OR r.source != 'manual') AND i.reason IS NOT NULL AND TRIM(i.reason) != '' 
+             GROUP BY r.category, TRIM(i.reason) 
+             ORDER BY COUNT(*) DESC"
+        )?;
 
-        if updates.is_empty() {
-            return Ok(()); // 无需更新
-        }
+        let counts = stmt
+            .query_map(params![user_id, start, end, include_manual], |row| {
+                Ok(InterruptionReasonCount {
+                    category: row.get(0)?,
+                    reason: row.get(1)?,
+                    count: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
 
-        // 添加 record_id 和 user_id 到参数列表
-        params_list.push(Box::new(record_id.to_string()));
-        params_list.push(Box::new(user_id));
+        Ok(counts)
+    }
 
-        let sql = format!(
-            "UPDATE timer_records SET {} WHERE id = ? AND user_id = ?",
-            updates.join(", ")
-        );
+    // ==================== 专注度统计 ====================
 
-        let params_refs: Vec<&dyn rusqlite::ToSql> = params_list.iter().map(|p| p.as_ref()).collect();
-        conn.execute(&sql, params_refs.as_slice())?;
+    /// 时间范围内已评分的工作记录，返回 (分类, 开始时间, 评分)
+    pub fn get_focus_ratings(&self, user_id: i64, start: i64, end: i64, include_manual: bool) -> Result<Vec<FocusRating>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT category, start_time, tz_offset_minutes, focus_rating 
+             FROM timer_records 
+             WHERE user_id = ?1 AND mode = 'work' AND focus_rating IS NOT NULL 
+               AND end_time >= ?2 AND end_time < ?3 AND (?4 OR source != 'manual') AND deleted_at IS NULL"
+        )?;
 
-        Ok(())
+        let rows = stmt
+            .query_map(params![user_id, start, end, include_manual], |row| {
+                Ok(FocusRating {
+                    category: row.get(0)?,
+                    start_time: row.get(1)?,
+                    tz_offset_minutes: row.get(2)?,
+                    rating: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(rows)
     }
 
-    pub fn delete_timer_record(&self, user_id: i64, record_id: &str) -> Result<()> {
+    // ==================== 撤销 / 重做 ====================
+
+    /// 读取 table 中 key 列取值为 ids 的行，作为撤销快照
+    pub fn snapshot_rows(
+        &self,
+        user_id: i64,
+        table: &str,
+        key: &str,
+        ids: &[serde_json::Value],
+    ) -> Result<Vec<serde_json::Map<String, serde_json::Value>>> {
         let conn = self.conn.lock().unwrap();
+        select_undo_rows(&conn, user_id, table, key, ids)
+    }
 
-        conn.execute(
-            "DELETE FROM timer_records WHERE id = ?1 AND user_id = ?2",
-            params![record_id, user_id],
+    /// 记录一次可撤销的操作：清空重做栈，只保留最近 limit 条
+    pub fn push_undo_entry(&self, user_id: i64, label: &str, changes: &[UndoChange], limit: usize) -> Result<i64> {
+        let mut conn = self.conn.lock().unwrap();
+        let now = time_utils::now_ms();
+        let changes_json = serde_json::to_string(changes)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM undo_log WHERE user_id = ?1 AND undone = 1",
+            params![user_id],
         )?;
+        tx.execute(
+            "INSERT INTO undo_log (user_id, label, changes, undone, created_at) VALUES (?1, ?2, ?3, 0, ?4)",
+            params![user_id, label, changes_json, now],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.execute(
+            "DELETE FROM undo_log WHERE user_id = ?1 AND id NOT IN 
+             (SELECT id FROM undo_log WHERE user_id = ?1 ORDER BY id DESC LIMIT ?2)",
+            params![user_id, limit as i64],
+        )?;
+        tx.commit()?;
 
-        Ok(())
+        Ok(id)
     }
 
-    pub fn clear_timer_records(&self, user_id: i64) -> Result<()> {
+    /// 撤销最近一次操作（redo 为 true 时重做最近一次撤销的操作），没有可执行的操作时返回 None
+    /// 受影响的行与记录时的状态不一致时不做修改，丢弃该条记录并返回 Stale
+    pub fn apply_undo(&self, user_id: i64, redo: bool) -> Result<Option<UndoOutcome>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        let sql = if redo {
+            "SELECT id, label, changes, created_at FROM undo_log WHERE user_id = ?1 AND undone = 1 ORDER BY id ASC LIMIT 1"
+        } else {
+            "SELECT id, label, changes, created_at FROM undo_log WHERE user_id = ?1 AND undone = 0 ORDER BY id DESC LIMIT 1"
+        };
+        let entry = match tx.query_row(sql, params![user_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, i64>(3)?))
+        }) {
+            Ok(entry) => entry,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let (id, label, changes_json, created_at) = entry;
+        let changes: Vec<UndoChange> = serde_json::from_str(&changes_json)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e)))?;
+
+        // 当前的行必须与操作完成后（重做时为撤销后）的快照一致
+        for change in &changes {
+            let expected = if redo { &change.before } else { &change.after };
+            let current = select_undo_rows(&tx, user_id, &change.table, &change.key, &change.ids)?;
+            if !same_undo_rows(&current, expected) {
+                // 重做栈中后面的记录都以这条为基础，一并丢弃
+                if redo {
+                    tx.execute("DELETE FROM undo_log WHERE user_id = ?1 AND undone = 1", params![user_id])?;
+                } else {
+                    tx.execute("DELETE FROM undo_log WHERE id = ?1", params![id])?;
+                }
+                tx.commit()?;
+                return Ok(Some(UndoOutcome::Stale(label)));
+            }
+        }
+
+        // 撤销时倒序恢复修改前的行，重做时顺序恢复修改后的行
+        if redo {
+            for change in &changes {
+                write_undo_rows(&tx, user_id, change, &change.after)?;
+            }
+        } else {
+            for change in changes.iter().rev() {
+                write_undo_rows(&tx, user_id, change, &change.before)?;
+            }
+        }
+        tx.execute(
+            "UPDATE undo_log SET undone = ?1 WHERE id = ?2",
+            params![!redo, id],
+        )?;
+        tx.commit()?;
+
+        let mut tables: Vec<String> = changes.into_iter().map(|c| c.table).collect();
+        tables.dedup();
+        Ok(Some(UndoOutcome::Applied(UndoEntry {
+            id,
+            label,
+            created_at,
+            tables,
+        })))
+    }
+
+    pub fn get_undo_state(&self, user_id: i64) -> Result<UndoState> {
         let conn = self.conn.lock().unwrap();
+        let label = |sql: &str| -> Result<Option<String>> {
+            match conn.query_row(sql, params![user_id], |row| row.get(0)) {
+                Ok(label) => Ok(Some(label)),
+                Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+                Err(e) => Err(e),
+            }
+        };
+
+        Ok(UndoState {
+            undo: label("SELECT label FROM undo_log WHERE user_id = ?1 AND undone = 0 ORDER BY id DESC LIMIT 1")?,
+            redo: label("SELECT label FROM undo_log WHERE user_id = ?1 AND undone = 1 ORDER BY id ASC LIMIT 1")?,
+        })
+    }
+
+    // ==================== 审计日志 ====================
 
+    /// 设置之后的数据变更在审计日志中记录的命令，None 表示后台任务
+    fn set_audit_command(&self, command: Option<&str>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
         conn.execute(
-            "DELETE FROM timer_records WHERE user_id = ?1",
-            params![user_id],
+            "INSERT INTO audit_context (id, command) VALUES (1, ?1) 
+             ON CONFLICT(id) DO UPDATE SET command = excluded.command",
+            params![command],
         )?;
-
-        println!("✓ All timer records cleared for user {}", user_id);
         Ok(())
     }
 
-    // ==================== 自定义分类 CRUD ====================
+    /// 返回值存活期间的数据变更记录为 command，结束后恢复为后台任务
+    /// 调用方需在持有数据库锁期间使用，以免其他线程的变更被记到该命令下
+    pub fn audit_scope(&self, command: &str) -> Result<AuditScope<'_>> {
+        self.set_audit_command(Some(command))?;
+        Ok(AuditScope { db: self })
+    }
 
-    pub fn get_custom_categories(&self, user_id: i64) -> Result<Vec<CustomCategory>> {
+    /// 某一行的变更历史，按时间先后排列
+    pub fn get_audit_history(&self, user_id: i64, table: &str, row_key: &str) -> Result<Vec<AuditEntry>> {
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, user_id, value, label, icon, created_at 
-             FROM custom_categories 
-             WHERE user_id = ?1 
-             ORDER BY created_at ASC"
+            "SELECT id, table_name, row_key, action, old_values, new_values, command, created_at 
+             FROM audit_log 
+             WHERE user_id = ?1 AND table_name = ?2 AND row_key = ?3 
+             ORDER BY id ASC",
         )?;
 
-        let categories = stmt.query_map(params![user_id], |row| {
-            Ok(CustomCategory {
-                id: row.get(0)?,
-                user_id: row.get(1)?,
-                value: row.get(2)?,
-                label: row.get(3)?,
-                icon: row.get(4)?,
-                created_at: row.get(5)?,
-            })
-        })?
-        .collect::<Result<Vec<_>, _>>()?;
+        let parse = |value: Option<String>| value.and_then(|v| serde_json::from_str(&v).ok());
+        let entries = stmt
+            .query_map(params![user_id, table, row_key], |row| {
+                Ok(AuditEntry {
+                    id: row.get(0)?,
+                    table_name: row.get(1)?,
+                    row_key: row.get(2)?,
+                    action: row.get(3)?,
+                    old_values: parse(row.get(4)?),
+                    new_values: parse(row.get(5)?),
+                    command: row.get(6)?,
+                    created_at: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
 
-        Ok(categories)
+        Ok(entries)
     }
 
-    pub fn add_custom_category(&self, user_id: i64, value: String, label: String, icon: String) -> Result<i64> {
+    // ==================== 全文搜索 ====================
+
+    /// 在名称、备注和标签中搜索，多个关键词之间为“且”关系
+    /// trigram 索引只能匹配 3 个字符及以上的关键词，更短的关键词改用 LIKE 逐条匹配
+    pub fn search_records(
+        &self,
+        user_id: i64,
+        query: &str,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<RecordSearchHit>> {
+        let (long_terms, short_terms): (Vec<&str>, Vec<&str>) = query
+            .split_whitespace()
+            .partition(|term| term.chars().count() >= 3);
+        if long_terms.is_empty() && short_terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
         let conn = self.conn.lock().unwrap();
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64;
+        let mut conditions = vec![
+            "r.user_id = ?".to_string(),
+            "r.deleted_at IS NULL".to_string(),
+        ];
+        let mut params_list: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(user_id)];
+
+        let (snippet_sql, rank_sql) = if long_terms.is_empty() {
+            ("''".to_string(), "0.0".to_string())
+        } else {
+            // 每个关键词作为短语，避免 FTS5 查询语法中的特殊字符
+            let match_expr = long_terms
+                .iter()
+                .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" ");
+            conditions.push("record_search MATCH ?".to_string());
+            params_list.push(Box::new(match_expr));
+            (
+                format!(
+                    "snippet(record_search, -1, '{}', '{}', '…', 32)",
+                    SEARCH_HIGHLIGHT.0, SEARCH_HIGHLIGHT.1
+                ),
+                // 名称匹配的权重最高，其次是标签和备注
+                "bm25(record_search, 0.0, 10.0, 2.0, 5.0)".to_string(),
+            )
+        };
+        for term in &short_terms {
+            conditions.push(
+                "(s.name LIKE ? ESCAPE '\\' OR s.note LIKE ? ESCAPE '\\' OR s.tags LIKE ? ESCAPE '\\')".to_string(),
+            );
+            let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            let pattern = format!("%{}%", escaped);
+            for _ in 0..3 {
+                params_list.push(Box::new(pattern.clone()));
+            }
+        }
+        if let Some(start) = start_time {
+            conditions.push("r.end_time >= ?".to_string());
+            params_list.push(Box::new(start));
+        }
+        if let Some(end) = end_time {
+            conditions.push("r.end_time < ?".to_string());
+            params_list.push(Box::new(end));
+        }
+        params_list.push(Box::new(limit));
 
-        conn.execute(
-            "INSERT INTO custom_categories (user_id, value, label, icon, created_at) 
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![user_id, value, label, icon, now],
-        )?;
+        let sql = format!(
+            "SELECT s.record_id, {snippet}, {rank} AS score, s.name, s.note, s.tags 
+             FROM record_search s JOIN timer_records r ON r.id = s.record_id 
+             WHERE {where_sql} 
+             ORDER BY score ASC, r.end_time DESC 
+             LIMIT ?",
+            snippet = snippet_sql,
+            rank = rank_sql,
+            where_sql = conditions.join(" AND "),
+        );
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params_list.iter().map(|p| p.as_ref()).collect();
+        let mut stmt = conn.prepare(&sql)?;
+        let matches = stmt
+            .query_map(params_refs.as_slice(), |row| {
+                let fields: [Option<String>; 3] = [row.get(3)?, row.get(4)?, row.get(5)?];
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, f64>(2)?, fields))
+            })?
+            .collect::<Result<Vec<_>>>()?;
 
-        Ok(conn.last_insert_rowid())
+        let mut hits = Vec::with_capacity(matches.len());
+        for (record_id, mut snippet, rank, fields) in matches {
+            if snippet.is_empty() {
+                snippet = short_terms
+                    .first()
+                    .and_then(|term| fields.iter().flatten().find_map(|text| highlight_snippet(text, term, 16)))
+                    .unwrap_or_default();
+            }
+            if let Some(record) = select_timer_record(&conn, user_id, &record_id)? {
+                hits.push(RecordSearchHit { record, snippet, rank });
+            }
+        }
+
+        Ok(hits)
     }
+}
 
-    pub fn update_custom_category(&self, user_id: i64, value: &str, label: String, icon: String) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        conn.execute(
-            "UPDATE custom_categories 
-             SET label = ?1, icon = ?2 
-             WHERE user_id = ?3 AND value = ?4",
-            params![label, icon, user_id, value],
-        )?;
+    #[test]
+    fn highlight_snippet_marks_case_insensitive_match() {
+        assert_eq!(
+            highlight_snippet("Write the Report draft", "report", 50).as_deref(),
+            Some("Write the <mark>Report</mark> draft")
+        );
+        assert_eq!(highlight_snippet("Write the report", "memo", 50), None);
+        assert_eq!(highlight_snippet("Write the report", "", 50), None);
+    }
 
-        Ok(())
+    #[test]
+    fn highlight_snippet_trims_context_by_characters() {
+        assert_eq!(
+            highlight_snippet("今天上午整理了季度报告的初稿和图表", "报告", 3).as_deref(),
+            Some("…了季度<mark>报告</mark>的初稿…")
+        );
+        assert_eq!(
+            highlight_snippet("报告初稿", "报告", 1).as_deref(),
+            Some("<mark>报告</mark>初…")
+        );
     }
 
-    pub fn delete_custom_category(&self, user_id: i64, value: &str) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+    #[test]
+    fn category_reminder_rules_follow_rename_and_merge() {
+        let path = std::env::temp_dir().join(format!("lpe-db-rules-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = Database::new(path).unwrap();
+        let user_id = db.get_or_create_user("test").unwrap().id;
+        db.save_setting(user_id, "categoryReminderRules", r#"{"work":[1],"study":[2],"meeting":[3]}"#).unwrap();
+        let rules = || db.get_setting_json(user_id, "categoryReminderRules").unwrap().unwrap();
+
+        db.rename_custom_category(user_id, "work", "deep").unwrap();
+        assert_eq!(rules(), serde_json::json!({"deep": [1], "study": [2], "meeting": [3]}));
+
+        // 合并时目标已有的规则优先，没有规则时继承来源的规则
+        db.merge_custom_categories(user_id, "study", "deep").unwrap();
+        assert_eq!(rules(), serde_json::json!({"deep": [1], "meeting": [3]}));
+        db.merge_custom_categories(user_id, "meeting", "reading").unwrap();
+        assert_eq!(rules(), serde_json::json!({"deep": [1], "reading": [3]}));
+    }
 
-        conn.execute(
-            "DELETE FROM custom_categories 
-             WHERE user_id = ?1 AND value = ?2",
-            params![user_id, value],
-        )?;
+    #[test]
+    fn merging_categories_moves_source_to_trash() {
+        let path = std::env::temp_dir().join(format!("lpe-db-merge-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = Database::new(path).unwrap();
+        let user_id = db.get_or_create_user("test").unwrap().id;
+        db.add_custom_category(user_id, "reading".into(), "阅读".into(), "📖".into()).unwrap();
+        db.add_custom_category(user_id, "study".into(), "学习".into(), "📚".into()).unwrap();
+
+        db.delete_custom_category(user_id, "reading", Some("study")).unwrap();
+        let values: Vec<String> = db.get_custom_categories(user_id).unwrap().into_iter().map(|c| c.value).collect();
+        assert_eq!(values, vec!["study"]);
+        let trash = db.get_trash(user_id).unwrap();
+        assert_eq!(trash.categories.len(), 1);
+        assert_eq!(trash.categories[0].category.value, "reading");
+    }
 
-        Ok(())
+    #[test]
+    fn search_follows_record_id_when_rowids_change() {
+        let path = std::env::temp_dir().join(format!("lpe-db-search-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = Database::new(path).unwrap();
+        let user_id = db.get_or_create_user("test").unwrap().id;
+        for (id, name) in [("r1", "quarterly report"), ("r2", "code review")] {
+            let record: TimerRecord = serde_json::from_value(serde_json::json!({
+                "id": id, "user_id": user_id, "record_type": "stopwatch", "mode": "work", "name": name,
+                "start_time": 1_000, "end_time": 61_000, "duration": 60_000, "created_at": 1_000
+            }))
+            .unwrap();
+            db.add_timer_record(&record).unwrap();
+        }
+        // 模拟 VACUUM 重新编号 rowid
+        db.conn.lock().unwrap().execute("UPDATE timer_records SET rowid = rowid + 100", []).unwrap();
+
+        let hits = db.search_records(user_id, "review", None, None, 10).unwrap();
+        assert_eq!(hits.iter().map(|h| h.record.id.as_str()).collect::<Vec<_>>(), vec!["r2"]);
+        db.update_timer_record(user_id, "r2", TimerRecordUpdate { name: Some("design review".to_string()), ..Default::default() }).unwrap();
+        assert_eq!(db.search_records(user_id, "design", None, None, 10).unwrap().len(), 1);
     }
 }
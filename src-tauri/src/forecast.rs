@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::Result;
+use serde::Serialize;
+
+use crate::db::{Database, Goal, TimerRecord};
+use crate::goals;
+use crate::time_utils;
+
+/// 默认参考的历史周数
+pub const DEFAULT_HISTORY_WEEKS: usize = 4;
+
+/// 一个每周目标在本周的预测
+#[derive(Debug, Serialize, Clone)]
+pub struct WeeklyForecast {
+    pub goal: Goal,
+    /// 本周截至目前的进度
+    pub current: f64,
+    /// 按历史同一星期几的平均值推算的本周最终值
+    pub projected: f64,
+    pub target: f64,
+    /// 按历史节奏，达成目标此时应有的进度
+    pub expected_by_now: f64,
+    /// achieved / ahead / behind
+    pub status: String,
+    /// 历史上周一到周日每天的平均值
+    pub weekday_averages: Vec<f64>,
+    /// 参与平均的历史周数（没有任何记录的周不计入）
+    pub history_weeks: usize,
+    /// 本周剩余天数（含今天）
+    pub remaining_days: u32,
+    /// 达成目标剩余每天平均需要完成的量，已达成时为 0
+    pub required_per_day: f64,
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+/// 距所属日期开始时刻的分钟数（按记录保存时的时区），用于比较“当天到此刻为止”的进度
+fn minutes_into_day(ts: i64, tz_offset_minutes: Option<i32>) -> u32 {
+    (time_utils::record_minute_of_day(ts, tz_offset_minutes) + 24 * 60 - time_utils::day_start_minutes()) % (24 * 60)
+}
+
+/// 根据最近 `weeks` 周同一星期几的平均值，预测所有启用的每周目标在本周的结果
+pub fn weekly_forecast(db: &Database, user_id: i64, now: i64, weeks: usize) -> Result<Vec<WeeklyForecast>> {
+    let goals: Vec<Goal> = db
+        .get_goals(user_id, true)?
+        .into_iter()
+        .filter(|g| g.period == "weekly")
+        .collect();
+    if goals.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (week_start, week_end) = time_utils::week_range(now);
+    let monday = time_utils::local_date(week_start);
+    let today = time_utils::local_date(now);
+    let today_index = (today - monday).num_days().clamp(0, 6) as usize;
+    let now_elapsed = minutes_into_day(now, None);
+
+    // 按记录所属日期归入本周或历史，查询范围前后各多取一天以包含其他时区保存的记录
+    let history_monday = monday - Duration::weeks(weeks as i64);
+    let (padded_start, padded_end) = time_utils::padded_date_range(history_monday, today);
+    let mut current_records = Vec::new();
+    let mut by_day: HashMap<NaiveDate, Vec<TimerRecord>> = HashMap::new();
+    for record in db.get_timer_records_between(user_id, padded_start, padded_end)? {
+        if record.end_time > now {
+            continue;
+        }
+        let date = time_utils::record_date(record.end_time, record.tz_offset_minutes);
+        if date >= monday && date <= today {
+            current_records.push(record);
+        } else if date >= history_monday && date < monday {
+            // 历史记录按日期分组，只有存在记录的周参与平均
+            by_day.entry(date).or_default().push(record);
+        }
+    }
+    let active_weeks: Vec<NaiveDate> = (1..=weeks as i64)
+        .map(|w| monday - Duration::weeks(w))
+        .filter(|start| (0..7).any(|d| by_day.contains_key(&(*start + Duration::days(d)))))
+        .collect();
+
+    let mut forecasts = Vec::with_capacity(goals.len());
+    for goal in goals {
+        let current = goals::measure(&goal, &current_records);
+
+        // 每个星期几的全天平均值，以及今天对应的星期几在此刻之前的平均值
+        let mut weekday_averages = vec![0.0; 7];
+        let mut today_before_now = 0.0;
+        for start in &active_weeks {
+            for (d, average) in weekday_averages.iter_mut().enumerate() {
+                let records = by_day.get(&(*start + Duration::days(d as i64)));
+                let records = records.map(|r| r.as_slice()).unwrap_or(&[]);
+                *average += goals::measure(&goal, records);
+                if d == today_index {
+                    let before: Vec<TimerRecord> = records
+                        .iter()
+                        .filter(|r| minutes_into_day(r.end_time, r.tz_offset_minutes) <= now_elapsed)
+                        .cloned()
+                        .collect();
+                    today_before_now += goals::measure(&goal, &before);
+                }
+            }
+        }
+        if !active_weeks.is_empty() {
+            let n = active_weeks.len() as f64;
+            weekday_averages.iter_mut().for_each(|v| *v /= n);
+            today_before_now /= n;
+        }
+
+        let rest_of_today = (weekday_averages[today_index] - today_before_now).max(0.0);
+        let rest_of_week: f64 = weekday_averages[today_index + 1..].iter().sum();
+        let projected = current + rest_of_today + rest_of_week;
+
+        // 没有历史数据时按本周已过去的时间比例计算节奏
+        let typical_week: f64 = weekday_averages.iter().sum();
+        let share_by_now = if typical_week > 0.0 {
+            (weekday_averages[..today_index].iter().sum::<f64>() + today_before_now) / typical_week
+        } else {
+            (now - week_start) as f64 / (week_end - week_start) as f64
+        };
+        let expected_by_now = goal.target * share_by_now.clamp(0.0, 1.0);
+
+        let status = if current >= goal.target {
+            "achieved"
+        } else if current >= expected_by_now {
+            "ahead"
+        } else {
+            "behind"
+        };
+        let remaining_days = (7 - today_index) as u32;
+
+        forecasts.push(WeeklyForecast {
+            current: round1(current),
+            projected: round1(projected),
+            target: goal.target,
+            expected_by_now: round1(expected_by_now),
+            status: status.to_string(),
+            weekday_averages: weekday_averages.into_iter().map(round1).collect(),
+            history_weeks: active_weeks.len(),
+            remaining_days,
+            required_per_day: round1((goal.target - current).max(0.0) / remaining_days as f64),
+            goal,
+        });
+    }
+
+    Ok(forecasts)
+}
+
+/// 托盘提示中显示的一行预测，例如「本周专注：已完成 320 / 600，预计 580（落后）」
+pub fn tooltip_line(forecast: &WeeklyForecast) -> String {
+    let status = match forecast.status.as_str() {
+        "achieved" => "已达成",
+        "ahead" => "领先",
+        _ => "落后",
+    };
+    format!(
+        "{}：已完成 {} / {}，预计 {}（{}）",
+        forecast.goal.title,
+        forecast.current.round(),
+        forecast.target.round(),
+        forecast.projected.round(),
+        status
+    )
+}
@@ -0,0 +1,166 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use rusqlite::Result;
+use serde::Serialize;
+
+use crate::db::{Database, Goal, GoalProgress, TimerRecord};
+use crate::time_utils;
+
+const METRICS: [&str; 3] = ["work_minutes", "work_sessions", "break_count"];
+const PERIODS: [&str; 2] = ["daily", "weekly"];
+
+/// 一次目标评估的结果
+#[derive(Debug, Serialize, Clone)]
+pub struct GoalEvaluation {
+    pub goal: Goal,
+    pub progress: GoalProgress,
+    /// 完成比例，可能超过 1
+    pub ratio: f64,
+    pub achieved: bool,
+    /// 本次评估中首次达成
+    pub newly_achieved: bool,
+    /// 与上次保存的进度相比是否有变化
+    pub changed: bool,
+}
+
+pub fn validate(goal: &Goal) -> std::result::Result<(), String> {
+    if goal.title.trim().is_empty() {
+        return Err("Goal title cannot be empty".to_string());
+    }
+    if !METRICS.contains(&goal.metric.as_str()) {
+        return Err(format!("Invalid goal metric: {}", goal.metric));
+    }
+    if !PERIODS.contains(&goal.period.as_str()) {
+        return Err(format!("Invalid goal period: {}", goal.period));
+    }
+    if !goal.target.is_finite() || goal.target <= 0.0 {
+        return Err("Goal target must be a positive number".to_string());
+    }
+    if goal.weekdays.iter().any(|d| !(1..=7).contains(d)) {
+        return Err("Weekdays must be between 1 (Monday) and 7 (Sunday)".to_string());
+    }
+    if let Some(t) = goal.before_time.as_deref() {
+        if time_utils::parse_hh_mm(t).is_none() {
+            return Err(format!("Invalid time (expected HH:MM): {}", t));
+        }
+    }
+    Ok(())
+}
+
+/// 目标在时间戳所在周期的键与起止时间
+fn period_of(goal: &Goal, ts: i64) -> (String, i64, i64) {
+    if goal.period == "weekly" {
+        let (start, end) = time_utils::week_range(ts);
+        (time_utils::week_key(ts), start, end)
+    } else {
+        let (start, end) = time_utils::day_range(ts);
+        (time_utils::day_key(ts), start, end)
+    }
+}
+
+/// 每日目标可以限定在某几个星期几生效
+fn applies_on(goal: &Goal, ts: i64) -> bool {
+    goal.period != "daily"
+        || goal.weekdays.is_empty()
+        || goal.weekdays.contains(&time_utils::local_weekday(ts))
+}
+
+/// 目标指标在给定记录上的取值
+pub fn measure(goal: &Goal, records: &[TimerRecord]) -> f64 {
+    let before = goal.before_time.as_deref().and_then(time_utils::parse_hh_mm);
+    let matching = records.iter().filter(|r| {
+        if let Some(limit) = before {
+            if time_utils::record_minute_of_day(r.end_time, r.tz_offset_minutes) >= limit {
+                return false;
+            }
+        }
+        if goal.metric == "break_count" {
+            return r.mode.as_deref() == Some("break");
+        }
+        r.mode.as_deref() == Some("work")
+            && (goal.category.is_none() || r.category == goal.category)
+    });
+
+    match goal.metric.as_str() {
+        "work_minutes" => matching.map(|r| r.duration).sum::<i64>() as f64 / 60_000.0,
+        _ => matching.count() as f64,
+    }
+}
+
+/// 重新计算时间戳所在周期内所有启用目标的进度并保存
+pub fn evaluate_goals(db: &Database, user_id: i64, ts: i64) -> Result<Vec<GoalEvaluation>> {
+    let evaluations = goal_status(db, user_id, ts, true)?;
+    for evaluation in &evaluations {
+        db.save_goal_progress(&evaluation.progress)?;
+    }
+    Ok(evaluations)
+}
+
+/// 计算时间戳所在周期内所有启用目标的进度，只与已保存的进度比较，不写入数据库
+/// evaluate_goals 保存的进度总是包含手动补录的记录
+pub fn goal_status(db: &Database, user_id: i64, ts: i64, include_manual: bool) -> Result<Vec<GoalEvaluation>> {
+    let now = time_utils::now_ms();
+    let mut records_cache: HashMap<(i64, i64), Vec<TimerRecord>> = HashMap::new();
+    let mut evaluations = Vec::new();
+
+    for goal in db.get_goals(user_id, true)? {
+        if !applies_on(&goal, ts) {
+            continue;
+        }
+
+        let (period_key, period_start, period_end) = period_of(&goal, ts);
+        let records = match records_cache.entry((period_start, period_end)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                // 按记录自己的日期归入周期，以包含在其他时区保存的记录
+                let first = time_utils::local_date(period_start);
+                let last = time_utils::local_date(period_end - 1);
+                let (start, end) = time_utils::padded_date_range(first, last);
+                let records = db
+                    .get_timer_records_between(user_id, start, end)?
+                    .into_iter()
+                    .filter(|r| {
+                        let date = time_utils::record_date(r.end_time, r.tz_offset_minutes);
+                        date >= first && date <= last && (include_manual || r.source != "manual")
+                    })
+                    .collect();
+                entry.insert(records)
+            }
+        };
+        let value = measure(&goal, records);
+
+        let previous = db.get_goal_progress(goal.id, &period_key)?;
+        let achieved = value >= goal.target;
+        let previous_achieved_at = previous.as_ref().and_then(|p| p.achieved_at);
+        let changed = previous
+            .as_ref()
+            .map(|p| p.value != value || p.target != goal.target)
+            .unwrap_or(true);
+
+        let progress = GoalProgress {
+            id: previous.as_ref().map(|p| p.id).unwrap_or(0),
+            goal_id: goal.id,
+            user_id,
+            period_key,
+            period_start,
+            period_end,
+            value,
+            target: goal.target,
+            // 记录被修改导致进度回落时，清除达成时间
+            achieved_at: if achieved { previous_achieved_at.or(Some(now)) } else { None },
+            updated_at: now,
+        };
+
+        evaluations.push(GoalEvaluation {
+            ratio: value / goal.target,
+            newly_achieved: achieved && previous_achieved_at.is_none(),
+            achieved,
+            changed,
+            goal,
+            progress,
+        });
+    }
+
+    Ok(evaluations)
+}
@@ -1,211 +1,1760 @@
 use tauri::{
-    menu::{Menu, MenuItem},
+    menu::{IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
-    Emitter, Manager, image::Image, State,
+    Emitter, Manager, Runtime, image::Image, State,
 };
 use std::sync::Mutex;
-use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::{HashMap, HashSet};
+use tauri_plugin_notification::NotificationExt;
 
+mod achievements;
+mod billing;
+mod charts;
 mod db;
-use db::{Database, TimerRecord};
+mod forecast;
+mod goals;
+mod pdf;
+mod report;
+mod stats;
+mod time_utils;
+mod undo;
+mod work_budget;
+use db::{BreakLog, Database, Goal, Project, RecordFilter, Tag, Task, TimerRecord};
+
+/// 托盘「开始任务」子菜单中最多显示的任务数
+const TRAY_TASK_LIMIT: usize = 10;
 
 // Icon cache wrapper - 存储 RGBA 原始数据
 pub struct IconCache {
     icons: HashMap<String, (Vec<u8>, u32, u32)>, // (rgba_data, width, height)
 }
 
-impl IconCache {
-    fn new() -> Self {
-        IconCache {
-            icons: HashMap::new(),
+impl IconCache {
+    fn new() -> Self {
+        IconCache {
+            icons: HashMap::new(),
+        }
+    }
+    
+    fn get_icon<'a>(&'a self, key: &str) -> Option<Image<'a>> {
+        self.icons.get(key).map(|(data, width, height)| {
+            Image::new(data.as_slice(), *width, *height)
+        })
+    }
+    
+    fn insert(&mut self, key: String, data: Vec<u8>, width: u32, height: u32) {
+        self.icons.insert(key, (data, width, height));
+    }
+}
+
+// Database state wrapper
+pub struct AppState {
+    pub db: Mutex<Database>,
+    pub current_user_id: Mutex<Option<i64>>,
+    pub icon_cache: Mutex<IconCache>,
+    /// 已发出的工作上限提醒，避免同一周期内重复提醒
+    pub budget_alerts: Mutex<HashSet<String>>,
+    /// 前端上报的计时状态，用于拼接托盘提示
+    pub tray_status: Mutex<String>,
+    /// 缓存的托盘预测行，在后台线程中刷新
+    pub tray_forecast: Mutex<Option<String>>,
+    pub tray_forecast_pending: AtomicBool,
+}
+
+// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+#[tauri::command]
+fn greet(name: &str) -> String {
+    format!("Hello, {}! You've been greeted from Rust!", name)
+}
+
+// ==================== Database Commands ====================
+
+#[tauri::command]
+fn db_init_user(app: tauri::AppHandle, device_id: String, state: State<AppState>) -> Result<db::User, String> {
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_init_user").map_err(|e| e.to_string())?;
+    let user = db.get_or_create_user(&device_id).map_err(|e| e.to_string())?;
+    
+    // 缓存当前用户 ID
+    let mut current_user = state.current_user_id.lock().unwrap();
+    *current_user = Some(user.id);
+    
+    // 用户就绪后刷新托盘中的任务列表
+    if let Ok(tasks) = db.get_tasks(user.id, false) {
+        refresh_tray_tasks(&app, &tasks);
+    }
+    
+    purge_expired_trash(&db, user.id);
+    apply_day_start(&db, user.id);
+    refresh_tray_forecast(&app);
+    
+    // 保存时只检查受影响日期附近的连续记录，启动时按完整历史补查一次成就
+    match achievements::check_achievements(&db, user.id, time_utils::now_ms()) {
+        Ok((_, newly)) => announce_achievements(&app, &db, user.id, &newly),
+        Err(e) => eprintln!("✗ Failed to check achievements: {}", e),
+    }
+    
+    Ok(user)
+}
+
+#[tauri::command]
+fn db_update_phone(phone: Option<String>, state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.update_user_phone(user_id, phone).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_get_user(state: State<AppState>) -> Result<db::User, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.get_user_by_id(user_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_get_settings(state: State<AppState>) -> Result<Vec<db::Setting>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.get_settings(user_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_save_setting(app: tauri::AppHandle, key: String, value: String, state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    validate_setting(&key, &value)?;
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_save_setting").map_err(|e| e.to_string())?;
+    db.save_setting(user_id, &key, &value).map_err(|e| e.to_string())?;
+    apply_day_start(&db, user_id);
+    refresh_tray_forecast(&app);
+    Ok(())
+}
+
+/// 窗口位置和尺寸，随窗口移动自动保存，不参与撤销
+const WINDOW_STATE_KEYS: &[&str] = &[
+    "windowX",
+    "windowY",
+    "floatingWindowX",
+    "floatingWindowY",
+    "timerWindowWidth",
+    "timerWindowHeight",
+    "statisticsWindowWidth",
+    "statisticsWindowHeight",
+];
+
+#[tauri::command]
+fn db_save_settings_batch(app: tauri::AppHandle, settings: Vec<(String, String)>, undoable: Option<bool>, state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    for (key, value) in &settings {
+        validate_setting(key, value)?;
+    }
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_save_settings_batch").map_err(|e| e.to_string())?;
+    // 只有用户在设置界面主动保存时才可撤销，且只记录值发生变化的设置（窗口位置等除外）
+    let recorder = if undoable.unwrap_or(false) {
+        let current: HashMap<String, String> = db.get_settings(user_id)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|s| (s.key, s.value))
+            .collect();
+        let keys = settings.iter()
+            .filter(|(key, value)| !WINDOW_STATE_KEYS.contains(&key.as_str()) && current.get(key) != Some(value))
+            .map(|(key, _)| serde_json::Value::from(key.as_str()))
+            .collect();
+        let mut recorder = undo::UndoRecorder::new(user_id, "保存设置");
+        recorder.track(&db, "settings", "key", keys).map_err(|e| e.to_string())?;
+        Some(recorder)
+    } else {
+        None
+    };
+    db.save_settings_batch(user_id, settings).map_err(|e| e.to_string())?;
+    if let Some(recorder) = recorder {
+        commit_undo(recorder, &db);
+    }
+    apply_day_start(&db, user_id);
+    refresh_tray_forecast(&app);
+    Ok(())
+}
+
+#[tauri::command]
+fn db_get_timer_records(limit: i64, state: State<AppState>) -> Result<Vec<TimerRecord>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.get_timer_records(user_id, limit).map_err(|e| e.to_string())
+}
+
+/// 按条件查询记录（时间范围、分类、类型、名称、标签）
+#[tauri::command]
+fn db_query_timer_records(filter: RecordFilter, limit: Option<i64>, state: State<AppState>) -> Result<Vec<TimerRecord>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.query_timer_records(user_id, &filter, limit).map_err(|e| e.to_string())
+}
+
+/// 在名称、备注和标签中全文搜索，按相关度排序，可按结束时间范围过滤
+#[tauri::command]
+fn db_search_records(
+    query: String,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    limit: Option<i64>,
+    state: State<AppState>
+) -> Result<Vec<db::RecordSearchHit>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.search_records(user_id, &query, start_time, end_time, limit.unwrap_or(50))
+        .map_err(|e| e.to_string())
+}
+
+/// 按条件批量修改记录；dry_run 为 true 时只返回将受影响的记录数
+#[tauri::command]
+fn db_bulk_update_records(
+    app: tauri::AppHandle,
+    filter: RecordFilter,
+    operation: db::BulkOperation,
+    dry_run: Option<bool>,
+    state: State<AppState>
+) -> Result<usize, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    if let db::BulkOperation::Tag { add, remove } = &operation {
+        if add.is_empty() && remove.is_empty() {
+            return Err("No tags to add or remove".to_string());
+        }
+    }
+    
+    let db = state.db.lock().unwrap();
+    if dry_run.unwrap_or(false) {
+        return db.count_timer_records(user_id, &filter)
+            .map(|count| count as usize)
+            .map_err(|e| e.to_string());
+    }
+    let _audit = db.audit_scope("db_bulk_update_records").map_err(|e| e.to_string())?;
+    
+    let label = match &operation {
+        db::BulkOperation::Recategorize { .. } => "批量修改分类",
+        db::BulkOperation::Rename { .. } => "批量重命名",
+        db::BulkOperation::Tag { .. } => "批量修改标签",
+        db::BulkOperation::Delete => "批量删除",
+    };
+    let ids: Vec<serde_json::Value> = db.query_timer_records(user_id, &filter, None)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|r| serde_json::Value::from(r.id))
+        .collect();
+    let mut recorder = undo::UndoRecorder::new(user_id, label);
+    if matches!(operation, db::BulkOperation::Tag { .. }) {
+        recorder.track(&db, "record_tags", "record_id", ids.clone()).map_err(|e| e.to_string())?;
+    }
+    recorder.track(&db, "timer_records", "id", ids).map_err(|e| e.to_string())?;
+    
+    let affected = db.bulk_update_records(user_id, &filter, &operation).map_err(|e| e.to_string())?;
+    commit_undo(recorder, &db);
+    if affected > 0 && !matches!(operation, db::BulkOperation::Rename { .. } | db::BulkOperation::Tag { .. }) {
+        evaluate_goals_and_notify(&app, &db, user_id, time_utils::now_ms());
+    }
+    Ok(affected)
+}
+
+#[tauri::command]
+fn db_add_timer_record(app: tauri::AppHandle, record: TimerRecord, state: State<AppState>) -> Result<(), String> {
+    if matches!(record.focus_rating, Some(r) if !(1..=5).contains(&r)) {
+        return Err("focus_rating must be an integer between 1 and 5".to_string());
+    }
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_add_timer_record").map_err(|e| e.to_string())?;
+    db.add_timer_record(&record).map_err(|e| e.to_string())?;
+    
+    // 记录变化后重新计算目标进度和成就
+    evaluate_goals_and_notify(&app, &db, record.user_id, record.end_time);
+    check_achievements_and_notify(&app, &db, record.user_id, record.end_time, record.tz_offset_minutes);
+    Ok(())
+}
+
+/// 手动补录一段过去的时间，与同类记录重叠时返回冲突且不保存（force 为 true 时仍然保存）
+#[tauri::command]
+fn db_add_manual_record(app: tauri::AppHandle, entry: db::ManualEntry, state: State<AppState>) -> Result<db::ManualEntryResult, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    if !["work", "break"].contains(&entry.mode.as_str()) {
+        return Err(format!("Invalid mode: {}", entry.mode));
+    }
+    if matches!(entry.focus_rating, Some(r) if !(1..=5).contains(&r)) {
+        return Err("focus_rating must be an integer between 1 and 5".to_string());
+    }
+    let end_time = match (entry.end_time, entry.duration) {
+        (Some(end), None) => end,
+        (None, Some(duration)) if duration > 0 => entry.start_time + duration,
+        (None, Some(_)) => return Err("duration must be positive".to_string()),
+        _ => return Err("Exactly one of end_time and duration is required".to_string()),
+    };
+    validate_record_times(entry.start_time, end_time)?;
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_add_manual_record").map_err(|e| e.to_string())?;
+    let conflicts = db.find_overlapping_records(user_id, Some(&entry.mode), entry.start_time, end_time, None)
+        .map_err(|e| e.to_string())?;
+    if !conflicts.is_empty() && !entry.force {
+        return Ok(db::ManualEntryResult { record: None, conflicts });
+    }
+    
+    let now = time_utils::now_ms();
+    let record = TimerRecord {
+        id: new_record_id("manual"),
+        user_id,
+        // 手动补录没有固定时长，按正计时记录处理，不计入番茄数
+        record_type: "stopwatch".to_string(),
+        mode: Some(entry.mode),
+        name: entry.name.map(|n| n.trim().to_string()).filter(|n| !n.is_empty()),
+        category: entry.category,
+        start_time: entry.start_time,
+        end_time,
+        duration: end_time - entry.start_time,
+        created_at: now,
+        reminder_count: 0,
+        reminder_times: Vec::new(),
+        note: entry.note,
+        focus_rating: entry.focus_rating,
+        task_id: entry.task_id,
+        project_id: entry.project_id,
+        billable: entry.billable,
+        tags: entry.tags,
+        source: "manual".to_string(),
+        tz_offset_minutes: None,
+        split_from: None,
+        day: String::new(),
+    };
+    db.add_timer_record(&record).map_err(|e| e.to_string())?;
+    
+    evaluate_goals_and_notify(&app, &db, user_id, record.end_time);
+    check_achievements_and_notify(&app, &db, user_id, record.end_time, record.tz_offset_minutes);
+    let record = db.get_timer_record(user_id, &record.id).map_err(|e| e.to_string())?;
+    Ok(db::ManualEntryResult { record, conflicts })
+}
+
+/// 后端生成的记录 ID，格式与前端一致：时间戳-后缀
+fn new_record_id(kind: &str) -> String {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .subsec_nanos();
+    format!("{}-{}{:x}", time_utils::now_ms(), kind, nanos)
+}
+
+/// 在 at 处把一条记录拆成两条，返回前后两段
+#[tauri::command]
+fn db_split_timer_record(
+    app: tauri::AppHandle,
+    record_id: String,
+    at: i64,
+    first: Option<db::RecordPart>,
+    second: Option<db::RecordPart>,
+    state: State<AppState>
+) -> Result<(TimerRecord, TimerRecord), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_split_timer_record").map_err(|e| e.to_string())?;
+    let record = db.get_timer_record(user_id, &record_id)
+        .map_err(|e| e.to_string())?
+        .ok_or("Timer record not found")?;
+    if at <= record.start_time || at >= record.end_time {
+        return Err("Split time must be between the record's start and end".to_string());
+    }
+    
+    let parts = db.split_timer_record(
+        user_id,
+        &record_id,
+        at,
+        &new_record_id("split"),
+        first.unwrap_or_default(),
+        second.unwrap_or_default(),
+    ).map_err(|e| e.to_string())?;
+    // 前半段可能落在另一个周期（例如跨过一天开始的时刻），两个周期都要重新评估
+    evaluate_goals_and_notify(&app, &db, user_id, at);
+    evaluate_goals_and_notify(&app, &db, user_id, record.end_time);
+    Ok(parts)
+}
+
+/// 合并同一模式下相邻的多条记录，返回合并后的记录
+#[tauri::command]
+fn db_merge_timer_records(app: tauri::AppHandle, record_ids: Vec<String>, state: State<AppState>) -> Result<TimerRecord, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let mut ids = record_ids;
+    ids.sort();
+    ids.dedup();
+    if ids.len() < 2 {
+        return Err("At least two records are required to merge".to_string());
+    }
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_merge_timer_records").map_err(|e| e.to_string())?;
+    let mut records = Vec::with_capacity(ids.len());
+    for id in &ids {
+        let record = db.get_timer_record(user_id, id)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Timer record not found: {}", id))?;
+        records.push(record);
+    }
+    if records.iter().any(|r| r.mode != records[0].mode) {
+        return Err("Only records of the same mode can be merged".to_string());
+    }
+    
+    // 相邻：合并范围内不能夹着其他同类记录
+    let start = records.iter().map(|r| r.start_time).min().unwrap_or_default();
+    let end = records.iter().map(|r| r.end_time).max().unwrap_or_default();
+    validate_record_times(start, end)?;
+    let between = db.find_overlapping_records(user_id, records[0].mode.as_deref(), start, end, None)
+        .map_err(|e| e.to_string())?;
+    if let Some(other) = between.iter().find(|r| !ids.contains(&r.id)) {
+        return Err(format!("Records are not adjacent: {} lies between them", other.id));
+    }
+    
+    let merged = db.merge_timer_records(user_id, &ids).map_err(|e| e.to_string())?;
+    for r in &records {
+        if time_utils::record_date(r.end_time, r.tz_offset_minutes)
+            != time_utils::record_date(merged.end_time, merged.tz_offset_minutes)
+        {
+            evaluate_goals_and_notify(&app, &db, user_id, r.end_time);
+        }
+    }
+    evaluate_goals_and_notify(&app, &db, user_id, merged.end_time);
+    Ok(merged)
+}
+
+/// 记录的时间段必须有效且已经结束
+fn validate_record_times(start: i64, end: i64) -> Result<(), String> {
+    if end <= start {
+        return Err("end_time must be later than start_time".to_string());
+    }
+    if end > time_utils::now_ms() {
+        return Err("end_time cannot be in the future".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn db_update_timer_record(
+    app: tauri::AppHandle,
+    record_id: String,
+    updates: std::collections::HashMap<String, serde_json::Value>,
+    state: State<AppState>
+) -> Result<db::TimerRecordUpdateResult, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let name = updates.get("name")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    
+    let category = updates.get("category")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    
+    // 备注和评分允许传 null 清空
+    let note = match updates.get("note") {
+        Some(v) if v.is_null() => Some(None),
+        Some(v) => Some(Some(v.as_str().ok_or("note must be a string")?.to_string())),
+        None => None,
+    };
+    
+    let focus_rating = match updates.get("focus_rating") {
+        Some(v) if v.is_null() => Some(None),
+        Some(v) => match v.as_i64() {
+            Some(r) if (1..=5).contains(&r) => Some(Some(r)),
+            _ => return Err("focus_rating must be an integer between 1 and 5".to_string()),
+        },
+        None => None,
+    };
+    
+    let task_id = match updates.get("task_id") {
+        Some(v) if v.is_null() => Some(None),
+        Some(v) => Some(Some(v.as_i64().ok_or("task_id must be an integer")?)),
+        None => None,
+    };
+    
+    let project_id = match updates.get("project_id") {
+        Some(v) if v.is_null() => Some(None),
+        Some(v) => Some(Some(v.as_i64().ok_or("project_id must be an integer")?)),
+        None => None,
+    };
+    
+    let billable = match updates.get("billable") {
+        Some(v) => Some(v.as_bool().ok_or("billable must be a boolean")?),
+        None => None,
+    };
+    
+    let start_time = match updates.get("start_time") {
+        Some(v) => Some(v.as_i64().ok_or("start_time must be an integer")?),
+        None => None,
+    };
+    
+    let end_time = match updates.get("end_time") {
+        Some(v) => Some(v.as_i64().ok_or("end_time must be an integer")?),
+        None => None,
+    };
+    
+    // 存在时间冲突时默认不保存，force 为 true 时仍然保存
+    let force = updates.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_update_timer_record").map_err(|e| e.to_string())?;
+    let existing = db.get_timer_record(user_id, &record_id)
+        .map_err(|e| e.to_string())?
+        .ok_or("Timer record not found")?;
+    
+    let mut conflicts = Vec::new();
+    let mut times = None;
+    if start_time.is_some() || end_time.is_some() {
+        let start = start_time.unwrap_or(existing.start_time);
+        let end = end_time.unwrap_or(existing.end_time);
+        validate_record_times(start, end)?;
+        
+        conflicts = db.find_overlapping_records(user_id, existing.mode.as_deref(), start, end, Some(&record_id))
+            .map_err(|e| e.to_string())?;
+        if !conflicts.is_empty() && !force {
+            return Ok(db::TimerRecordUpdateResult { updated: false, conflicts });
+        }
+        times = Some((start, end));
+    }
+    
+    let update = db::TimerRecordUpdate {
+        name,
+        category,
+        note,
+        focus_rating,
+        task_id,
+        project_id,
+        billable,
+        times,
+    };
+    
+    let mut recorder = undo::UndoRecorder::new(user_id, "编辑记录");
+    recorder.track(&db, "timer_records", "id", vec![serde_json::Value::from(record_id.as_str())])
+        .map_err(|e| e.to_string())?;
+    db.update_timer_record(user_id, &record_id, update).map_err(|e| e.to_string())?;
+    commit_undo(recorder, &db);
+    
+    // 结束时间跨周期移动时，原周期的目标进度也需要更新
+    if let Some((_, end)) = times {
+        // 编辑不改变记录的时区偏移
+        let offset = existing.tz_offset_minutes;
+        if time_utils::record_date(end, offset) != time_utils::record_date(existing.end_time, offset) {
+            evaluate_goals_and_notify(&app, &db, user_id, existing.end_time);
+        }
+        evaluate_goals_and_notify(&app, &db, user_id, end);
+    } else {
+        evaluate_goals_and_notify(&app, &db, user_id, existing.end_time);
+    }
+    Ok(db::TimerRecordUpdateResult { updated: true, conflicts })
+}
+
+#[tauri::command]
+fn db_get_focus_stats(
+    start_time: i64,
+    end_time: i64,
+    include_manual: Option<bool>,
+    state: State<AppState>
+) -> Result<stats::FocusStats, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let ratings = db.get_focus_ratings(user_id, start_time, end_time, include_manual.unwrap_or(true)).map_err(|e| e.to_string())?;
+    Ok(stats::focus_stats(&ratings))
+}
+
+#[tauri::command]
+fn db_delete_timer_record(record_id: String, state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_delete_timer_record").map_err(|e| e.to_string())?;
+    db.delete_timer_record(user_id, &record_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_clear_timer_records(state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_clear_timer_records").map_err(|e| e.to_string())?;
+    db.clear_timer_records(user_id).map_err(|e| e.to_string())
+}
+
+/// 回收站内容（先清除超过保留期限的条目）
+#[tauri::command]
+fn db_get_trash(state: State<AppState>) -> Result<db::Trash, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    purge_expired_trash(&db, user_id);
+    db.get_trash(user_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_restore_from_trash(app: tauri::AppHandle, selection: db::TrashSelection, state: State<AppState>) -> Result<usize, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_restore_from_trash").map_err(|e| e.to_string())?;
+    let restored = db.restore_from_trash(user_id, &selection).map_err(|e| e.to_string())?;
+    if !selection.record_ids.is_empty() {
+        evaluate_goals_and_notify(&app, &db, user_id, time_utils::now_ms());
+    }
+    Ok(restored)
+}
+
+#[tauri::command]
+fn db_purge_from_trash(selection: db::TrashSelection, state: State<AppState>) -> Result<usize, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_purge_from_trash").map_err(|e| e.to_string())?;
+    db.purge_from_trash(user_id, &selection).map_err(|e| e.to_string())
+}
+
+/// 清空回收站
+#[tauri::command]
+fn db_empty_trash(state: State<AppState>) -> Result<usize, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_empty_trash").map_err(|e| e.to_string())?;
+    db.purge_trash_before(user_id, i64::MAX).map_err(|e| e.to_string())
+}
+
+/// 某条记录的变更历史（来自审计日志）
+#[tauri::command]
+fn db_get_record_history(record_id: String, state: State<AppState>) -> Result<Vec<db::AuditEntry>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.get_audit_history(user_id, "timer_records", &record_id).map_err(|e| e.to_string())
+}
+
+/// 撤销最近一次操作，没有可撤销的操作时返回 None
+#[tauri::command]
+fn db_undo(app: tauri::AppHandle, state: State<AppState>) -> Result<Option<db::UndoEntry>, String> {
+    apply_undo(&app, &state, false)
+}
+
+/// 重做最近一次撤销的操作
+#[tauri::command]
+fn db_redo(app: tauri::AppHandle, state: State<AppState>) -> Result<Option<db::UndoEntry>, String> {
+    apply_undo(&app, &state, true)
+}
+
+#[tauri::command]
+fn db_get_undo_state(state: State<AppState>) -> Result<db::UndoState, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.get_undo_state(user_id).map_err(|e| e.to_string())
+}
+
+fn apply_undo(app: &tauri::AppHandle, state: &State<AppState>, redo: bool) -> Result<Option<db::UndoEntry>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope(if redo { "db_redo" } else { "db_undo" }).map_err(|e| e.to_string())?;
+    let entry = match db.apply_undo(user_id, redo).map_err(|e| e.to_string())? {
+        Some(db::UndoOutcome::Applied(entry)) => Some(entry),
+        Some(db::UndoOutcome::Stale(label)) => {
+            return Err(format!(
+                "无法{}「{}」：相关数据之后已被修改",
+                if redo { "重做" } else { "撤销" },
+                label
+            ));
+        }
+        None => None,
+    };
+    if let Some(entry) = &entry {
+        if entry.tables.iter().any(|t| t == "settings") {
+            apply_day_start(&db, user_id);
+        }
+        let _ = app.emit("undo-applied", entry);
+        evaluate_goals_and_notify(app, &db, user_id, time_utils::now_ms());
+    }
+    Ok(entry)
+}
+
+/// 读取“一天开始的时刻”设置（dayStartTime，HH:MM），用于按日统计
+fn apply_day_start(db: &Database, user_id: i64) {
+    let minutes = db.get_setting_json(user_id, "dayStartTime")
+        .ok()
+        .flatten()
+        .and_then(|v| v.as_str().and_then(time_utils::parse_hh_mm))
+        .unwrap_or(0);
+    time_utils::set_day_start_minutes(minutes);
+}
+
+/// 校验 dayStartTime 设置的值（JSON 字符串 "HH:MM"）
+fn validate_setting(key: &str, value: &str) -> Result<(), String> {
+    if key == "dayStartTime" {
+        let valid = serde_json::from_str::<String>(value)
+            .ok()
+            .and_then(|v| time_utils::parse_hh_mm(&v))
+            .is_some();
+        if !valid {
+            return Err(format!("Invalid day start time (expected HH:MM): {}", value));
+        }
+    }
+    Ok(())
+}
+
+/// 按 trashRetentionDays 设置（默认 30 天，0 表示永久保留）清除回收站中过期的条目
+fn purge_expired_trash(db: &Database, user_id: i64) {
+    let days = db.get_setting_json(user_id, "trashRetentionDays")
+        .ok()
+        .flatten()
+        .and_then(|v| v.as_i64())
+        .unwrap_or(30);
+    if days <= 0 {
+        return;
+    }
+    
+    let before = time_utils::now_ms() - days * 24 * 3_600_000;
+    match db.purge_trash_before(user_id, before) {
+        Ok(0) => {}
+        Ok(n) => println!("✓ Purged {} expired trash items", n),
+        Err(e) => eprintln!("✗ Failed to purge trash: {}", e),
+    }
+}
+
+#[tauri::command]
+fn db_get_custom_categories(state: State<AppState>) -> Result<Vec<db::CustomCategory>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.get_custom_categories(user_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_add_custom_category(value: String, label: String, icon: String, state: State<AppState>) -> Result<i64, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_add_custom_category").map_err(|e| e.to_string())?;
+    db.add_custom_category(user_id, value, label, icon).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_update_custom_category(value: String, label: String, icon: String, state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_update_custom_category").map_err(|e| e.to_string())?;
+    db.update_custom_category(user_id, &value, label, icon).map_err(|e| e.to_string())
+}
+
+/// 更新分类的父分类、颜色、置顶和归档状态
+#[tauri::command]
+fn db_update_category_meta(
+    value: String,
+    updates: HashMap<String, serde_json::Value>,
+    state: State<AppState>
+) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    // parent_value / color 允许传 null 清空
+    let parent_value = updates.get("parent_value")
+        .map(|v| v.as_str().map(|s| s.to_string()));
+    let color = match updates.get("color") {
+        Some(v) if v.is_null() => Some(None),
+        Some(v) => match v.as_str() {
+            Some(c) if is_hex_color(c) => Some(Some(c.to_string())),
+            _ => return Err("color must be in #RRGGBB format".to_string()),
+        },
+        None => None,
+    };
+    let pinned = updates.get("pinned").and_then(|v| v.as_bool());
+    let archived = updates.get("archived").and_then(|v| v.as_bool());
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_update_category_meta").map_err(|e| e.to_string())?;
+    if let Some(Some(parent)) = &parent_value {
+        let categories = db.get_custom_categories(user_id).map_err(|e| e.to_string())?;
+        if creates_category_cycle(&categories, &value, parent) {
+            return Err("A category cannot be nested under itself or its descendants".to_string());
+        }
+    }
+    
+    let update = db::CategoryMetaUpdate {
+        parent_value,
+        color,
+        pinned,
+        archived,
+    };
+    db.update_category_meta(user_id, &value, update).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_reorder_custom_categories(values: Vec<String>, state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_reorder_custom_categories").map_err(|e| e.to_string())?;
+    db.reorder_custom_categories(user_id, &values).map_err(|e| e.to_string())
+}
+
+/// 分类工作时长，子分类时长汇总到父分类
+#[tauri::command]
+fn db_get_category_rollup(
+    start_time: i64,
+    end_time: i64,
+    include_manual: Option<bool>,
+    state: State<AppState>
+) -> Result<Vec<stats::CategoryRollup>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let categories = db.get_custom_categories(user_id).map_err(|e| e.to_string())?;
+    let durations = db.get_category_durations(user_id, start_time, end_time, include_manual.unwrap_or(true)).map_err(|e| e.to_string())?;
+    Ok(stats::category_rollup(&categories, &durations))
+}
+
+fn is_hex_color(value: &str) -> bool {
+    value.len() == 7 && value.starts_with('#') && value[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// 沿新父分类向上查找，若经过自身则会形成环
+fn creates_category_cycle(categories: &[db::CustomCategory], value: &str, new_parent: &str) -> bool {
+    let mut current = Some(new_parent.to_string());
+    let mut steps = 0;
+    while let Some(c) = current {
+        if c == value || steps > categories.len() {
+            return true;
+        }
+        current = categories.iter()
+            .find(|cat| cat.value == c)
+            .and_then(|cat| cat.parent_value.clone());
+        steps += 1;
+    }
+    false
+}
+
+/// 跟踪把 source 并入 target 时会被修改的分类、记录、任务和目标
+fn track_category_change(
+    db: &Database,
+    user_id: i64,
+    label: &str,
+    source: &str,
+    target: &str
+) -> rusqlite::Result<undo::UndoRecorder> {
+    use serde_json::Value;
+    
+    let mut recorder = undo::UndoRecorder::new(user_id, label);
+    let mut values = vec![Value::from(source), Value::from(target)];
+    for child in recorder.keys_matching(db, "custom_categories", "parent_value", vec![Value::from(source)], "value")? {
+        if !values.contains(&child) {
+            values.push(child);
+        }
+    }
+    recorder.track(db, "custom_categories", "value", values)?;
+    // 分类的提醒规则随之移动
+    recorder.track(db, "settings", "key", vec![Value::from("categoryReminderRules")])?;
+    for table in ["timer_records", "tasks", "goals"] {
+        let ids = recorder.keys_matching(db, table, "category", vec![Value::from(source)], "id")?;
+        recorder.track(db, table, "id", ids)?;
+    }
+    Ok(recorder)
+}
+
+/// 写入撤销日志；操作本身已经完成，失败时只记录错误
+fn commit_undo(recorder: undo::UndoRecorder, db: &Database) {
+    if let Err(e) = recorder.commit(db) {
+        eprintln!("✗ Failed to record undo entry: {}", e);
+    }
+}
+
+/// 前端内置的预设分类，数据库中可能没有对应的行
+const BUILTIN_CATEGORIES: &[&str] = &["work", "study", "entertainment", "meeting"];
+
+fn require_category(categories: &[db::CustomCategory], value: &str) -> Result<(), String> {
+    if BUILTIN_CATEGORIES.contains(&value) || categories.iter().any(|c| c.value == value) {
+        Ok(())
+    } else {
+        Err(format!("Category not found: {}", value))
+    }
+}
+
+/// 按最近几周同一星期几的平均值预测本周各每周目标的结果
+#[tauri::command]
+fn db_get_weekly_forecast(weeks: Option<usize>, state: State<AppState>) -> Result<Vec<forecast::WeeklyForecast>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let weeks = weeks.unwrap_or(forecast::DEFAULT_HISTORY_WEEKS).clamp(1, 26);
+    let db = state.db.lock().unwrap();
+    forecast::weekly_forecast(&db, user_id, time_utils::now_ms(), weeks).map_err(|e| e.to_string())
+}
+
+/// 某一年每天的工作分钟数（贡献图）
+#[tauri::command]
+fn db_get_yearly_heatmap(year: i32, include_manual: Option<bool>, state: State<AppState>) -> Result<stats::YearHeatmap, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let first = chrono::NaiveDate::from_ymd_opt(year, 1, 1).ok_or("Invalid year")?;
+    let last = chrono::NaiveDate::from_ymd_opt(year, 12, 31).ok_or("Invalid year")?;
+    let (start, end) = time_utils::padded_date_range(first, last);
+    
+    let db = state.db.lock().unwrap();
+    let entries = db.get_work_entries(user_id, start, end, include_manual.unwrap_or(true)).map_err(|e| e.to_string())?;
+    Ok(stats::yearly_heatmap(&entries, year))
+}
+
+/// 将时间范围 [start_time, end_time) 转为首尾日期，最多 10 年
+fn date_span(start_time: i64, end_time: i64) -> Result<(chrono::NaiveDate, chrono::NaiveDate), String> {
+    if end_time <= start_time {
+        return Err("end_time must be later than start_time".to_string());
+    }
+    let first = time_utils::local_date(start_time);
+    let last = time_utils::local_date(end_time - 1);
+    if (last - first).num_days() > 3660 {
+        return Err("Time range cannot exceed 10 years".to_string());
+    }
+    Ok((first, last))
+}
+
+/// 每日工作时长和休息执行率及其 7 天、30 天滚动平均
+#[tauri::command]
+fn db_get_trends(
+    start_time: i64,
+    end_time: i64,
+    include_manual: Option<bool>,
+    state: State<AppState>
+) -> Result<stats::Trends, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let (first, last) = date_span(start_time, end_time)?;
+    // 多取 29 天用于填满第一天的 30 天窗口
+    let (start, end) = time_utils::padded_date_range(first - chrono::Duration::days(29), last);
+    
+    let db = state.db.lock().unwrap();
+    let entries = db.get_work_entries(user_id, start, end, include_manual.unwrap_or(true)).map_err(|e| e.to_string())?;
+    let logs = db.get_break_logs(user_id, start, end).map_err(|e| e.to_string())?;
+    Ok(stats::trends(&entries, &logs, first, last))
+}
+
+/// 各分类逐月工作时长及环比
+#[tauri::command]
+fn db_get_category_monthly(
+    start_time: i64,
+    end_time: i64,
+    include_manual: Option<bool>,
+    state: State<AppState>
+) -> Result<stats::CategoryMonthly, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let (first, last) = date_span(start_time, end_time)?;
+    let (start, end) = time_utils::padded_date_range(first, last);
+    
+    let db = state.db.lock().unwrap();
+    let entries = db.get_work_entries(user_id, start, end, include_manual.unwrap_or(true)).map_err(|e| e.to_string())?;
+    Ok(stats::category_monthly(&entries, first, last))
+}
+
+/// 将统计图表保存为图片，格式由 path 的扩展名（.svg / .png）决定
+/// kind: category_pie（时间范围内的分类占比）、daily_bars（时间范围内每天的工作时长）、heatmap（year 年的热力图）
+#[tauri::command]
+fn db_export_chart(
+    kind: String,
+    path: String,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    year: Option<i32>,
+    include_manual: Option<bool>,
+    state: State<AppState>
+) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let include_manual = include_manual.unwrap_or(true);
+    // 未指定时间范围时默认为本周
+    let (week_start, week_end) = time_utils::week_range(time_utils::now_ms());
+    let start_time = start_time.unwrap_or(week_start);
+    let end_time = end_time.unwrap_or(week_end);
+    
+    let db = state.db.lock().unwrap();
+    let chart = match kind.as_str() {
+        "category_pie" => {
+            date_span(start_time, end_time)?;
+            charts::category_pie_chart(&db, user_id, start_time, end_time, include_manual)
+        }
+        "daily_bars" => {
+            let (first, last) = date_span(start_time, end_time)?;
+            if (last - first).num_days() >= 366 {
+                return Err("Time range cannot exceed one year".to_string());
+            }
+            charts::daily_bars_chart(&db, user_id, first, last, include_manual)
+        }
+        "heatmap" => {
+            let year = year.unwrap_or_else(|| chrono::Datelike::year(&time_utils::local_date(time_utils::now_ms())));
+            chrono::NaiveDate::from_ymd_opt(year, 1, 1).ok_or("Invalid year")?;
+            charts::year_heatmap_chart(&db, user_id, year, include_manual)
+        }
+        _ => return Err(format!("Unknown chart kind: {}", kind)),
+    }
+    .map_err(|e| e.to_string())?;
+    drop(db);
+    
+    chart.save(std::path::Path::new(&path))
+}
+
+/// 生成 start_date ~ end_date（YYYY-MM-DD，含首尾两天）的 PDF 报告并写入 path
+#[tauri::command]
+fn db_export_report(start_date: String, end_date: String, path: String, state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let first = chrono::NaiveDate::parse_from_str(&start_date, "%Y-%m-%d").map_err(|_| "Invalid start_date")?;
+    let last = chrono::NaiveDate::parse_from_str(&end_date, "%Y-%m-%d").map_err(|_| "Invalid end_date")?;
+    if last < first {
+        return Err("end_date cannot be earlier than start_date".to_string());
+    }
+    if (last - first).num_days() >= 366 {
+        return Err("Report period cannot exceed one year".to_string());
+    }
+    
+    // 只在汇总数据时持有数据库锁，排版和写文件在锁外进行
+    let report = {
+        let db = state.db.lock().unwrap();
+        report::build_report(&db, user_id, first, last, time_utils::now_ms()).map_err(|e| e.to_string())?
+    };
+    report::write_report(&report, std::path::Path::new(&path))
+}
+
+/// 修改分类的值，已有记录随之更新，返回受影响的记录数
+#[tauri::command]
+fn db_rename_custom_category(old_value: String, new_value: String, state: State<AppState>) -> Result<usize, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let new_value = new_value.trim().to_string();
+    if new_value.is_empty() {
+        return Err("Category value cannot be empty".to_string());
+    }
+    if new_value == old_value {
+        return Ok(0);
+    }
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_rename_custom_category").map_err(|e| e.to_string())?;
+    let categories = db.get_custom_categories(user_id).map_err(|e| e.to_string())?;
+    require_category(&categories, &old_value)?;
+    if BUILTIN_CATEGORIES.contains(&new_value.as_str()) || categories.iter().any(|c| c.value == new_value) {
+        return Err(format!("Category already exists: {}", new_value));
+    }
+    db.rename_custom_category(user_id, &old_value, &new_value).map_err(|e| e.to_string())
+}
+
+/// 将 source 合并到 target，返回受影响的记录数
+#[tauri::command]
+fn db_merge_custom_categories(
+    app: tauri::AppHandle,
+    source: String,
+    target: String,
+    state: State<AppState>
+) -> Result<usize, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    if source == target {
+        return Err("Cannot merge a category into itself".to_string());
+    }
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_merge_custom_categories").map_err(|e| e.to_string())?;
+    let categories = db.get_custom_categories(user_id).map_err(|e| e.to_string())?;
+    require_category(&categories, &source)?;
+    require_category(&categories, &target)?;
+    let recorder = track_category_change(&db, user_id, "合并分类", &source, &target).map_err(|e| e.to_string())?;
+    let affected = db.merge_custom_categories(user_id, &source, &target).map_err(|e| e.to_string())?;
+    commit_undo(recorder, &db);
+    evaluate_goals_and_notify(&app, &db, user_id, time_utils::now_ms());
+    Ok(affected)
+}
+
+/// 删除分类，reassign_to 为空时相关记录变为未分类，返回受影响的记录数
+#[tauri::command]
+fn db_delete_custom_category(
+    app: tauri::AppHandle,
+    value: String,
+    reassign_to: Option<String>,
+    state: State<AppState>
+) -> Result<usize, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let _audit = db.audit_scope("db_delete_custom_category").map_err(|e| e.to_string())?;
+    let mut recorder = None;
+    if let Some(target) = reassign_to.as_deref() {
+        if target == value {
+            return Err("Cannot reassign records to the deleted category".to_string());
+        }
+        let categories = db.get_custom_categories(user_id).map_err(|e| e.to_string())?;
+        require_category(&categories, target)?;
+        recorder = Some(track_category_change(&db, user_id, "删除分类", &value, target).map_err(|e| e.to_string())?);
+    }
+    let affected = db.delete_custom_category(user_id, &value, reassign_to.as_deref()).map_err(|e| e.to_string())?;
+    if let Some(recorder) = recorder {
+        commit_undo(recorder, &db);
+    }
+    evaluate_goals_and_notify(&app, &db, user_id, time_utils::now_ms());
+    Ok(affected)
+}
+
+#[tauri::command]
+fn db_add_break_log(app: tauri::AppHandle, log: BreakLog, state: State<AppState>) -> Result<i64, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let id = db.add_break_log(user_id, &log).map_err(|e| e.to_string())?;
+    check_achievements_and_notify(&app, &db, user_id, log.end_time, log.tz_offset_minutes);
+    Ok(id)
+}
+
+#[tauri::command]
+fn db_get_break_logs(start_time: i64, end_time: i64, state: State<AppState>) -> Result<Vec<BreakLog>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.get_break_logs(user_id, start_time, end_time).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_get_break_compliance(start_time: i64, end_time: i64, state: State<AppState>) -> Result<stats::BreakCompliance, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let logs = db.get_break_logs(user_id, start_time, end_time).map_err(|e| e.to_string())?;
+    Ok(stats::break_compliance(&logs))
+}
+
+#[tauri::command]
+fn db_log_interruption(
+    kind: String,
+    reason: Option<String>,
+    occurred_at: Option<i64>,
+    state: State<AppState>
+) -> Result<db::Interruption, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    if kind != "internal" && kind != "external" {
+        return Err(format!("Invalid interruption kind: {}", kind));
+    }
+    
+    let db = state.db.lock().unwrap();
+    db.log_interruption(user_id, &kind, reason, occurred_at.unwrap_or_else(time_utils::now_ms))
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_get_interruptions(
+    record_id: Option<String>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    state: State<AppState>
+) -> Result<Vec<db::Interruption>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.get_interruptions(
+        user_id,
+        record_id.as_deref(),
+        start_time.unwrap_or(0),
+        end_time.unwrap_or(i64::MAX),
+    ).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_delete_interruption(id: i64, state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.delete_interruption(user_id, id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_get_interruption_stats(
+    start_time: i64,
+    end_time: i64,
+    include_manual: Option<bool>,
+    state: State<AppState>
+) -> Result<stats::InterruptionStats, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    let include_manual = include_manual.unwrap_or(true);
+    let sessions = db.get_session_interruptions(user_id, start_time, end_time, include_manual).map_err(|e| e.to_string())?;
+    let reasons = db.get_interruption_reason_counts(user_id, start_time, end_time, include_manual).map_err(|e| e.to_string())?;
+    Ok(stats::interruption_stats(sessions, reasons, 5))
+}
+
+/// 托盘菜单快速记录打断（不带原因）
+fn log_interruption_from_tray(app: &tauri::AppHandle, kind: &str) {
+    let state = app.state::<AppState>();
+    let user_id = match *state.current_user_id.lock().unwrap() {
+        Some(id) => id,
+        None => {
+            eprintln!("✗ Cannot log interruption: user not initialized");
+            return;
         }
+    };
+    
+    let db = state.db.lock().unwrap();
+    match db.log_interruption(user_id, kind, None, time_utils::now_ms()) {
+        Ok(interruption) => {
+            println!("✓ Interruption logged from tray: {}", kind);
+            let _ = app.emit("interruption-logged", &interruption);
+        }
+        Err(e) => eprintln!("✗ Failed to log interruption: {}", e),
     }
+}
+
+// ==================== Goal Commands ====================
+
+#[tauri::command]
+fn db_get_goals(state: State<AppState>) -> Result<Vec<Goal>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
     
-    fn get_icon<'a>(&'a self, key: &str) -> Option<Image<'a>> {
-        self.icons.get(key).map(|(data, width, height)| {
-            Image::new(data.as_slice(), *width, *height)
-        })
+    let db = state.db.lock().unwrap();
+    db.get_goals(user_id, false).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_add_goal(app: tauri::AppHandle, goal: Goal, state: State<AppState>) -> Result<i64, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    goals::validate(&goal)?;
+    let db = state.db.lock().unwrap();
+    let id = db.add_goal(user_id, &goal).map_err(|e| e.to_string())?;
+    evaluate_goals_and_notify(&app, &db, user_id, time_utils::now_ms());
+    Ok(id)
+}
+
+#[tauri::command]
+fn db_update_goal(app: tauri::AppHandle, goal: Goal, state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    goals::validate(&goal)?;
+    let db = state.db.lock().unwrap();
+    db.update_goal(user_id, &goal).map_err(|e| e.to_string())?;
+    evaluate_goals_and_notify(&app, &db, user_id, time_utils::now_ms());
+    Ok(())
+}
+
+#[tauri::command]
+fn db_delete_goal(goal_id: i64, state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.delete_goal(user_id, goal_id).map_err(|e| e.to_string())
+}
+
+/// 当前周期内所有启用目标的进度（不发送事件）
+#[tauri::command]
+fn db_get_goal_status(include_manual: Option<bool>, state: State<AppState>) -> Result<Vec<goals::GoalEvaluation>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    // 只读查询，进度由记录和目标变化时的 evaluate_goals_and_notify 保存
+    goals::goal_status(&db, user_id, time_utils::now_ms(), include_manual.unwrap_or(true)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_get_goal_history(
+    goal_id: Option<i64>,
+    start_time: i64,
+    end_time: i64,
+    state: State<AppState>
+) -> Result<Vec<db::GoalProgress>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    db.get_goal_history(user_id, goal_id, start_time, end_time).map_err(|e| e.to_string())
+}
+
+// ==================== Streak & Achievement Commands ====================
+
+#[tauri::command]
+fn db_get_streaks(include_manual: Option<bool>, state: State<AppState>) -> Result<Vec<achievements::Streak>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    let db = state.db.lock().unwrap();
+    achievements::compute_streaks(&db, user_id, time_utils::now_ms(), include_manual.unwrap_or(true)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_get_achievements(state: State<AppState>) -> Result<Vec<achievements::AchievementStatus>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
+    // 只读查询，新成就在保存记录和休息日志时以及启动时解锁
+    let db = state.db.lock().unwrap();
+    achievements::achievement_status(&db, user_id, time_utils::now_ms()).map_err(|e| e.to_string())
+}
+
+/// 检查成就，新解锁的成就发出 `achievement-unlocked` 事件和系统通知
+/// 只检查包含 end_time 所在日期的连续记录，避免每次保存都读取全部历史
+fn check_achievements_and_notify(app: &tauri::AppHandle, db: &Database, user_id: i64, end_time: i64, tz_offset_minutes: Option<i32>) {
+    let day = time_utils::record_date(end_time, tz_offset_minutes);
+    match achievements::check_achievements_on(db, user_id, time_utils::now_ms(), day) {
+        Ok(newly) => announce_achievements(app, db, user_id, &newly),
+        Err(e) => eprintln!("✗ Failed to check achievements: {}", e),
+    }
+}
+
+fn announce_achievements(app: &tauri::AppHandle, db: &Database, user_id: i64, newly: &[achievements::AchievementStatus]) {
+    if newly.is_empty() {
+        return;
     }
+    let notify = notifications_enabled(db, user_id);
     
-    fn insert(&mut self, key: String, data: Vec<u8>, width: u32, height: u32) {
-        self.icons.insert(key, (data, width, height));
+    for achievement in newly {
+        let _ = app.emit("achievement-unlocked", achievement);
+        if notify {
+            let _ = app.notification().builder()
+                .title(format!("解锁成就：{}", achievement.title))
+                .body(&achievement.description)
+                .show();
+        }
     }
 }
 
-// Database state wrapper
-pub struct AppState {
-    pub db: Mutex<Database>,
-    pub current_user_id: Mutex<Option<i64>>,
-    pub icon_cache: Mutex<IconCache>,
+fn notifications_enabled(db: &Database, user_id: i64) -> bool {
+    db.get_setting_json(user_id, "enableNotification")
+        .ok()
+        .flatten()
+        .and_then(|v| v.as_bool())
+        .unwrap_or(true)
 }
 
-// Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-#[tauri::command]
-fn greet(name: &str) -> String {
-    format!("Hello, {}! You've been greeted from Rust!", name)
+/// 重新计算目标进度，发出 `goal-progress` / `goal-achieved` 事件，新达成时发送系统通知
+fn evaluate_goals_and_notify(app: &tauri::AppHandle, db: &Database, user_id: i64, ts: i64) {
+    // 记录或目标变化后，托盘中的每周预测也随之更新
+    refresh_tray_forecast(app);
+    let evaluations = match goals::evaluate_goals(db, user_id, ts) {
+        Ok(evaluations) => evaluations,
+        Err(e) => {
+            eprintln!("✗ Failed to evaluate goals: {}", e);
+            return;
+        }
+    };
+    let notify = notifications_enabled(db, user_id);
+    
+    for evaluation in evaluations.iter().filter(|e| e.changed) {
+        let _ = app.emit("goal-progress", evaluation);
+        
+        if evaluation.newly_achieved {
+            let _ = app.emit("goal-achieved", evaluation);
+            if notify {
+                let _ = app.notification().builder()
+                    .title("目标达成")
+                    .body(format!("「{}」已完成（{}）", evaluation.goal.title, evaluation.progress.period_key))
+                    .show();
+            }
+        }
+    }
 }
 
-// ==================== Database Commands ====================
+// ==================== Project & Billing Commands ====================
 
 #[tauri::command]
-fn db_init_user(device_id: String, state: State<AppState>) -> Result<db::User, String> {
+fn db_get_projects(state: State<AppState>) -> Result<Vec<Project>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
     let db = state.db.lock().unwrap();
-    let user = db.get_or_create_user(&device_id).map_err(|e| e.to_string())?;
+    db.get_projects(user_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_add_project(project: Project, state: State<AppState>) -> Result<i64, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
     
-    // 缓存当前用户 ID
-    let mut current_user = state.current_user_id.lock().unwrap();
-    *current_user = Some(user.id);
+    validate_project(&project)?;
+    let db = state.db.lock().unwrap();
+    db.add_project(user_id, &project).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn db_update_project(project: Project, state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
     
-    Ok(user)
+    validate_project(&project)?;
+    let db = state.db.lock().unwrap();
+    db.update_project(user_id, &project).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn db_update_phone(phone: Option<String>, state: State<AppState>) -> Result<(), String> {
+fn db_delete_project(project_id: i64, state: State<AppState>) -> Result<(), String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
     let db = state.db.lock().unwrap();
-    db.update_user_phone(user_id, phone).map_err(|e| e.to_string())
+    let _audit = db.audit_scope("db_delete_project").map_err(|e| e.to_string())?;
+    db.delete_project(user_id, project_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn db_get_user(state: State<AppState>) -> Result<db::User, String> {
+fn db_get_billing_report(
+    start_time: i64,
+    end_time: i64,
+    rounding: Option<billing::RoundingRule>,
+    state: State<AppState>
+) -> Result<billing::BillingReport, String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
     let db = state.db.lock().unwrap();
-    db.get_user_by_id(user_id).map_err(|e| e.to_string())
+    let entries = db.get_billable_entries(user_id, start_time, end_time).map_err(|e| e.to_string())?;
+    Ok(billing::billing_report(&entries, start_time, end_time, rounding.unwrap_or_default()))
 }
 
+/// 生成计费报表 CSV 文本，由前端负责保存到文件
 #[tauri::command]
-fn db_get_settings(state: State<AppState>) -> Result<Vec<db::Setting>, String> {
+fn db_export_billing_csv(
+    start_time: i64,
+    end_time: i64,
+    rounding: Option<billing::RoundingRule>,
+    state: State<AppState>
+) -> Result<String, String> {
+    let report = db_get_billing_report(start_time, end_time, rounding, state)?;
+    Ok(billing::billing_csv(&report))
+}
+
+fn validate_project(project: &Project) -> Result<(), String> {
+    if project.name.trim().is_empty() {
+        return Err("Project name cannot be empty".to_string());
+    }
+    if !project.hourly_rate.is_finite() || project.hourly_rate < 0.0 {
+        return Err("Hourly rate must be a non-negative number".to_string());
+    }
+    if project.currency.trim().is_empty() {
+        return Err("Currency cannot be empty".to_string());
+    }
+    Ok(())
+}
+
+// ==================== Tag Commands ====================
+
+#[tauri::command]
+fn db_get_tags(state: State<AppState>) -> Result<Vec<Tag>, String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
     let db = state.db.lock().unwrap();
-    db.get_settings(user_id).map_err(|e| e.to_string())
+    db.get_tags(user_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn db_save_setting(key: String, value: String, state: State<AppState>) -> Result<(), String> {
+fn db_add_tag(name: String, color: Option<String>, state: State<AppState>) -> Result<i64, String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Tag name cannot be empty".to_string());
+    }
     let db = state.db.lock().unwrap();
-    db.save_setting(user_id, &key, &value).map_err(|e| e.to_string())
+    db.add_tag(user_id, name, color).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn db_save_settings_batch(settings: Vec<(String, String)>, state: State<AppState>) -> Result<(), String> {
+fn db_update_tag(tag_id: i64, name: String, color: Option<String>, state: State<AppState>) -> Result<(), String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Tag name cannot be empty".to_string());
+    }
     let db = state.db.lock().unwrap();
-    db.save_settings_batch(user_id, settings).map_err(|e| e.to_string())
+    db.update_tag(user_id, tag_id, name, color).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn db_get_timer_records(limit: i64, state: State<AppState>) -> Result<Vec<TimerRecord>, String> {
+fn db_delete_tag(tag_id: i64, state: State<AppState>) -> Result<(), String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
     let db = state.db.lock().unwrap();
-    db.get_timer_records(user_id, limit).map_err(|e| e.to_string())
+    db.delete_tag(user_id, tag_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn db_add_timer_record(record: TimerRecord, state: State<AppState>) -> Result<(), String> {
+fn db_set_record_tags(record_id: String, tag_ids: Vec<i64>, state: State<AppState>) -> Result<(), String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
+    
     let db = state.db.lock().unwrap();
-    db.add_timer_record(&record).map_err(|e| e.to_string())
+    db.set_record_tags(user_id, &record_id, &tag_ids).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn db_update_timer_record(
-    record_id: String,
-    updates: std::collections::HashMap<String, serde_json::Value>,
+fn db_get_tag_stats(
+    start_time: i64,
+    end_time: i64,
+    include_manual: Option<bool>,
     state: State<AppState>
-) -> Result<(), String> {
+) -> Result<Vec<db::TagStat>, String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
-    let name = updates.get("name")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
-    
-    let category = updates.get("category")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+    let db = state.db.lock().unwrap();
+    db.get_tag_stats(user_id, start_time, end_time, include_manual.unwrap_or(true)).map_err(|e| e.to_string())
+}
+
+// ==================== Task Commands ====================
+
+#[tauri::command]
+fn db_get_tasks(include_done: Option<bool>, state: State<AppState>) -> Result<Vec<Task>, String> {
+    let user_id = state.current_user_id.lock().unwrap()
+        .ok_or("User not initialized")?;
     
     let db = state.db.lock().unwrap();
-    db.update_timer_record(user_id, &record_id, name, category).map_err(|e| e.to_string())
+    db.get_tasks(user_id, include_done.unwrap_or(true)).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn db_delete_timer_record(record_id: String, state: State<AppState>) -> Result<(), String> {
+fn db_add_task(app: tauri::AppHandle, task: Task, state: State<AppState>) -> Result<i64, String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
+    validate_task(&task)?;
     let db = state.db.lock().unwrap();
-    db.delete_timer_record(user_id, &record_id).map_err(|e| e.to_string())
+    let id = db.add_task(user_id, &task).map_err(|e| e.to_string())?;
+    sync_tray_tasks(&app, &db, user_id);
+    Ok(id)
 }
 
 #[tauri::command]
-fn db_clear_timer_records(state: State<AppState>) -> Result<(), String> {
+fn db_update_task(app: tauri::AppHandle, task: Task, state: State<AppState>) -> Result<(), String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
+    validate_task(&task)?;
     let db = state.db.lock().unwrap();
-    db.clear_timer_records(user_id).map_err(|e| e.to_string())
+    db.update_task(user_id, &task).map_err(|e| e.to_string())?;
+    sync_tray_tasks(&app, &db, user_id);
+    Ok(())
 }
 
 #[tauri::command]
-fn db_get_custom_categories(state: State<AppState>) -> Result<Vec<db::CustomCategory>, String> {
+fn db_delete_task(app: tauri::AppHandle, task_id: i64, state: State<AppState>) -> Result<(), String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
     let db = state.db.lock().unwrap();
-    db.get_custom_categories(user_id).map_err(|e| e.to_string())
+    let _audit = db.audit_scope("db_delete_task").map_err(|e| e.to_string())?;
+    db.delete_task(user_id, task_id).map_err(|e| e.to_string())?;
+    sync_tray_tasks(&app, &db, user_id);
+    Ok(())
 }
 
 #[tauri::command]
-fn db_add_custom_category(value: String, label: String, icon: String, state: State<AppState>) -> Result<i64, String> {
+fn db_get_task_report(include_done: Option<bool>, state: State<AppState>) -> Result<Vec<db::TaskProgress>, String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
     let db = state.db.lock().unwrap();
-    db.add_custom_category(user_id, value, label, icon).map_err(|e| e.to_string())
+    db.get_task_progress(user_id, include_done.unwrap_or(true)).map_err(|e| e.to_string())
+}
+
+fn validate_task(task: &Task) -> Result<(), String> {
+    if task.title.trim().is_empty() {
+        return Err("Task title cannot be empty".to_string());
+    }
+    if task.estimated_pomodoros < 0 {
+        return Err("Estimated pomodoros cannot be negative".to_string());
+    }
+    if !matches!(task.status.as_str(), "todo" | "doing" | "done") {
+        return Err(format!("Invalid task status: {}", task.status));
+    }
+    Ok(())
+}
+
+/// 重新读取未完成任务并刷新托盘菜单
+fn sync_tray_tasks(app: &tauri::AppHandle, db: &Database, user_id: i64) {
+    match db.get_tasks(user_id, false) {
+        Ok(tasks) => refresh_tray_tasks(app, &tasks),
+        Err(e) => eprintln!("✗ Failed to load tasks for tray: {}", e),
+    }
+}
+
+fn refresh_tray_tasks(app: &tauri::AppHandle, tasks: &[Task]) {
+    if let Some(tray) = app.tray_by_id("tray") {
+        match build_tray_menu(app, tasks) {
+            Ok(menu) => {
+                let _ = tray.set_menu(Some(menu));
+            }
+            Err(e) => eprintln!("✗ Failed to rebuild tray menu: {}", e),
+        }
+    }
+}
+
+/// 构建托盘菜单，「开始任务」子菜单列出未完成的任务
+fn build_tray_menu<R: Runtime, M: Manager<R>>(manager: &M, tasks: &[Task]) -> tauri::Result<Menu<R>> {
+    let start_i = MenuItem::with_id(manager, "start", "开始", true, None::<&str>)?;
+    let pause_i = MenuItem::with_id(manager, "pause", "暂停", true, None::<&str>)?;
+    let reset_i = MenuItem::with_id(manager, "reset", "重置计时", true, None::<&str>)?;
+
+    let mut task_items = Vec::new();
+    for task in tasks.iter().take(TRAY_TASK_LIMIT) {
+        task_items.push(MenuItem::with_id(manager, format!("task:{}", task.id), &task.title, true, None::<&str>)?);
+    }
+    if task_items.is_empty() {
+        task_items.push(MenuItem::with_id(manager, "task:none", "暂无任务", false, None::<&str>)?);
+    }
+    let task_refs: Vec<&dyn IsMenuItem<R>> = task_items.iter().map(|i| i as &dyn IsMenuItem<R>).collect();
+    let tasks_i = Submenu::with_id_and_items(manager, "tasks", "开始任务", true, &task_refs)?;
+
+    let interrupt_internal_i = MenuItem::with_id(manager, "interrupt_internal", "记录打断（内部）", true, None::<&str>)?;
+    let interrupt_external_i = MenuItem::with_id(manager, "interrupt_external", "记录打断（外部）", true, None::<&str>)?;
+    let separator = PredefinedMenuItem::separator(manager)?;
+    let settings_i = MenuItem::with_id(manager, "settings", "设置", true, None::<&str>)?;
+    let quit_i = MenuItem::with_id(manager, "quit", "退出应用", true, None::<&str>)?;
+
+    Menu::with_items(manager, &[
+        &start_i,
+        &pause_i,
+        &reset_i,
+        &tasks_i,
+        &interrupt_internal_i,
+        &interrupt_external_i,
+        &separator,
+        &settings_i,
+        &quit_i,
+    ])
 }
 
+// ==================== Work Budget Commands ====================
+
 #[tauri::command]
-fn db_update_custom_category(value: String, label: String, icon: String, state: State<AppState>) -> Result<(), String> {
+fn get_work_budget(
+    live_session_ms: Option<i64>,
+    include_manual: Option<bool>,
+    state: State<AppState>
+) -> Result<work_budget::WorkBudget, String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
     let db = state.db.lock().unwrap();
-    db.update_custom_category(user_id, &value, label, icon).map_err(|e| e.to_string())
+    work_budget::compute_work_budget(&db, user_id, live_session_ms.unwrap_or(0), time_utils::now_ms(), include_manual.unwrap_or(true))
+        .map_err(|e| e.to_string())
 }
 
+/// 计算预算并对新达到的阈值发出 `work-budget-warning` 事件和系统通知
 #[tauri::command]
-fn db_delete_custom_category(value: String, state: State<AppState>) -> Result<(), String> {
+fn check_work_budget(
+    app: tauri::AppHandle,
+    live_session_ms: Option<i64>,
+    include_manual: Option<bool>,
+    state: State<AppState>
+) -> Result<work_budget::WorkBudget, String> {
     let user_id = state.current_user_id.lock().unwrap()
         .ok_or("User not initialized")?;
     
     let db = state.db.lock().unwrap();
-    db.delete_custom_category(user_id, &value).map_err(|e| e.to_string())
+    let budget = work_budget::compute_work_budget(&db, user_id, live_session_ms.unwrap_or(0), time_utils::now_ms(), include_manual.unwrap_or(true))
+        .map_err(|e| e.to_string())?;
+    let notify = notifications_enabled(&db, user_id);
+    drop(db);
+    
+    let mut alerts = state.budget_alerts.lock().unwrap();
+    for (period_start, warning) in work_budget::reached_warnings(&budget) {
+        if !alerts.insert(warning.key(period_start)) {
+            continue;
+        }
+        
+        let _ = app.emit("work-budget-warning", &warning);
+        
+        if notify {
+            let scope = if warning.scope == "daily" { "今日" } else { "本周" };
+            let body = if warning.threshold >= 100 {
+                format!("{}工作时长已达到上限（{} 分钟），请注意休息。", scope, warning.cap_ms / 60_000)
+            } else {
+                format!("{}工作时长已达到上限的 {}%（{} / {} 分钟）。", scope, warning.threshold, warning.used_ms / 60_000, warning.cap_ms / 60_000)
+            };
+            let _ = app.notification().builder()
+                .title("工作时长提醒")
+                .body(body)
+                .show();
+        }
+    }
+    
+    Ok(budget)
 }
 
 #[tauri::command]
 fn set_tray_icon(app: tauri::AppHandle, state: &str, app_state: State<AppState>) {
+    *app_state.tray_status.lock().unwrap() = state.to_string();
+    update_tray_tooltip(&app, &app_state);
+    // 预测与当前时刻有关，状态变化时顺便在后台更新
+    refresh_tray_forecast(&app);
+    
     if let Some(tray) = app.tray_by_id("tray") {
-        // 更新提示文本
-        let tooltip = match state {
-            "working" => "LPE Reminder - 工作中",
-            "paused" => "LPE Reminder - 已暂停",
-            "break" => "LPE Reminder - 休息中",
-            _ => "LPE Reminder - 空闲",
-        };
-        let _ = tray.set_tooltip(Some(tooltip));
-        
         // 根据状态选择图标键
         let icon_key = match state {
             "working" | "break" => "busy",
@@ -230,6 +1779,65 @@ fn set_tray_icon(app: tauri::AppHandle, state: &str, app_state: State<AppState>)
     }
 }
 
+/// 按当前状态和缓存的预测行更新托盘提示文本
+fn update_tray_tooltip(app: &tauri::AppHandle, app_state: &AppState) {
+    let Some(tray) = app.tray_by_id("tray") else {
+        return;
+    };
+    let mut tooltip = match app_state.tray_status.lock().unwrap().as_str() {
+        "working" => "LPE Reminder - 工作中",
+        "paused" => "LPE Reminder - 已暂停",
+        "break" => "LPE Reminder - 休息中",
+        _ => "LPE Reminder - 空闲",
+    }.to_string();
+    if let Some(line) = app_state.tray_forecast.lock().unwrap().as_ref() {
+        tooltip.push('\n');
+        tooltip.push_str(line);
+    }
+    let _ = tray.set_tooltip(Some(tooltip));
+}
+
+/// 在后台线程重新计算托盘预测行；已有未开始的刷新时不重复启动
+fn refresh_tray_forecast(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<AppState>() else {
+        return;
+    };
+    if state.tray_forecast_pending.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let state = app.state::<AppState>();
+        // 计算期间发生的修改会再触发一次刷新
+        state.tray_forecast_pending.store(false, Ordering::SeqCst);
+        let line = tray_forecast_line(&state);
+        *state.tray_forecast.lock().unwrap() = line;
+        update_tray_tooltip(&app, &state);
+    });
+}
+
+/// 开启 showForecastInTray 时，托盘提示中显示第一个每周目标的预测
+fn tray_forecast_line(app_state: &AppState) -> Option<String> {
+    let user_id = (*app_state.current_user_id.lock().unwrap())?;
+    let db = app_state.db.lock().unwrap();
+    let enabled = db.get_setting_json(user_id, "showForecastInTray")
+        .ok()
+        .flatten()
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    if !enabled {
+        return None;
+    }
+    
+    match forecast::weekly_forecast(&db, user_id, time_utils::now_ms(), forecast::DEFAULT_HISTORY_WEEKS) {
+        Ok(forecasts) => forecasts.first().map(forecast::tooltip_line),
+        Err(e) => {
+            eprintln!("✗ Failed to compute weekly forecast: {}", e);
+            None
+        }
+    }
+}
+
 #[tauri::command]
 fn update_tray_menu(app: tauri::AppHandle, is_running: bool) {
     if let Some(_tray) = app.tray_by_id("tray") {
@@ -306,6 +1914,59 @@ fn get_floating_window_position(app: tauri::AppHandle) -> Result<(f64, f64), Str
     }
 }
 
+/// 检查定时周报的间隔
+const WEEKLY_REPORT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+/// 后台定时检查：设置了 weeklyReportFolder 时，在每周一（或之后第一次运行时）生成上一周的报告
+fn spawn_weekly_report_scheduler(app: tauri::AppHandle) {
+    std::thread::spawn(move || {
+        // 首次检查前等待用户初始化
+        std::thread::sleep(std::time::Duration::from_secs(60));
+        loop {
+            if let Err(e) = write_weekly_report(&app) {
+                eprintln!("✗ Failed to write weekly report: {}", e);
+            }
+            std::thread::sleep(WEEKLY_REPORT_INTERVAL);
+        }
+    });
+}
+
+/// 上一周的报告文件不存在时生成，已存在则跳过
+fn write_weekly_report(app: &tauri::AppHandle) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let Some(user_id) = *state.current_user_id.lock().unwrap() else {
+        return Ok(());
+    };
+    let folder = state.db.lock().unwrap()
+        .get_setting_json(user_id, "weeklyReportFolder")
+        .map_err(|e| e.to_string())?
+        .and_then(|v| v.as_str().map(|s| s.trim().to_string()))
+        .unwrap_or_default();
+    if folder.is_empty() {
+        return Ok(());
+    }
+    
+    let now = time_utils::now_ms();
+    let this_monday = time_utils::week_start_date(time_utils::local_date(now));
+    let first = this_monday - chrono::Duration::days(7);
+    let last = this_monday - chrono::Duration::days(1);
+    let folder = std::path::Path::new(&folder);
+    let path = folder.join(report::weekly_file_name(first));
+    if path.exists() {
+        return Ok(());
+    }
+    
+    std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+    // 只在汇总数据时持有数据库锁，排版和写文件在锁外进行
+    let report = {
+        let db = state.db.lock().unwrap();
+        report::build_report(&db, user_id, first, last, now).map_err(|e| e.to_string())?
+    };
+    report::write_report(&report, &path)?;
+    println!("✓ Weekly report written to {:?}", path);
+    Ok(())
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -407,19 +2068,19 @@ pub fn run() {
                 db: Mutex::new(database),
                 current_user_id: Mutex::new(None),
                 icon_cache: Mutex::new(icon_cache),
+                budget_alerts: Mutex::new(HashSet::new()),
+                tray_status: Mutex::new("idle".to_string()),
+                tray_forecast: Mutex::new(None),
+                tray_forecast_pending: AtomicBool::new(false),
             });
             
             println!("✓ Database and icon cache initialized successfully");
             
+            spawn_weekly_report_scheduler(app.handle().clone());
+            
 
-            // 创建托盘菜单项
-            let start_i = MenuItem::with_id(app, "start", "开始", true, None::<&str>)?;
-            let pause_i = MenuItem::with_id(app, "pause", "暂停", true, None::<&str>)?;
-            let reset_i = MenuItem::with_id(app, "reset", "重置计时", true, None::<&str>)?;
-            let settings_i = MenuItem::with_id(app, "settings", "设置", true, None::<&str>)?;
-            let quit_i = MenuItem::with_id(app, "quit", "退出应用", true, None::<&str>)?;
-
-            let menu = Menu::with_items(app, &[&start_i, &pause_i, &reset_i, &settings_i, &quit_i])?;
+            // 创建托盘菜单（任务列表在用户初始化后刷新）
+            let menu = build_tray_menu(app, &[])?;
 
             // 创建托盘图标
             let _tray = TrayIconBuilder::with_id("tray")
@@ -439,6 +2100,14 @@ pub fn run() {
                         println!("Tray: Reset clicked");
                         let _ = app.emit("tray-reset", ());
                     }
+                    "interrupt_internal" => {
+                        println!("Tray: Internal interruption clicked");
+                        log_interruption_from_tray(app, "internal");
+                    }
+                    "interrupt_external" => {
+                        println!("Tray: External interruption clicked");
+                        log_interruption_from_tray(app, "external");
+                    }
                     "settings" => {
                         println!("Tray: Settings clicked");
                         let _ = app.emit("tray-settings", ());
@@ -447,7 +2116,12 @@ pub fn run() {
                         println!("Tray: Quit clicked");
                         let _ = app.emit("tray-quit", ());
                     }
-                    _ => {}
+                    id => {
+                        if let Some(task_id) = id.strip_prefix("task:").and_then(|s| s.parse::<i64>().ok()) {
+                            println!("Tray: Start task {} clicked", task_id);
+                            let _ = app.emit("tray-start-task", task_id);
+                        }
+                    }
                 })
                 .on_tray_icon_event(|tray, event| {
                     if let TrayIconEvent::Click {
@@ -486,12 +2160,72 @@ pub fn run() {
             db_get_timer_records,
             db_add_timer_record,
             db_update_timer_record,
+            db_add_manual_record,
+            db_split_timer_record,
+            db_merge_timer_records,
+            db_get_focus_stats,
             db_delete_timer_record,
             db_clear_timer_records,
             db_get_custom_categories,
             db_add_custom_category,
             db_update_custom_category,
-            db_delete_custom_category
+            db_delete_custom_category,
+            db_rename_custom_category,
+            db_merge_custom_categories,
+            db_update_category_meta,
+            db_reorder_custom_categories,
+            db_get_category_rollup,
+            db_add_break_log,
+            db_get_break_logs,
+            db_get_break_compliance,
+            db_log_interruption,
+            db_get_interruptions,
+            db_delete_interruption,
+            db_get_interruption_stats,
+            db_query_timer_records,
+            db_bulk_update_records,
+            db_get_trash,
+            db_restore_from_trash,
+            db_purge_from_trash,
+            db_empty_trash,
+            db_undo,
+            db_redo,
+            db_get_undo_state,
+            db_get_record_history,
+            db_search_records,
+            db_get_yearly_heatmap,
+            db_get_trends,
+            db_get_category_monthly,
+            db_get_weekly_forecast,
+            db_export_chart,
+            db_export_report,
+            db_get_tags,
+            db_add_tag,
+            db_update_tag,
+            db_delete_tag,
+            db_set_record_tags,
+            db_get_tag_stats,
+            db_get_tasks,
+            db_add_task,
+            db_update_task,
+            db_delete_task,
+            db_get_task_report,
+            db_get_goals,
+            db_add_goal,
+            db_update_goal,
+            db_delete_goal,
+            db_get_goal_status,
+            db_get_goal_history,
+            db_get_streaks,
+            db_get_achievements,
+            db_get_projects,
+            db_add_project,
+            db_update_project,
+            db_delete_project,
+            db_get_billing_report,
+            db_export_billing_csv,
+            get_work_budget,
+            check_work_budget
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
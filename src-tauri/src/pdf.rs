@@ -0,0 +1,281 @@
+use std::fmt::Write;
+
+/// A4 纵向，单位为点（1/72 英寸）
+pub const PAGE_WIDTH: f64 = 595.0;
+pub const PAGE_HEIGHT: f64 = 842.0;
+
+/// Helvetica 中 ASCII 32-126 的字宽（千分之一字号）
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, // ' ' - '/'
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, // '0' - '?'
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, // '@' - 'O'
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, // 'P' - '_'
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, // '`' - 'o'
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, // 'p' - '~'
+];
+
+/// 西文使用 Helvetica（WinAnsiEncoding），能表示的字符为 ASCII 和 Latin-1
+fn is_latin(c: char) -> bool {
+    matches!(c as u32, 0x20..=0x7E | 0xA0..=0xFF)
+}
+
+fn char_width(c: char) -> f64 {
+    match c as u32 {
+        code @ 0x20..=0x7E => HELVETICA_WIDTHS[(code - 0x20) as usize] as f64,
+        0xA0..=0xFF => 556.0,
+        // 中文字体为全角
+        _ => 1000.0,
+    }
+}
+
+/// 文字宽度（点），按常规字重计算
+pub fn text_width(text: &str, size: f64) -> f64 {
+    text.chars().map(|c| char_width(sanitize(c))).sum::<f64>() * size / 1000.0
+}
+
+fn sanitize(c: char) -> char {
+    if c.is_control() {
+        ' '
+    } else if (c as u32) > 0xFFFF {
+        // UCS-2 编码无法表示 BMP 以外的字符
+        '?'
+    } else {
+        c
+    }
+}
+
+fn rgb(color: &str) -> (f64, f64, f64) {
+    let hex = color.trim_start_matches('#');
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+            .map(|v| v as f64 / 255.0)
+    };
+    match (hex.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(r), Some(g), Some(b)) => (r, g, b),
+        _ => (0.5, 0.5, 0.5),
+    }
+}
+
+/// 一页的绘制指令；坐标原点在左上角，y 向下，文字的 y 为基线位置
+#[derive(Default)]
+pub struct PdfPage {
+    ops: String,
+}
+
+impl PdfPage {
+    pub fn fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64, color: &str) {
+        let (r, g, b) = rgb(color);
+        let _ = writeln!(
+            self.ops,
+            "{:.3} {:.3} {:.3} rg {:.2} {:.2} {:.2} {:.2} re f",
+            r, g, b, x, PAGE_HEIGHT - y - h, w, h
+        );
+    }
+
+    pub fn fill_polygon(&mut self, points: &[(f64, f64)], color: &str) {
+        if points.len() < 3 {
+            return;
+        }
+        let (r, g, b) = rgb(color);
+        let _ = write!(self.ops, "{:.3} {:.3} {:.3} rg", r, g, b);
+        for (i, (x, y)) in points.iter().enumerate() {
+            let _ = write!(self.ops, " {:.2} {:.2} {}", x, PAGE_HEIGHT - y, if i == 0 { "m" } else { "l" });
+        }
+        self.ops.push_str(" h f*\n");
+    }
+
+    pub fn line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, width: f64, color: &str) {
+        let (r, g, b) = rgb(color);
+        let _ = writeln!(
+            self.ops,
+            "{:.3} {:.3} {:.3} RG {:.2} w {:.2} {:.2} m {:.2} {:.2} l S",
+            r, g, b, width, x1, PAGE_HEIGHT - y1, x2, PAGE_HEIGHT - y2
+        );
+    }
+
+    /// 西文部分使用 Helvetica，其余字符使用 STSong-Light（不嵌入，由阅读器替换为本机中文字体）
+    pub fn text(&mut self, x: f64, y: f64, size: f64, text: &str, color: &str, bold: bool) {
+        let (r, g, b) = rgb(color);
+        let mut left = x;
+        let chars: Vec<char> = text.chars().map(sanitize).collect();
+        for run in chars.chunk_by(|a, b| is_latin(*a) == is_latin(*b)) {
+            let (font, encoded) = if is_latin(run[0]) {
+                let mut s = String::from("(");
+                for c in run {
+                    match c {
+                        '(' | ')' | '\\' => {
+                            s.push('\\');
+                            s.push(*c);
+                        }
+                        c if c.is_ascii() => s.push(*c),
+                        c => {
+                            let _ = write!(s, "\\{:03o}", *c as u32);
+                        }
+                    }
+                }
+                s.push(')');
+                (if bold { "F2" } else { "F1" }, s)
+            } else {
+                let mut s = String::from("<");
+                for c in run {
+                    let _ = write!(s, "{:04X}", *c as u32);
+                }
+                s.push('>');
+                ("F3", s)
+            };
+            let _ = writeln!(
+                self.ops,
+                "BT /{} {:.1} Tf {:.3} {:.3} {:.3} rg {:.2} {:.2} Td {} Tj ET",
+                font, size, r, g, b, left, PAGE_HEIGHT - y, encoded
+            );
+            left += run.iter().map(|c| char_width(*c)).sum::<f64>() * size / 1000.0;
+        }
+    }
+}
+
+/// 只包含文字和填充图形的最小 PDF 文档
+#[derive(Default)]
+pub struct PdfDocument {
+    pages: Vec<PdfPage>,
+}
+
+impl PdfDocument {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_page(&mut self) -> &mut PdfPage {
+        self.pages.push(PdfPage::default());
+        self.pages.last_mut().unwrap()
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn page(&mut self, index: usize) -> &mut PdfPage {
+        &mut self.pages[index]
+    }
+
+    /// 生成 PDF 文件内容；对象 1-8 为目录、页树、字体和文档信息，之后每页占两个对象（页面和内容流）
+    pub fn to_bytes(&self, title: &str) -> Vec<u8> {
+        let mut objects: Vec<String> = vec![
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                (0..self.pages.len()).map(|i| format!("{} 0 R", 9 + i * 2)).collect::<Vec<_>>().join(" "),
+                self.pages.len()
+            ),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>".to_string(),
+            "<< /Type /Font /Subtype /Type0 /BaseFont /STSong-Light /Encoding /UniGB-UCS2-H /DescendantFonts [6 0 R] >>".to_string(),
+            "<< /Type /Font /Subtype /CIDFontType0 /BaseFont /STSong-Light \
+             /CIDSystemInfo << /Registry (Adobe) /Ordering (GB1) /Supplement 2 >> /FontDescriptor 7 0 R /DW 1000 >>"
+                .to_string(),
+            "<< /Type /FontDescriptor /FontName /STSong-Light /Flags 6 /FontBBox [-25 -254 1000 880] \
+             /ItalicAngle 0 /Ascent 880 /Descent -120 /CapHeight 880 /StemV 93 >>"
+                .to_string(),
+            format!(
+                "<< /Title {} /Producer (LPE Reminder) /CreationDate (D:{}) >>",
+                utf16_string(title),
+                chrono::Local::now().format("%Y%m%d%H%M%S")
+            ),
+        ];
+        for (i, page) in self.pages.iter().enumerate() {
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 3 0 R /F2 4 0 R /F3 5 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                10 + i * 2
+            ));
+            objects.push(format!(
+                "<< /Length {} >>\nstream\n{}endstream",
+                page.ops.len(),
+                page.ops
+            ));
+        }
+
+        // 所有内容均为 ASCII，字节偏移即字符串长度
+        let mut out = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(out.len());
+            let _ = write!(out, "{} 0 obj\n{}\nendobj\n", i + 1, object);
+        }
+        let xref = out.len();
+        let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+        for offset in offsets {
+            let _ = writeln!(out, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            out,
+            "trailer\n<< /Size {} /Root 1 0 R /Info 8 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        );
+        out.into_bytes()
+    }
+}
+
+/// 文档信息中的文本字符串，使用带 BOM 的 UTF-16BE
+fn utf16_string(text: &str) -> String {
+    let mut s = String::from("<FEFF");
+    for unit in text.encode_utf16() {
+        let _ = write!(s, "{:04X}", unit);
+    }
+    s.push('>');
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xref_offsets_point_at_objects() {
+        let mut doc = PdfDocument::new();
+        doc.add_page().text(50.0, 50.0, 12.0, "Focus 专注 (1)", "#000000", false);
+        doc.add_page().fill_rect(10.0, 10.0, 20.0, 20.0, "#ff0000");
+        let bytes = doc.to_bytes("报告");
+        let out = String::from_utf8(bytes).unwrap();
+
+        let startxref: usize = out
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|tail| tail.lines().next())
+            .and_then(|n| n.parse().ok())
+            .unwrap();
+        assert!(out[startxref..].starts_with("xref\n0 13\n"));
+
+        // 8 个固定对象加每页两个
+        let entries: Vec<usize> = out[startxref..]
+            .lines()
+            .skip(3)
+            .take(12)
+            .map(|line| line[..10].parse().unwrap())
+            .collect();
+        for (i, offset) in entries.iter().enumerate() {
+            assert!(out[*offset..].starts_with(&format!("{} 0 obj\n", i + 1)), "object {}", i + 1);
+        }
+        assert!(out.contains("/Size 13 /Root 1 0 R"));
+        assert!(out.contains("/Count 2"));
+    }
+
+    #[test]
+    fn text_splits_latin_and_cjk_runs() {
+        let mut page = PdfPage::default();
+        page.text(0.0, 0.0, 10.0, "ab(c)报告", "#000000", true);
+        assert!(page.ops.contains("/F2 10.0 Tf"));
+        assert!(page.ops.contains("(ab\\(c\\)) Tj"));
+        assert!(page.ops.contains("/F3 10.0 Tf"));
+        assert!(page.ops.contains("<62A5544A> Tj"));
+    }
+
+    #[test]
+    fn text_width_uses_helvetica_and_full_width_cjk() {
+        assert_eq!(text_width("A", 10.0), 6.67);
+        assert_eq!(text_width("报", 12.0), 12.0);
+    }
+}
@@ -0,0 +1,294 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::Result;
+
+use crate::achievements::{self, Streak};
+use crate::charts;
+use crate::db::Database;
+use crate::pdf::{self, PdfDocument, PdfPage, PAGE_HEIGHT, PAGE_WIDTH};
+use crate::stats::{self, CategoryRollup, ComplianceBucket};
+use crate::time_utils;
+
+/// 报告中最多列出的任务数
+const TOP_TASKS: usize = 10;
+const MARGIN: f64 = 50.0;
+const TEXT_COLOR: &str = "#1f2937";
+const MUTED_COLOR: &str = "#6b7280";
+const RULE_COLOR: &str = "#e5e7eb";
+
+/// 时间段内在某个任务上的工作
+#[derive(Debug, Clone)]
+pub struct TaskSummary {
+    pub title: String,
+    pub sessions: i64,
+    pub work_ms: i64,
+}
+
+/// 一个时间段（含首尾两天）的报告数据
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+    pub total_work_ms: i64,
+    pub sessions: i64,
+    /// 完成的倒计时工作轮数
+    pub pomodoros: i64,
+    pub active_days: usize,
+    /// 从 first 开始每天的工作分钟数
+    pub daily_minutes: Vec<u32>,
+    pub categories: Vec<CategoryRollup>,
+    pub top_tasks: Vec<TaskSummary>,
+    pub compliance: ComplianceBucket,
+    /// 截至时间段结束（或生成报告时，取较早者）的连续记录
+    pub streaks: Vec<Streak>,
+}
+
+/// 汇总 first..=last 的数据
+pub fn build_report(db: &Database, user_id: i64, first: NaiveDate, last: NaiveDate, now: i64) -> Result<Report> {
+    let start = time_utils::local_day_start(first);
+    let end = time_utils::local_day_start(last + Duration::days(1));
+
+    let work: Vec<_> = db
+        .get_timer_records_between(user_id, start, end)?
+        .into_iter()
+        .filter(|r| r.mode.as_deref() == Some("work"))
+        .collect();
+    let active_days = work.iter().map(|r| r.day.as_str()).collect::<HashSet<_>>().len();
+
+    // 按任务汇总，取工作时长最多的几个
+    let titles: HashMap<i64, String> = db
+        .get_tasks(user_id, true)?
+        .into_iter()
+        .map(|t| (t.id, t.title))
+        .collect();
+    let mut by_task: HashMap<i64, TaskSummary> = HashMap::new();
+    for record in &work {
+        let Some((task_id, title)) = record.task_id.and_then(|id| titles.get(&id).map(|t| (id, t))) else {
+            continue;
+        };
+        let summary = by_task.entry(task_id).or_insert_with(|| TaskSummary {
+            title: title.clone(),
+            sessions: 0,
+            work_ms: 0,
+        });
+        summary.sessions += 1;
+        summary.work_ms += record.duration;
+    }
+    let mut top_tasks: Vec<TaskSummary> = by_task.into_values().collect();
+    top_tasks.sort_by(|a, b| b.work_ms.cmp(&a.work_ms).then_with(|| a.title.cmp(&b.title)));
+    top_tasks.truncate(TOP_TASKS);
+
+    let (padded_start, padded_end) = time_utils::padded_date_range(first, last);
+    let entries = db.get_work_entries(user_id, padded_start, padded_end, true)?;
+    let categories = db.get_custom_categories(user_id)?;
+    let durations = db.get_category_durations(user_id, start, end, true)?;
+
+    Ok(Report {
+        first,
+        last,
+        total_work_ms: work.iter().map(|r| r.duration).sum(),
+        sessions: work.len() as i64,
+        pomodoros: work.iter().filter(|r| r.record_type == "countdown" && r.split_from.is_none()).count() as i64,
+        active_days,
+        daily_minutes: stats::trends(&entries, &[], first, last).work_minutes,
+        categories: stats::category_rollup(&categories, &durations),
+        top_tasks,
+        compliance: stats::break_compliance(&db.get_break_logs(user_id, start, end)?).overall,
+        streaks: achievements::compute_streaks(db, user_id, (end - 1).min(now), true)?,
+    })
+}
+
+fn format_duration(ms: i64) -> String {
+    let minutes = ms / 60_000;
+    if minutes >= 60 {
+        format!("{}小时{}分钟", minutes / 60, minutes % 60)
+    } else {
+        format!("{}分钟", minutes)
+    }
+}
+
+fn format_percent(rate: f64) -> String {
+    format!("{:.0}%", rate * 100.0)
+}
+
+/// 按顺序向下排版，空间不足时换页
+struct Layout {
+    doc: PdfDocument,
+    y: f64,
+}
+
+impl Layout {
+    fn new() -> Self {
+        let mut doc = PdfDocument::new();
+        doc.add_page();
+        Layout { doc, y: MARGIN }
+    }
+
+    fn page(&mut self) -> &mut PdfPage {
+        let last = self.doc.page_count() - 1;
+        self.doc.page(last)
+    }
+
+    fn ensure(&mut self, height: f64) {
+        if self.y + height > PAGE_HEIGHT - MARGIN {
+            self.doc.add_page();
+            self.y = MARGIN;
+        }
+    }
+
+    fn heading(&mut self, text: &str) {
+        self.ensure(60.0);
+        self.y += 28.0;
+        let y = self.y;
+        self.page().text(MARGIN, y, 14.0, text, TEXT_COLOR, true);
+        self.page().line(MARGIN, y + 6.0, PAGE_WIDTH - MARGIN, y + 6.0, 0.5, RULE_COLOR);
+        self.y += 12.0;
+    }
+
+    /// 一行表格：第一列左对齐，其余列在给定的 x 处右对齐
+    fn row(&mut self, label: &str, indent: f64, values: &[(f64, String)], color: &str) {
+        self.ensure(18.0);
+        self.y += 16.0;
+        let y = self.y;
+        let page = self.page();
+        page.text(MARGIN + indent, y, 10.0, label, color, false);
+        for (right, value) in values {
+            page.text(right - pdf::text_width(value, 10.0), y, 10.0, value, color, false);
+        }
+    }
+
+    fn note(&mut self, text: &str) {
+        self.ensure(18.0);
+        self.y += 16.0;
+        let y = self.y;
+        self.page().text(MARGIN, y, 10.0, text, MUTED_COLOR, false);
+    }
+
+    /// 图表按页面可用宽度等比缩放
+    fn chart(&mut self, chart: &charts::Chart) {
+        let scale = (PAGE_WIDTH - MARGIN * 2.0) / chart.width() as f64;
+        let height = chart.height() as f64 * scale;
+        self.ensure(height + 8.0);
+        self.y += 8.0;
+        let y = self.y;
+        chart.draw_pdf(self.page(), MARGIN, y, scale);
+        self.y += height;
+    }
+}
+
+/// 将报告排版为 PDF
+pub fn render_pdf(report: &Report) -> Vec<u8> {
+    let period = format!("{} ~ {}", report.first.format("%Y-%m-%d"), report.last.format("%Y-%m-%d"));
+    let days = (report.last - report.first).num_days() + 1;
+    let right = PAGE_WIDTH - MARGIN;
+    let mut layout = Layout::new();
+
+    layout.y += 20.0;
+    let y = layout.y;
+    layout.page().text(MARGIN, y, 22.0, "专注报告", TEXT_COLOR, true);
+    layout.y += 20.0;
+    let y = layout.y;
+    layout.page().text(MARGIN, y, 11.0, &format!("{}（{} 天）", period, days), MUTED_COLOR, false);
+
+    layout.heading("总览");
+    let average = report.total_work_ms / days.max(1);
+    let summary = [
+        ("总工作时长", format_duration(report.total_work_ms)),
+        ("工作次数", report.sessions.to_string()),
+        ("完成番茄", report.pomodoros.to_string()),
+        ("有工作的天数", format!("{} / {}", report.active_days, days)),
+        ("日均工作时长", format_duration(average)),
+    ];
+    for (label, value) in summary {
+        layout.row(label, 0.0, &[(right, value)], TEXT_COLOR);
+    }
+
+    layout.heading("每日工作时长");
+    let labels = charts::day_labels(report.first, report.daily_minutes.len());
+    layout.chart(&charts::daily_bars("", &labels, &report.daily_minutes));
+
+    layout.heading("分类");
+    let total: i64 = report.categories.iter().filter(|c| c.depth == 0).map(|c| c.total_ms).sum();
+    if total == 0 {
+        layout.note("这段时间没有工作记录");
+    } else {
+        layout.chart(&charts::category_pie("", &report.categories));
+        for category in report.categories.iter().filter(|c| c.total_ms > 0) {
+            let color = if category.depth == 0 { TEXT_COLOR } else { MUTED_COLOR };
+            layout.row(
+                &category.label,
+                category.depth as f64 * 14.0,
+                &[
+                    (right - 80.0, format_duration(category.total_ms)),
+                    (right, format_percent(category.total_ms as f64 / total as f64)),
+                ],
+                color,
+            );
+        }
+    }
+
+    layout.heading("任务");
+    if report.top_tasks.is_empty() {
+        layout.note("这段时间没有关联任务的工作记录");
+    } else {
+        layout.row("", 0.0, &[(right - 100.0, "次数".to_string()), (right, "时长".to_string())], MUTED_COLOR);
+        for task in &report.top_tasks {
+            layout.row(
+                &task.title,
+                0.0,
+                &[(right - 100.0, task.sessions.to_string()), (right, format_duration(task.work_ms))],
+                TEXT_COLOR,
+            );
+        }
+    }
+
+    layout.heading("休息");
+    let compliance = &report.compliance;
+    if compliance.total == 0 {
+        layout.note("这段时间没有休息记录");
+    } else {
+        let breaks = [
+            ("按计划完成率", format_percent(compliance.rate)),
+            ("按计划完成", format!("{} / {}", compliance.completed, compliance.total)),
+            ("提前结束", compliance.cut_short.to_string()),
+            ("跳过", compliance.skipped.to_string()),
+            ("推迟", compliance.snoozed.to_string()),
+            ("实际休息时长", format_duration(compliance.actual_ms)),
+        ];
+        for (label, value) in breaks {
+            layout.row(label, 0.0, &[(right, value)], TEXT_COLOR);
+        }
+    }
+
+    layout.heading("连续记录");
+    layout.row("", 0.0, &[(right - 100.0, "当前".to_string()), (right, "最长".to_string())], MUTED_COLOR);
+    for streak in &report.streaks {
+        layout.row(
+            &streak.label,
+            0.0,
+            &[(right - 100.0, format!("{} 天", streak.current)), (right, format!("{} 天", streak.longest))],
+            TEXT_COLOR,
+        );
+    }
+
+    // 页脚页码
+    let pages = layout.doc.page_count();
+    for i in 0..pages {
+        let footer = format!("{}    {} / {}", period, i + 1, pages);
+        layout.doc.page(i).text(MARGIN, PAGE_HEIGHT - 24.0, 8.0, &footer, MUTED_COLOR, false);
+    }
+
+    layout.doc.to_bytes(&format!("专注报告 {}", period))
+}
+
+/// 将报告排版后写入 path；不需要数据库，调用方应先释放数据库锁
+pub fn write_report(report: &Report, path: &Path) -> std::result::Result<(), String> {
+    std::fs::write(path, render_pdf(report)).map_err(|e| e.to_string())
+}
+
+/// 定时生成的周报文件名
+pub fn weekly_file_name(first: NaiveDate) -> String {
+    format!("lpe-reminder-report-{}.pdf", first.format("%Y-%m-%d"))
+}
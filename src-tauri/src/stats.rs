@@ -0,0 +1,611 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Serialize;
+
+use crate::db::{BreakLog, CustomCategory, FocusRating, InterruptionReasonCount, SessionInterruptions, WorkEntry};
+use crate::time_utils;
+
+// ==================== 休息执行率 ====================
+
+/// 某一周期内的休息执行情况
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ComplianceBucket {
+    /// 日期键（YYYY-MM-DD），周统计时为该周周一
+    pub period: String,
+    pub total: i64,
+    pub completed: i64,
+    pub cut_short: i64,
+    pub skipped: i64,
+    pub snoozed: i64,
+    pub planned_ms: i64,
+    pub actual_ms: i64,
+    pub overtime_ms: i64,
+    /// 按计划完成的休息占比（0-1），没有休息时为 0
+    pub rate: f64,
+}
+
+impl ComplianceBucket {
+    fn new(period: String) -> Self {
+        ComplianceBucket {
+            period,
+            ..Default::default()
+        }
+    }
+
+    fn add(&mut self, log: &BreakLog) {
+        self.total += 1;
+        match log.outcome.as_str() {
+            "completed" => self.completed += 1,
+            "cut_short" => self.cut_short += 1,
+            "skipped" => self.skipped += 1,
+            "snoozed" => self.snoozed += 1,
+            _ => {}
+        }
+        self.planned_ms += log.planned_ms;
+        self.actual_ms += log.actual_ms;
+        self.overtime_ms += log.overtime_ms;
+        self.rate = self.completed as f64 / self.total as f64;
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BreakCompliance {
+    pub daily: Vec<ComplianceBucket>,
+    pub weekly: Vec<ComplianceBucket>,
+    pub overall: ComplianceBucket,
+}
+
+/// 按日、按周汇总休息执行率
+pub fn break_compliance(logs: &[BreakLog]) -> BreakCompliance {
+    let mut daily: BTreeMap<String, ComplianceBucket> = BTreeMap::new();
+    let mut weekly: BTreeMap<String, ComplianceBucket> = BTreeMap::new();
+    let mut overall = ComplianceBucket::new("all".to_string());
+
+    for log in logs {
+        // 按休息保存时的时区归入日期和周
+        let date = time_utils::record_date(log.end_time, log.tz_offset_minutes);
+        let day = date.format("%Y-%m-%d").to_string();
+        daily
+            .entry(day.clone())
+            .or_insert_with(|| ComplianceBucket::new(day))
+            .add(log);
+
+        let week = time_utils::week_start_date(date).format("%Y-%m-%d").to_string();
+        weekly
+            .entry(week.clone())
+            .or_insert_with(|| ComplianceBucket::new(week))
+            .add(log);
+
+        overall.add(log);
+    }
+
+    BreakCompliance {
+        daily: daily.into_values().collect(),
+        weekly: weekly.into_values().collect(),
+        overall,
+    }
+}
+
+// ==================== 打断统计 ====================
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CategoryInterruptions {
+    pub category: Option<String>,
+    pub total: i64,
+    /// 出现次数最多的原因（降序）
+    pub top_reasons: Vec<InterruptionReasonCount>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct InterruptionStats {
+    pub sessions: Vec<SessionInterruptions>,
+    pub total_internal: i64,
+    pub total_external: i64,
+    /// 平均每次工作的打断次数
+    pub per_session: f64,
+    pub categories: Vec<CategoryInterruptions>,
+}
+
+/// 汇总打断统计，每个分类保留前 `top_n` 个原因
+pub fn interruption_stats(
+    sessions: Vec<SessionInterruptions>,
+    reasons: Vec<InterruptionReasonCount>,
+    top_n: usize,
+) -> InterruptionStats {
+    let total_internal: i64 = sessions.iter().map(|s| s.internal).sum();
+    let total_external: i64 = sessions.iter().map(|s| s.external).sum();
+    let per_session = if sessions.is_empty() {
+        0.0
+    } else {
+        (total_internal + total_external) as f64 / sessions.len() as f64
+    };
+
+    let mut totals: BTreeMap<Option<String>, i64> = BTreeMap::new();
+    for s in &sessions {
+        *totals.entry(s.category.clone()).or_insert(0) += s.internal + s.external;
+    }
+
+    // reasons 已按次数降序排列
+    let mut by_category: BTreeMap<Option<String>, Vec<InterruptionReasonCount>> = BTreeMap::new();
+    for r in reasons {
+        let list = by_category.entry(r.category.clone()).or_default();
+        if list.len() < top_n {
+            list.push(r);
+        }
+    }
+
+    let mut categories: Vec<CategoryInterruptions> = totals
+        .into_iter()
+        .filter(|(_, total)| *total > 0)
+        .map(|(category, total)| CategoryInterruptions {
+            top_reasons: by_category.remove(&category).unwrap_or_default(),
+            category,
+            total,
+        })
+        .collect();
+    categories.sort_by_key(|c| std::cmp::Reverse(c.total));
+
+    InterruptionStats {
+        sessions,
+        total_internal,
+        total_external,
+        per_session,
+        categories,
+    }
+}
+
+// ==================== 专注度统计 ====================
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FocusAverage {
+    pub count: i64,
+    pub average: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CategoryFocus {
+    pub category: Option<String>,
+    pub count: i64,
+    pub average: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FocusStats {
+    pub overall: FocusAverage,
+    pub by_category: Vec<CategoryFocus>,
+    /// 下标为小时 0-23，按工作开始时间在记录保存时的时区归入
+    pub by_hour: Vec<FocusAverage>,
+}
+
+/// 按分类和开始小时统计平均专注度
+pub fn focus_stats(ratings: &[FocusRating]) -> FocusStats {
+    let average = |sum: i64, count: i64| if count > 0 { sum as f64 / count as f64 } else { 0.0 };
+
+    let mut categories: BTreeMap<Option<String>, (i64, i64)> = BTreeMap::new();
+    let mut hours = [(0i64, 0i64); 24];
+    let mut total = (0i64, 0i64);
+
+    for r in ratings {
+        let c = categories.entry(r.category.clone()).or_insert((0, 0));
+        c.0 += r.rating;
+        c.1 += 1;
+
+        let h = &mut hours[(time_utils::record_minute_of_day(r.start_time, r.tz_offset_minutes) / 60) as usize];
+        h.0 += r.rating;
+        h.1 += 1;
+
+        total.0 += r.rating;
+        total.1 += 1;
+    }
+
+    FocusStats {
+        overall: FocusAverage {
+            count: total.1,
+            average: average(total.0, total.1),
+        },
+        by_category: categories
+            .into_iter()
+            .map(|(category, (sum, count))| CategoryFocus {
+                category,
+                count,
+                average: average(sum, count),
+            })
+            .collect(),
+        by_hour: hours
+            .iter()
+            .map(|(sum, count)| FocusAverage {
+                count: *count,
+                average: average(*sum, *count),
+            })
+            .collect(),
+    }
+}
+
+// ==================== 分类汇总（含子分类） ====================
+
+/// 分类树中的一个节点
+#[derive(Debug, Serialize, Clone)]
+pub struct CategoryRollup {
+    /// None 表示未分类
+    pub category: Option<String>,
+    pub label: String,
+    pub parent_value: Option<String>,
+    pub color: Option<String>,
+    pub archived: bool,
+    /// 在树中的层级，顶级为 0
+    pub depth: usize,
+    /// 该分类自身的工作时长
+    pub own_ms: i64,
+    /// 包含所有子分类的工作时长
+    pub total_ms: i64,
+}
+
+/// 将各分类的工作时长向上汇总到父分类，按分类树的先序排列；没有时长的分类不输出
+pub fn category_rollup(categories: &[CustomCategory], durations: &[(Option<String>, i64)]) -> Vec<CategoryRollup> {
+    let defined: HashMap<&str, &CustomCategory> =
+        categories.iter().map(|c| (c.value.as_str(), c)).collect();
+    let parent_of = |value: &str| {
+        defined
+            .get(value)
+            .and_then(|c| c.parent_value.as_deref())
+            .filter(|p| defined.contains_key(p))
+    };
+
+    let mut own: HashMap<Option<String>, i64> = HashMap::new();
+    let mut total: HashMap<Option<String>, i64> = HashMap::new();
+    for (category, ms) in durations {
+        *own.entry(category.clone()).or_default() += ms;
+        *total.entry(category.clone()).or_default() += ms;
+
+        // 沿父链向上累加，防止异常数据形成环
+        let mut visited = HashSet::new();
+        let mut current = category.as_deref().and_then(parent_of);
+        while let Some(parent) = current {
+            if !visited.insert(parent) {
+                break;
+            }
+            *total.entry(Some(parent.to_string())).or_default() += ms;
+            current = parent_of(parent);
+        }
+    }
+
+    // 子分类按 categories 的顺序（置顶、排序值）排列
+    let mut children: HashMap<Option<&str>, Vec<&str>> = HashMap::new();
+    for c in categories {
+        children.entry(parent_of(&c.value)).or_default().push(&c.value);
+    }
+
+    let mut result = Vec::new();
+    let mut stack: Vec<(&str, usize)> = children
+        .get(&None)
+        .map(|roots| roots.iter().rev().map(|v| (*v, 0)).collect())
+        .unwrap_or_default();
+    let mut emitted = HashSet::new();
+    while let Some((value, depth)) = stack.pop() {
+        if !emitted.insert(value) {
+            continue;
+        }
+        let key = Some(value.to_string());
+        let total_ms = total.get(&key).copied().unwrap_or(0);
+        if total_ms == 0 {
+            continue;
+        }
+        let c = defined[value];
+        result.push(CategoryRollup {
+            category: key.clone(),
+            label: c.label.clone(),
+            parent_value: parent_of(value).map(|p| p.to_string()),
+            color: c.color.clone(),
+            archived: c.archived,
+            depth,
+            own_ms: own.get(&key).copied().unwrap_or(0),
+            total_ms,
+        });
+        if let Some(kids) = children.get(&Some(value)) {
+            stack.extend(kids.iter().rev().map(|v| (*v, depth + 1)));
+        }
+    }
+
+    // 未定义的分类（例如内置分类或未分类）作为顶级节点放在最后
+    let mut undefined: Vec<(&Option<String>, &i64)> = own
+        .iter()
+        .filter(|(c, _)| c.as_deref().is_none_or(|v| !defined.contains_key(v)))
+        .collect();
+    undefined.sort_by_key(|(_, ms)| std::cmp::Reverse(**ms));
+    for (category, ms) in undefined {
+        result.push(CategoryRollup {
+            category: category.clone(),
+            label: category.clone().unwrap_or_else(|| "未分类".to_string()),
+            parent_value: None,
+            color: None,
+            archived: false,
+            depth: 0,
+            own_ms: *ms,
+            total_ms: *ms,
+        });
+    }
+
+    result
+}
+
+// ==================== 长期趋势 ====================
+
+fn ms_to_minutes(ms: i64) -> u32 {
+    (ms.max(0) as f64 / 60_000.0).round() as u32
+}
+
+fn round_to(value: f64, digits: i32) -> f64 {
+    let factor = 10f64.powi(digits);
+    (value * factor).round() / factor
+}
+
+/// 按记录所属日期汇总工作时长，只保留 [first, last] 内的日期，下标 0 为 first
+fn daily_work_ms(entries: &[WorkEntry], first: NaiveDate, last: NaiveDate) -> Vec<i64> {
+    let mut days = vec![0i64; ((last - first).num_days() + 1).max(0) as usize];
+    for entry in entries {
+        let date = time_utils::record_date(entry.end_time, entry.tz_offset_minutes);
+        if date >= first && date <= last {
+            days[(date - first).num_days() as usize] += entry.duration;
+        }
+    }
+    days
+}
+
+/// 一年中每天的工作分钟数（贡献图）
+#[derive(Debug, Serialize, Clone)]
+pub struct YearHeatmap {
+    pub year: i32,
+    /// 1 月 1 日是星期几（1 = 周一 … 7 = 周日），用于对齐到周列
+    pub first_weekday: u32,
+    /// 下标 0 为 1 月 1 日
+    pub minutes: Vec<u32>,
+    pub max_minutes: u32,
+    /// 有工作记录的天数
+    pub active_days: u32,
+}
+
+pub fn yearly_heatmap(entries: &[WorkEntry], year: i32) -> YearHeatmap {
+    let first = NaiveDate::from_ymd_opt(year, 1, 1).unwrap_or_default();
+    let last = NaiveDate::from_ymd_opt(year, 12, 31).unwrap_or_default();
+    let minutes: Vec<u32> = daily_work_ms(entries, first, last)
+        .into_iter()
+        .map(ms_to_minutes)
+        .collect();
+
+    YearHeatmap {
+        year,
+        first_weekday: first.weekday().number_from_monday(),
+        max_minutes: minutes.iter().copied().max().unwrap_or(0),
+        active_days: minutes.iter().filter(|m| **m > 0).count() as u32,
+        minutes,
+    }
+}
+
+/// 每日工作时长和休息执行率，以及 7 天、30 天滚动平均
+#[derive(Debug, Serialize, Clone)]
+pub struct Trends {
+    /// 第一天的日期（YYYY-MM-DD），各数组依次对应之后的每一天
+    pub start_date: String,
+    pub work_minutes: Vec<u32>,
+    /// 截至当天的 7 / 30 天平均每日工作分钟数（没有工作的天按 0 计）
+    pub work_avg_7: Vec<f64>,
+    pub work_avg_30: Vec<f64>,
+    /// 当天按计划完成的休息占比，没有休息时为 null
+    pub compliance: Vec<Option<f64>>,
+    /// 截至当天 7 / 30 天内的休息执行率，窗口内没有休息时为 null
+    pub compliance_avg_7: Vec<Option<f64>>,
+    pub compliance_avg_30: Vec<Option<f64>>,
+}
+
+/// 计算 [start, end] 内每天的趋势；entries 和 logs 需要包含 start 之前 29 天的数据，以填满滚动窗口
+pub fn trends(entries: &[WorkEntry], logs: &[BreakLog], start: NaiveDate, end: NaiveDate) -> Trends {
+    const LOOKBACK: i64 = 29;
+    let first = start - Duration::days(LOOKBACK);
+    let work = daily_work_ms(entries, first, end);
+
+    // (按计划完成数, 总数)
+    let mut breaks = vec![(0i64, 0i64); work.len()];
+    for log in logs {
+        let date = time_utils::record_date(log.end_time, log.tz_offset_minutes);
+        if date >= first && date <= end {
+            let day = &mut breaks[(date - first).num_days() as usize];
+            day.0 += (log.outcome == "completed") as i64;
+            day.1 += 1;
+        }
+    }
+
+    // 前缀和，便于计算任意窗口
+    let mut work_sum = vec![0i64; work.len() + 1];
+    let mut break_sum = vec![(0i64, 0i64); work.len() + 1];
+    for i in 0..work.len() {
+        work_sum[i + 1] = work_sum[i] + work[i];
+        break_sum[i + 1] = (break_sum[i].0 + breaks[i].0, break_sum[i].1 + breaks[i].1);
+    }
+    let work_avg = |i: usize, window: usize| {
+        let ms = work_sum[i + 1] - work_sum[i + 1 - window];
+        round_to(ms as f64 / 60_000.0 / window as f64, 1)
+    };
+    let rate = |completed: i64, total: i64| (total > 0).then(|| round_to(completed as f64 / total as f64, 3));
+    let compliance_avg = |i: usize, window: usize| {
+        let (c0, t0) = break_sum[i + 1 - window];
+        let (c1, t1) = break_sum[i + 1];
+        rate(c1 - c0, t1 - t0)
+    };
+
+    let days = LOOKBACK as usize..work.len();
+    Trends {
+        start_date: start.format("%Y-%m-%d").to_string(),
+        work_minutes: days.clone().map(|i| ms_to_minutes(work[i])).collect(),
+        work_avg_7: days.clone().map(|i| work_avg(i, 7)).collect(),
+        work_avg_30: days.clone().map(|i| work_avg(i, 30)).collect(),
+        compliance: days.clone().map(|i| rate(breaks[i].0, breaks[i].1)).collect(),
+        compliance_avg_7: days.clone().map(|i| compliance_avg(i, 7)).collect(),
+        compliance_avg_30: days.map(|i| compliance_avg(i, 30)).collect(),
+    }
+}
+
+/// 各分类逐月的工作时长
+#[derive(Debug, Serialize, Clone)]
+pub struct CategoryMonthly {
+    /// 月份键（YYYY-MM），与各分类的 minutes 一一对应
+    pub months: Vec<String>,
+    /// 按总时长降序
+    pub categories: Vec<CategoryMonthSeries>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CategoryMonthSeries {
+    /// None 表示未分类
+    pub category: Option<String>,
+    pub minutes: Vec<u32>,
+    /// 相对上月的变化比例（0.25 表示增加 25%），第一个月或上月为 0 时为 null
+    pub change: Vec<Option<f64>>,
+}
+
+/// 按月汇总 [start, end] 内各分类的工作时长，并计算环比
+pub fn category_monthly(entries: &[WorkEntry], start: NaiveDate, end: NaiveDate) -> CategoryMonthly {
+    let month_index = |date: NaiveDate| date.year() * 12 + date.month0() as i32;
+    let first = month_index(start);
+    let count = (month_index(end) - first + 1).max(0) as usize;
+
+    let mut by_category: HashMap<Option<String>, Vec<i64>> = HashMap::new();
+    for entry in entries {
+        let date = time_utils::record_date(entry.end_time, entry.tz_offset_minutes);
+        if date < start || date > end {
+            continue;
+        }
+        let months = by_category
+            .entry(entry.category.clone())
+            .or_insert_with(|| vec![0; count]);
+        months[(month_index(date) - first) as usize] += entry.duration;
+    }
+
+    let mut categories: Vec<(i64, CategoryMonthSeries)> = by_category
+        .into_iter()
+        .map(|(category, months)| {
+            let change = (0..months.len())
+                .map(|i| {
+                    let previous = if i > 0 { months[i - 1] } else { 0 };
+                    (previous > 0).then(|| round_to((months[i] - previous) as f64 / previous as f64, 3))
+                })
+                .collect();
+            let series = CategoryMonthSeries {
+                category,
+                minutes: months.iter().copied().map(ms_to_minutes).collect(),
+                change,
+            };
+            (months.iter().sum(), series)
+        })
+        .collect();
+    categories.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.category.cmp(&b.1.category)));
+
+    CategoryMonthly {
+        months: (0..count as i32)
+            .map(|i| {
+                let m = first + i;
+                format!("{:04}-{:02}", m.div_euclid(12), m.rem_euclid(12) + 1)
+            })
+            .collect(),
+        categories: categories.into_iter().map(|(_, series)| series).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    /// UTC 正午结束的工作，不受一天开始的时刻影响
+    fn entry(day: &str, minutes: i64) -> WorkEntry {
+        WorkEntry {
+            end_time: date(day).and_hms_opt(12, 0, 0).unwrap().and_utc().timestamp_millis(),
+            tz_offset_minutes: Some(0),
+            duration: minutes * 60_000,
+            category: None,
+        }
+    }
+
+    fn log(day: &str, outcome: &str) -> BreakLog {
+        let end_time = date(day).and_hms_opt(12, 0, 0).unwrap().and_utc().timestamp_millis();
+        BreakLog {
+            id: 0,
+            user_id: 1,
+            timer_mode: "countdown".to_string(),
+            planned_ms: 300_000,
+            actual_ms: 300_000,
+            overtime_ms: 0,
+            outcome: outcome.to_string(),
+            start_time: end_time - 300_000,
+            end_time,
+            tz_offset_minutes: Some(0),
+            created_at: end_time,
+        }
+    }
+
+    #[test]
+    fn trends_fill_rolling_windows_from_lookback() {
+        let entries = [
+            entry("2026-03-10", 60),
+            entry("2026-03-04", 30),
+            // 恰好是 03-10 的 30 天窗口的第一天
+            entry("2026-02-09", 90),
+            // 早于所有窗口
+            entry("2026-02-08", 120),
+        ];
+        let trends = trends(&entries, &[], date("2026-03-10"), date("2026-03-12"));
+
+        assert_eq!(trends.start_date, "2026-03-10");
+        assert_eq!(trends.work_minutes, vec![60, 0, 0]);
+        assert_eq!(trends.work_avg_7, vec![12.9, 8.6, 8.6]);
+        assert_eq!(trends.work_avg_30, vec![6.0, 3.0, 3.0]);
+    }
+
+    #[test]
+    fn trends_bucket_by_record_offset() {
+        // UTC+8 的 03-11 00:30 在 UTC 仍是 03-10
+        let mut late = entry("2026-03-10", 45);
+        late.end_time = date("2026-03-10").and_hms_opt(16, 30, 0).unwrap().and_utc().timestamp_millis();
+        late.tz_offset_minutes = Some(480);
+        let trends = trends(&[late], &[], date("2026-03-10"), date("2026-03-11"));
+
+        assert_eq!(trends.work_minutes, vec![0, 45]);
+    }
+
+    #[test]
+    fn trends_compliance_is_null_without_breaks() {
+        let logs = [log("2026-03-10", "completed"), log("2026-03-10", "skipped"), log("2026-03-05", "completed")];
+        let trends = trends(&[], &logs, date("2026-03-10"), date("2026-03-12"));
+
+        assert_eq!(trends.compliance, vec![Some(0.5), None, None]);
+        assert_eq!(trends.compliance_avg_7, vec![Some(0.667), Some(0.667), Some(0.5)]);
+    }
+
+    #[test]
+    fn break_compliance_buckets_by_log_offset() {
+        // UTC+8 的周一 03-09 00:30 在 UTC 仍是周日 03-08
+        let mut late = log("2026-03-08", "completed");
+        late.end_time = date("2026-03-08").and_hms_opt(16, 30, 0).unwrap().and_utc().timestamp_millis();
+        late.tz_offset_minutes = Some(480);
+        let compliance = break_compliance(&[late, log("2026-03-08", "skipped")]);
+
+        let days: Vec<&str> = compliance.daily.iter().map(|b| b.period.as_str()).collect();
+        assert_eq!(days, vec!["2026-03-08", "2026-03-09"]);
+        let weeks: Vec<(&str, i64)> = compliance.weekly.iter().map(|b| (b.period.as_str(), b.total)).collect();
+        assert_eq!(weeks, vec![("2026-03-02", 1), ("2026-03-09", 1)]);
+    }
+
+    #[test]
+    fn focus_by_hour_uses_record_offset() {
+        let start_time = date("2026-03-10").and_hms_opt(1, 15, 0).unwrap().and_utc().timestamp_millis();
+        let rating = |offset, rating| FocusRating { category: None, start_time, tz_offset_minutes: Some(offset), rating };
+        let stats = focus_stats(&[rating(0, 4), rating(480, 2)]);
+
+        assert_eq!(stats.by_hour[1].count, 1);
+        assert_eq!(stats.by_hour[9].average, 2.0);
+        assert_eq!(stats.overall.average, 3.0);
+    }
+}
@@ -0,0 +1,197 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, TimeZone, Timelike};
+
+/// 一天开始的时刻（距零点的分钟数），之前的时间计入前一天
+static DAY_START_MINUTES: AtomicU32 = AtomicU32::new(0);
+
+/// 设置一天开始的时刻（“day starts at HH:MM”）
+pub fn set_day_start_minutes(minutes: u32) {
+    DAY_START_MINUTES.store(minutes.min(24 * 60 - 1), Ordering::Relaxed);
+}
+
+pub fn day_start_minutes() -> u32 {
+    DAY_START_MINUTES.load(Ordering::Relaxed)
+}
+
+/// 解析 HH:MM，返回距零点的分钟数
+pub fn parse_hh_mm(value: &str) -> Option<u32> {
+    let (h, m) = value.trim().split_once(':')?;
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+/// 当前时间戳（毫秒）
+pub fn now_ms() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64
+}
+
+/// 时间戳对应的本地日期（按一天开始的时刻划分）
+pub fn local_date(ts_ms: i64) -> NaiveDate {
+    Local
+        .timestamp_millis_opt(ts_ms)
+        .earliest()
+        .map(|dt: DateTime<Local>| shifted_date(dt.naive_local()))
+        .unwrap_or_default()
+}
+
+/// 时间戳在本机时区下相对 UTC 的偏移（分钟）
+pub fn local_offset_minutes(ts_ms: i64) -> i32 {
+    Local
+        .timestamp_millis_opt(ts_ms)
+        .earliest()
+        .map(|dt| dt.offset().local_minus_utc() / 60)
+        .unwrap_or(0)
+}
+
+/// 记录所属的日期：使用记录保存时的时区偏移，旧记录没有偏移时使用本机时区
+pub fn record_date(ts_ms: i64, tz_offset_minutes: Option<i32>) -> NaiveDate {
+    record_date_with_start(ts_ms, tz_offset_minutes, day_start_minutes())
+}
+
+/// 同 record_date，一天开始的时刻由参数给出
+pub fn record_date_with_start(ts_ms: i64, tz_offset_minutes: Option<i32>, day_start: u32) -> NaiveDate {
+    let local = match tz_offset_minutes.and_then(|m| FixedOffset::east_opt(m * 60)) {
+        Some(offset) => offset.timestamp_millis_opt(ts_ms).single().map(|dt| dt.naive_local()),
+        None => Local.timestamp_millis_opt(ts_ms).earliest().map(|dt| dt.naive_local()),
+    };
+    local
+        .map(|local| (local - Duration::minutes(day_start as i64)).date())
+        .unwrap_or_default()
+}
+
+/// 记录所属日期的键，格式 YYYY-MM-DD
+pub fn record_day_key(ts_ms: i64, tz_offset_minutes: Option<i32>) -> String {
+    record_date(ts_ms, tz_offset_minutes).format("%Y-%m-%d").to_string()
+}
+
+/// 墙上时间减去一天开始的时刻后所在的日期
+fn shifted_date(local: chrono::NaiveDateTime) -> NaiveDate {
+    (local - Duration::minutes(day_start_minutes() as i64)).date()
+}
+
+/// 本地日期开始时刻（零点加上一天开始的时刻）的时间戳（毫秒）
+pub fn local_day_start(date: NaiveDate) -> i64 {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap() + Duration::minutes(day_start_minutes() as i64);
+    Local
+        .from_local_datetime(&midnight)
+        .earliest()
+        .map(|dt| dt.timestamp_millis())
+        // 极少数时区在这一时刻发生夏令时跳变，退回到 UTC 解释
+        .unwrap_or_else(|| midnight.and_utc().timestamp_millis())
+}
+
+/// 时间戳所在自然日的起止时间 [start, end)
+pub fn day_range(ts_ms: i64) -> (i64, i64) {
+    let date = local_date(ts_ms);
+    (local_day_start(date), local_day_start(date + Duration::days(1)))
+}
+
+/// 日期范围 [first, last] 对应的查询时间范围 [start, end)
+/// 前后各多取一天，以包含按其他时区偏移记录、但归入这些日期的记录
+pub fn padded_date_range(first: NaiveDate, last: NaiveDate) -> (i64, i64) {
+    (
+        local_day_start(first - Duration::days(1)),
+        local_day_start(last + Duration::days(2)),
+    )
+}
+
+/// 时间戳所在自然周（周一开始）的起止时间 [start, end)
+pub fn week_range(ts_ms: i64) -> (i64, i64) {
+    let monday = week_start_date(local_date(ts_ms));
+    (local_day_start(monday), local_day_start(monday + Duration::days(7)))
+}
+
+/// 日期所在周的周一
+pub fn week_start_date(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// 日期键，格式 YYYY-MM-DD
+pub fn day_key(ts_ms: i64) -> String {
+    local_date(ts_ms).format("%Y-%m-%d").to_string()
+}
+
+/// 周键，使用该周周一的日期
+pub fn week_key(ts_ms: i64) -> String {
+    week_start_date(local_date(ts_ms)).format("%Y-%m-%d").to_string()
+}
+
+/// 时间戳对应的本地星期（1 = 周一 … 7 = 周日）
+pub fn local_weekday(ts_ms: i64) -> u32 {
+    local_date(ts_ms).weekday().number_from_monday()
+}
+
+/// 时间戳对应的本地时间距当天零点的分钟数
+pub fn local_minute_of_day(ts_ms: i64) -> u32 {
+    Local
+        .timestamp_millis_opt(ts_ms)
+        .earliest()
+        .map(|dt| dt.hour() * 60 + dt.minute())
+        .unwrap_or(0)
+}
+
+/// 记录时间距当天零点的分钟数：使用记录保存时的时区偏移，没有偏移时使用本机时区
+pub fn record_minute_of_day(ts_ms: i64, tz_offset_minutes: Option<i32>) -> u32 {
+    match tz_offset_minutes.and_then(|m| FixedOffset::east_opt(m * 60)) {
+        Some(offset) => offset
+            .timestamp_millis_opt(ts_ms)
+            .single()
+            .map(|dt| dt.hour() * 60 + dt.minute())
+            .unwrap_or(0),
+        None => local_minute_of_day(ts_ms),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 某个时区偏移下的墙上时间对应的时间戳
+    fn ts(date: &str, time: &str, offset_minutes: i32) -> i64 {
+        let local = chrono::NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap();
+        (local - Duration::minutes(offset_minutes as i64)).and_utc().timestamp_millis()
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn parse_hh_mm_accepts_only_valid_times() {
+        assert_eq!(parse_hh_mm("04:30"), Some(270));
+        assert_eq!(parse_hh_mm(" 0:00 "), Some(0));
+        assert_eq!(parse_hh_mm("23:59"), Some(1439));
+        assert_eq!(parse_hh_mm("24:00"), None);
+        assert_eq!(parse_hh_mm("12:60"), None);
+        assert_eq!(parse_hh_mm("1230"), None);
+        assert_eq!(parse_hh_mm("ab:cd"), None);
+    }
+
+    #[test]
+    fn record_date_uses_record_offset_and_day_start() {
+        // UTC+8 的 23:30 在 UTC 仍是当天下午，按各自的偏移归入不同的日期
+        let late = ts("2026-03-01", "23:30", 480);
+        assert_eq!(record_date(late, Some(480)), date("2026-03-01"));
+        assert_eq!(record_date(late + 60 * 60_000, Some(480)), date("2026-03-02"));
+        assert_eq!(record_date(late + 60 * 60_000, Some(0)), date("2026-03-01"));
+        assert_eq!(record_minute_of_day(late, Some(480)), 23 * 60 + 30);
+        assert_eq!(record_minute_of_day(late, Some(0)), 15 * 60 + 30);
+
+        // 一天从 04:00 开始时，03:59 仍属于前一天；不修改全局设置，以免影响并行的测试
+        let early = ts("2026-03-02", "03:59", 480);
+        assert_eq!(record_date_with_start(early, Some(480), 4 * 60), date("2026-03-01"));
+        assert_eq!(record_date_with_start(early + 60_000, Some(480), 4 * 60), date("2026-03-02"));
+        assert_eq!(record_date_with_start(early, Some(480), 0), date("2026-03-02"));
+    }
+
+    #[test]
+    fn week_starts_on_monday() {
+        assert_eq!(week_start_date(date("2026-03-01")), date("2026-02-23"));
+        assert_eq!(week_start_date(date("2026-03-02")), date("2026-03-02"));
+        assert_eq!(week_start_date(date("2026-03-08")), date("2026-03-02"));
+    }
+}
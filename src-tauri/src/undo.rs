@@ -0,0 +1,127 @@
+use rusqlite::Result;
+use serde_json::Value;
+
+use crate::db::{Database, UndoChange};
+
+/// 每个用户最多保留的可撤销操作数
+pub const UNDO_LIMIT: usize = 50;
+
+/// 记录一次操作影响到的行：修改前调用 track 保存快照，修改成功后调用 commit 写入撤销日志
+pub struct UndoRecorder {
+    user_id: i64,
+    label: String,
+    changes: Vec<UndoChange>,
+}
+
+impl UndoRecorder {
+    pub fn new(user_id: i64, label: impl Into<String>) -> Self {
+        Self {
+            user_id,
+            label: label.into(),
+            changes: Vec::new(),
+        }
+    }
+
+    /// 保存 table 中 key 列取值为 ids 的行在修改前的状态
+    pub fn track(&mut self, db: &Database, table: &str, key: &str, ids: Vec<Value>) -> Result<()> {
+        let before = db.snapshot_rows(self.user_id, table, key, &ids)?;
+        self.changes.push(UndoChange {
+            table: table.to_string(),
+            key: key.to_string(),
+            ids,
+            before,
+            after: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// 查出 column 列取值在 values 中的行的 key 列，用于只跟踪当前会被修改的行
+    pub fn keys_matching(&self, db: &Database, table: &str, column: &str, values: Vec<Value>, key: &str) -> Result<Vec<Value>> {
+        let rows = db.snapshot_rows(self.user_id, table, column, &values)?;
+        Ok(rows.into_iter().filter_map(|mut row| row.remove(key)).collect())
+    }
+
+    /// 保存修改后的状态并写入撤销日志（没有跟踪到任何行时不记录）
+    pub fn commit(mut self, db: &Database) -> Result<()> {
+        if self.changes.iter().all(|c| c.ids.is_empty()) {
+            return Ok(());
+        }
+        for change in &mut self.changes {
+            change.after = db.snapshot_rows(self.user_id, &change.table, &change.key, &change.ids)?;
+        }
+        db.push_undo_entry(self.user_id, &self.label, &self.changes, UNDO_LIMIT)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{TimerRecord, TimerRecordUpdate, UndoOutcome};
+
+    fn temp_db(name: &str) -> (Database, i64) {
+        let path = std::env::temp_dir().join(format!("lpe-undo-{}-{}.db", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let db = Database::new(path).unwrap();
+        let user_id = db.get_or_create_user("test").unwrap().id;
+        (db, user_id)
+    }
+
+    fn add_record(db: &Database, user_id: i64, id: &str) {
+        let record: TimerRecord = serde_json::from_value(serde_json::json!({
+            "id": id, "user_id": user_id, "record_type": "countdown", "mode": "work", "name": "before",
+            "category": "work", "start_time": 1_000, "end_time": 61_000, "duration": 60_000, "created_at": 1_000
+        }))
+        .unwrap();
+        db.add_timer_record(&record).unwrap();
+    }
+
+    fn rename(db: &Database, user_id: i64, id: &str, name: &str) {
+        let mut recorder = UndoRecorder::new(user_id, "重命名");
+        recorder.track(db, "timer_records", "id", vec![Value::from(id)]).unwrap();
+        let update = TimerRecordUpdate { name: Some(name.to_string()), ..Default::default() };
+        db.update_timer_record(user_id, id, update).unwrap();
+        recorder.commit(db).unwrap();
+    }
+
+    fn name_of(db: &Database, user_id: i64, id: &str) -> Option<String> {
+        db.get_timer_record(user_id, id).unwrap().and_then(|r| r.name)
+    }
+
+    #[test]
+    fn undo_and_redo_restore_tracked_rows() {
+        let (db, user_id) = temp_db("replay");
+        add_record(&db, user_id, "r1");
+        rename(&db, user_id, "r1", "after");
+
+        assert!(matches!(db.apply_undo(user_id, false).unwrap(), Some(UndoOutcome::Applied(_))));
+        assert_eq!(name_of(&db, user_id, "r1").as_deref(), Some("before"));
+        assert!(matches!(db.apply_undo(user_id, true).unwrap(), Some(UndoOutcome::Applied(_))));
+        assert_eq!(name_of(&db, user_id, "r1").as_deref(), Some("after"));
+        assert!(db.apply_undo(user_id, true).unwrap().is_none());
+    }
+
+    #[test]
+    fn undo_refuses_rows_changed_afterwards() {
+        let (db, user_id) = temp_db("stale");
+        add_record(&db, user_id, "r1");
+        rename(&db, user_id, "r1", "after");
+        // 未跟踪的修改：移入回收站
+        db.delete_timer_record(user_id, "r1").unwrap();
+
+        assert!(matches!(db.apply_undo(user_id, false).unwrap(), Some(UndoOutcome::Stale(_))));
+        assert!(db.get_timer_record(user_id, "r1").unwrap().is_none());
+        assert_eq!(db.get_trash(user_id).unwrap().records.len(), 1);
+        // 过期的记录已被丢弃
+        assert!(db.apply_undo(user_id, false).unwrap().is_none());
+    }
+
+    #[test]
+    fn commit_without_rows_records_nothing() {
+        let (db, user_id) = temp_db("empty");
+        let mut recorder = UndoRecorder::new(user_id, "无");
+        recorder.track(&db, "timer_records", "id", Vec::new()).unwrap();
+        recorder.commit(&db).unwrap();
+        assert!(db.get_undo_state(user_id).unwrap().undo.is_none());
+    }
+}
@@ -0,0 +1,185 @@
+use rusqlite::Result;
+use serde::Serialize;
+
+use crate::db::Database;
+use crate::time_utils;
+
+/// 默认提醒阈值（百分比）
+const DEFAULT_THRESHOLDS: [u32; 2] = [80, 100];
+
+/// 工作时长上限配置，读取自设置表
+pub struct WorkBudgetConfig {
+    pub daily_cap_ms: i64,
+    pub weekly_cap_ms: i64,
+    pub thresholds: Vec<u32>,
+    pub block_on_cap: bool,
+}
+
+impl WorkBudgetConfig {
+    pub fn load(db: &Database, user_id: i64) -> Result<Self> {
+        let minutes_to_ms = |v: Option<serde_json::Value>| {
+            v.and_then(|v| v.as_f64())
+                .map(|m| (m.max(0.0) * 60_000.0) as i64)
+                .unwrap_or(0)
+        };
+
+        let daily_cap_ms = minutes_to_ms(db.get_setting_json(user_id, "dailyWorkCapMinutes")?);
+        let weekly_cap_ms = minutes_to_ms(db.get_setting_json(user_id, "weeklyWorkCapMinutes")?);
+
+        let mut thresholds: Vec<u32> = db
+            .get_setting_json(user_id, "workCapThresholds")?
+            .and_then(|v| serde_json::from_value(v).ok())
+            .unwrap_or_else(|| DEFAULT_THRESHOLDS.to_vec());
+        thresholds.sort_unstable();
+        thresholds.dedup();
+
+        let block_on_cap = db
+            .get_setting_json(user_id, "blockWorkOnCap")?
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+
+        Ok(WorkBudgetConfig {
+            daily_cap_ms,
+            weekly_cap_ms,
+            thresholds,
+            block_on_cap,
+        })
+    }
+}
+
+/// 单个周期（日/周）的预算状态
+#[derive(Debug, Serialize, Clone)]
+pub struct BudgetPeriod {
+    pub cap_ms: i64,
+    pub used_ms: i64,
+    pub remaining_ms: i64,
+    pub ratio: f64,
+    pub period_start: i64,
+    pub period_end: i64,
+    /// 已达到的提醒阈值（百分比）
+    pub reached_thresholds: Vec<u32>,
+}
+
+impl BudgetPeriod {
+    fn new(cap_ms: i64, used_ms: i64, range: (i64, i64), thresholds: &[u32]) -> Self {
+        let ratio = used_ms as f64 / cap_ms as f64;
+        BudgetPeriod {
+            cap_ms,
+            used_ms,
+            remaining_ms: (cap_ms - used_ms).max(0),
+            ratio,
+            period_start: range.0,
+            period_end: range.1,
+            reached_thresholds: thresholds
+                .iter()
+                .copied()
+                .filter(|t| ratio * 100.0 >= *t as f64)
+                .collect(),
+        }
+    }
+
+    pub fn is_exceeded(&self) -> bool {
+        self.used_ms >= self.cap_ms
+    }
+}
+
+/// 当前工作预算状态
+#[derive(Debug, Serialize, Clone)]
+pub struct WorkBudget {
+    /// 未设置上限时为 None
+    pub daily: Option<BudgetPeriod>,
+    pub weekly: Option<BudgetPeriod>,
+    pub live_session_ms: i64,
+    pub block_on_cap: bool,
+    /// 是否禁止开始新的工作
+    pub blocked: bool,
+    /// 解除禁止的时间（下一个日/周边界）
+    pub blocked_until: Option<i64>,
+}
+
+/// 根据已保存的工作记录和当前进行中的工作时长计算预算状态
+pub fn compute_work_budget(
+    db: &Database,
+    user_id: i64,
+    live_session_ms: i64,
+    now: i64,
+    include_manual: bool,
+) -> Result<WorkBudget> {
+    let config = WorkBudgetConfig::load(db, user_id)?;
+    let live_session_ms = live_session_ms.max(0);
+
+    let daily = if config.daily_cap_ms > 0 {
+        let range = time_utils::day_range(now);
+        let used = db.sum_work_duration(user_id, range.0, range.1, include_manual)? + live_session_ms;
+        Some(BudgetPeriod::new(config.daily_cap_ms, used, range, &config.thresholds))
+    } else {
+        None
+    };
+
+    let weekly = if config.weekly_cap_ms > 0 {
+        let range = time_utils::week_range(now);
+        let used = db.sum_work_duration(user_id, range.0, range.1, include_manual)? + live_session_ms;
+        Some(BudgetPeriod::new(config.weekly_cap_ms, used, range, &config.thresholds))
+    } else {
+        None
+    };
+
+    // 达到上限后一直禁止到对应周期结束（周上限优先，因为它的边界更晚）
+    let blocked_until = if !config.block_on_cap {
+        None
+    } else if let Some(w) = weekly.as_ref().filter(|w| w.is_exceeded()) {
+        Some(w.period_end)
+    } else {
+        daily
+            .as_ref()
+            .filter(|d| d.is_exceeded())
+            .map(|d| d.period_end)
+    };
+
+    Ok(WorkBudget {
+        daily,
+        weekly,
+        live_session_ms,
+        block_on_cap: config.block_on_cap,
+        blocked: blocked_until.is_some(),
+        blocked_until,
+    })
+}
+
+/// 一次新达到的阈值提醒
+#[derive(Debug, Serialize, Clone)]
+pub struct BudgetWarning {
+    /// "daily" 或 "weekly"
+    pub scope: String,
+    pub threshold: u32,
+    pub used_ms: i64,
+    pub cap_ms: i64,
+}
+
+impl BudgetWarning {
+    /// 用于去重的键，同一周期内每个阈值只提醒一次
+    pub fn key(&self, period_start: i64) -> String {
+        format!("{}:{}:{}", self.scope, period_start, self.threshold)
+    }
+}
+
+/// 列出预算中已达到的全部阈值，附带所属周期起点
+pub fn reached_warnings(budget: &WorkBudget) -> Vec<(i64, BudgetWarning)> {
+    let mut warnings = Vec::new();
+    for (scope, period) in [("daily", &budget.daily), ("weekly", &budget.weekly)] {
+        if let Some(p) = period {
+            for threshold in &p.reached_thresholds {
+                warnings.push((
+                    p.period_start,
+                    BudgetWarning {
+                        scope: scope.to_string(),
+                        threshold: *threshold,
+                        used_ms: p.used_ms,
+                        cap_ms: p.cap_ms,
+                    },
+                ));
+            }
+        }
+    }
+    warnings
+}
@@ -1,6 +1,15 @@
 import { reactive } from "vue";
 import { initDatabase, getSettings, saveSettingsBatch } from "../utils/database";
 
+/**
+ * 正计时重复提醒规则
+ * - 从工作满 afterMinutes 分钟起，每隔 everyMinutes 分钟提醒一次
+ */
+export interface ReminderRule {
+  afterMinutes: number;
+  everyMinutes: number;
+}
+
 export interface AppSettings {
   timerMode: "countdown" | "stopwatch";
   workDurationMinutes: number;
@@ -13,6 +22,26 @@ export interface AppSettings {
   stopwatchBreakSeconds: number;
   stopwatchReminderMinutes: number;
   stopwatchReminderSeconds: number;
+  /** 正计时重复提醒规则，为空时仅在提醒时间触发一次 */
+  stopwatchReminderRules: ReminderRule[];
+  /** 按分类覆盖的提醒规则（key 为分类 value） */
+  categoryReminderRules: Record<string, ReminderRule[]>;
+  /** 每日工作时长上限（分钟），0 表示不限制 */
+  dailyWorkCapMinutes: number;
+  /** 每周工作时长上限（分钟），0 表示不限制 */
+  weeklyWorkCapMinutes: number;
+  /** 达到上限百分比时提醒 */
+  workCapThresholds: number[];
+  /** 达到上限后禁止开始新的工作，直到下一个日/周边界 */
+  blockWorkOnCap: boolean;
+  /** 回收站保留天数，0 表示永久保留 */
+  trashRetentionDays: number;
+  /** 一天开始的时刻（HH:MM），之前的记录计入前一天 */
+  dayStartTime: string;
+  /** 在托盘提示中显示每周目标的预测 */
+  showForecastInTray: boolean;
+  /** 每周一自动保存上周 PDF 报告的文件夹，为空表示不生成 */
+  weeklyReportFolder: string;
   enableworkSound: boolean;
   enablerestSound: boolean;
   enableStopwatchReminderSound: boolean;
@@ -45,6 +74,16 @@ export const defaultSettings: AppSettings = {
   stopwatchBreakSeconds: 0,
   stopwatchReminderMinutes: 25,
   stopwatchReminderSeconds: 0,
+  stopwatchReminderRules: [],
+  categoryReminderRules: {},
+  dailyWorkCapMinutes: 0,
+  weeklyWorkCapMinutes: 0,
+  workCapThresholds: [80, 100],
+  blockWorkOnCap: false,
+  trashRetentionDays: 30,
+  dayStartTime: "00:00",
+  showForecastInTray: false,
+  weeklyReportFolder: "",
   enableworkSound: true,
   enablerestSound: true,
   enableStopwatchReminderSound: true,
@@ -101,7 +140,7 @@ async function loadSettings() {
  * 手动保存设置到数据库（批量操作）
  * 由用户主动调用，而非自动保存
  */
-async function saveSettingsToDB(): Promise<void> {
+async function saveSettingsToDB(undoable: boolean): Promise<void> {
   try {
     // 将设置转换为 [key, value] 数组格式
     const settingsPairs = Object.entries(settings).map(([key, value]) => [
@@ -110,7 +149,7 @@ async function saveSettingsToDB(): Promise<void> {
     ]) as Array<[string, string]>;
     
     // 批量保存到数据库
-    await saveSettingsBatch(settingsPairs);
+    await saveSettingsBatch(settingsPairs, undoable);
     
     console.log("✓ Settings saved to database (batch)");
   } catch (error) {
@@ -141,14 +180,21 @@ export function useSettings() {
   
   /**
    * 手动保存当前设置到数据库
-   * 在用户点击保存按钮时调用
+   * 在用户点击保存按钮时调用；undoable 为 true 时可用 Ctrl+Z 撤销
    */
-  async function save(): Promise<void> {
+  async function save(options: { undoable?: boolean } = {}): Promise<void> {
     if (!initialized) {
       console.warn("Settings not initialized, skipping save");
       return;
     }
-    await saveSettingsToDB();
+    await saveSettingsToDB(options.undoable ?? false);
+  }
+  
+  /**
+   * 重新从数据库读取设置（撤销 / 重做后调用）
+   */
+  async function reload(): Promise<void> {
+    await loadSettings();
   }
   
   function resetToDefault() {
@@ -159,6 +205,7 @@ export function useSettings() {
     settings,
     init,
     save,
+    reload,
     defaultSettings,
     resetToDefault,
   };
@@ -3,6 +3,8 @@ import { ref, onBeforeUnmount } from "vue";
 export interface StopwatchCallbacks {
   onBreakEnd?: () => void | Promise<void>;
   onReminderReached?: () => void | Promise<void>;
+  /** 开始新一轮工作前的检查，返回 false 时拒绝开始 */
+  canStartWork?: () => boolean | Promise<boolean>;
 }
 
 /**
@@ -14,12 +16,15 @@ export function useStopwatch(callbacks?: StopwatchCallbacks) {
   const isRunning = ref(false);
   const mode = ref<"work" | "break">("work"); // work: 工作中, break: 休息中
   const breakTargetMs = ref(0); // 休息目标时长
-  const reminderMs = ref(0); // 提醒时间（毫秒）
+  const reminderMs = ref(0); // 下一次提醒时间（毫秒）
+  const reminderTimes = ref<number[]>([]); // 本次工作中每次提醒的时间戳
 
   let intervalId: number | null = null;
   let lastTick = 0; // 上一次 tick 的时间戳（ms）
   let breakEndTriggered = false; // 标记休息结束回调是否已触发
   let reminderTriggered = false; // 标记提醒回调是否已触发
+  let lastReminderElapsed = 0; // 上一次提醒时的已计时时长
+  let reminderResolver: ((lastReminderMs: number) => number) | null = null; // 重复提醒调度
 
   function clearTimer() {
     if (intervalId !== null) {
@@ -36,7 +41,14 @@ export function useStopwatch(callbacks?: StopwatchCallbacks) {
 
     // 检查工作提醒时间
     if (mode.value === "work" && reminderMs.value > 0 && elapsedMs.value >= reminderMs.value && !reminderTriggered) {
-      reminderTriggered = true; // 标记已触发
+      reminderTimes.value.push(Date.now());
+      lastReminderElapsed = elapsedMs.value;
+      if (reminderResolver) {
+        // 计算下一次提醒时间，返回 0 表示不再提醒
+        reminderMs.value = reminderResolver(lastReminderElapsed);
+      } else {
+        reminderTriggered = true; // 标记已触发
+      }
       // 触发提醒回调
       if (callbacks?.onReminderReached) {
         callbacks.onReminderReached();
@@ -62,7 +74,19 @@ export function useStopwatch(callbacks?: StopwatchCallbacks) {
   /**
    * 开始/继续计时
    */
-  function start() {
+  async function start() {
+    // 新一轮工作开始时重置提醒状态
+    if (mode.value === "work" && elapsedMs.value === 0) {
+      if (callbacks?.canStartWork && !(await callbacks.canStartWork())) return;
+      // 检查期间可能已被其他入口开始
+      if (isRunning.value) return;
+      reminderTimes.value = [];
+      lastReminderElapsed = 0;
+      reminderTriggered = false;
+      if (reminderResolver) {
+        reminderMs.value = reminderResolver(0);
+      }
+    }
     isRunning.value = true;
     ensureIntervalRunning();
   }
@@ -72,10 +96,21 @@ export function useStopwatch(callbacks?: StopwatchCallbacks) {
    * @param targetMs 提醒时间（毫秒）
    */
   function setReminderTime(targetMs: number) {
+    reminderResolver = null;
     reminderMs.value = targetMs;
     reminderTriggered = false; // 重置标志
   }
 
+  /**
+   * 设置重复提醒调度
+   * @param resolver 根据上一次提醒时长计算下一次提醒时长（毫秒），返回 0 表示不再提醒
+   */
+  function setReminderSchedule(resolver: (lastReminderMs: number) => number) {
+    reminderResolver = resolver;
+    reminderMs.value = resolver(lastReminderElapsed);
+    reminderTriggered = false; // 重置标志
+  }
+
   /**
    * 暂停计时
    */
@@ -93,6 +128,8 @@ export function useStopwatch(callbacks?: StopwatchCallbacks) {
     elapsedMs.value = 0;
     mode.value = "work";
     reminderTriggered = false; // 重置标志
+    lastReminderElapsed = 0;
+    // reminderTimes 保留到下一次开始，供完成对话框保存记录使用
   }
 
   /**
@@ -127,7 +164,7 @@ export function useStopwatch(callbacks?: StopwatchCallbacks) {
   }
 
   function updateCallbacks(newCallbacks: Partial<StopwatchCallbacks>) {
-    if (newCallbacks.onBreakEnd || newCallbacks.onReminderReached) {
+    if (newCallbacks.onBreakEnd || newCallbacks.onReminderReached || newCallbacks.canStartWork) {
       callbacks = { ...callbacks, ...newCallbacks };
     }
   }
@@ -139,6 +176,7 @@ export function useStopwatch(callbacks?: StopwatchCallbacks) {
     if (callbacks) {
       callbacks.onBreakEnd = undefined;
       callbacks.onReminderReached = undefined;
+      callbacks.canStartWork = undefined;
     }
     // 重置状态
     isRunning.value = false;
@@ -151,6 +189,7 @@ export function useStopwatch(callbacks?: StopwatchCallbacks) {
     mode,
     breakTargetMs,
     reminderMs,
+    reminderTimes,
     start,
     pause,
     stop,
@@ -158,6 +197,7 @@ export function useStopwatch(callbacks?: StopwatchCallbacks) {
     endBreak,
     skipBreak,
     setReminderTime,
+    setReminderSchedule,
     updateCallbacks,
   };
 }
@@ -7,6 +7,8 @@ export interface UseTimerOptions {
   breakDurationMs?: number;
   onWorkEnd?: () => void;
   onBreakEnd?: (silent?: boolean) => void;
+  /** 开始新一轮工作前的检查，返回 false 时拒绝开始 */
+  canStartWork?: () => boolean | Promise<boolean>;
 }
 
 /**
@@ -105,8 +107,11 @@ export function useTimer(options: UseTimerOptions = {}) {
   /**
    * 从 idle 或 break 进入工作模式
    */
-  function start() {
+  async function start() {
     if (mode.value === "idle" || mode.value === "break") {
+      if (options.canStartWork && !(await options.canStartWork())) return;
+      // 检查期间可能已被其他入口开始
+      if (mode.value !== "idle" && mode.value !== "break") return;
       setMode("work");
     }
     isRunning.value = true;
@@ -132,11 +137,20 @@ export function useTimer(options: UseTimerOptions = {}) {
    * - 保持 isRunning 状态不变（如果原本在计时，则继续走）
    * @param silent 是否静音（不播放提示音）
    */
-  function skipBreak(silent: boolean = false) {
+  async function skipBreak(silent: boolean = false) {
     if (mode.value !== "break") return;
     setMode("work");
     options.onBreakEnd?.(silent);
-    if (isRunning.value) {
+    if (!isRunning.value) return;
+    // 检查期间停在工作起点，不允许开始新一轮工作时保持暂停
+    if (options.canStartWork) {
+      clearTimer();
+      if (!(await options.canStartWork())) {
+        isRunning.value = false;
+        return;
+      }
+    }
+    if (isRunning.value && mode.value === "work") {
       ensureIntervalRunning();
     }
   }
@@ -146,6 +160,7 @@ export function useTimer(options: UseTimerOptions = {}) {
   function updateCallbacks(newCallbacks: Partial<UseTimerOptions>) {
     if (newCallbacks.onWorkEnd) options.onWorkEnd = newCallbacks.onWorkEnd;
     if (newCallbacks.onBreakEnd) options.onBreakEnd = newCallbacks.onBreakEnd;
+    if (newCallbacks.canStartWork) options.canStartWork = newCallbacks.canStartWork;
   }
 
   onBeforeUnmount(() => {
@@ -154,6 +169,7 @@ export function useTimer(options: UseTimerOptions = {}) {
     // 清空回调引用，防止在组件销毁后触发
     options.onWorkEnd = undefined;
     options.onBreakEnd = undefined;
+    options.canStartWork = undefined;
     // 重置所有状态，防止内存泄漏
     isRunning.value = false;
     mode.value = "idle";
@@ -8,7 +8,12 @@ import {
 import { safeExecute } from "../utils/errorHandler";
 import { playAudio } from "../utils/audioPlayer";
 import { minutesSecondsToMs } from "../utils/timeUtils";
-import { addTimerRecord as addTimerRecordDB } from "../utils/database";
+import { createReminderResolver } from "../utils/reminderSchedule";
+import {
+  addTimerRecord as addTimerRecordDB,
+  addBreakLog,
+  type BreakOutcome,
+} from "../utils/database";
 import type { AppSettings } from "./useSettingsDB";
 import type { useTimer } from "./useTimer";
 import type { useStopwatch } from "./useStopwatch";
@@ -27,8 +32,45 @@ export function useTimerHandlers(
   addRecord: ReturnType<typeof useTimerHistory>["addRecord"]
 ) {
   const currentCountdownCategory = ref<string>("work");
+  const currentStopwatchCategory = ref<string>("work");
+  // 当前关联的任务（null 表示未关联）
+  const currentTaskId = ref<number | null>(null);
   const showStopwatchComplete = ref(false);
   const stopwatchWorkDuration = ref(0);
+  const stopwatchReminderTimes = ref<number[]>([]);
+
+  /**
+   * 根据当前正计时分类应用提醒规则
+   */
+  function applyStopwatchReminderSchedule(): void {
+    stopwatch.setReminderSchedule(
+      createReminderResolver(settings, currentStopwatchCategory.value)
+    );
+  }
+
+  /**
+   * 记录一次休息的执行情况（完成 / 提前结束 / 跳过 / 推迟）
+   * 未指定 outcome 时根据实际时长判断是否完成
+   */
+  async function logBreak(
+    timerMode: "countdown" | "stopwatch",
+    plannedMs: number,
+    actualMs: number,
+    outcome?: BreakOutcome,
+    endTime: number = Date.now()
+  ): Promise<void> {
+    await safeExecute(async () => {
+      await addBreakLog({
+        timer_mode: timerMode,
+        planned_ms: plannedMs,
+        actual_ms: actualMs,
+        overtime_ms: Math.max(0, actualMs - plannedMs),
+        outcome: outcome ?? (actualMs >= plannedMs ? "completed" : "cut_short"),
+        start_time: endTime - actualMs,
+        end_time: endTime,
+      });
+    }, "Save break log");
+  }
 
   /**
    * 设置正计时提醒回调
@@ -46,9 +88,14 @@ export function useTimerHandlers(
             }
 
             if (permissionGranted) {
+              const count = stopwatch.reminderTimes.value.length;
+              const minutes = Math.floor(stopwatch.elapsedMs.value / 60000);
               sendNotification({
                 title: "提醒时间到！",
-                body: "已工作一段时间，别忘了休息哦！",
+                body:
+                  count > 1
+                    ? `已连续工作 ${minutes} 分钟（第 ${count} 次提醒），别忘了休息哦！`
+                    : "已工作一段时间，别忘了休息哦！",
                 sound: "default",
               });
             }
@@ -85,6 +132,7 @@ export function useTimerHandlers(
           endTime: endTime,
           duration: workDuration,
           category: currentCountdownCategory.value || "work",
+          taskId: currentTaskId.value ?? undefined,
         });
         console.log("[Countdown] Work record saved:", workDuration, "ms");
 
@@ -177,6 +225,7 @@ export function useTimerHandlers(
           newMode === "work"
         ) {
           stopwatchWorkDuration.value = oldElapsed as number;
+          stopwatchReminderTimes.value = [...stopwatch.reminderTimes.value];
           showStopwatchComplete.value = true;
           console.log("[Stopwatch] Work completed:", oldElapsed, "ms");
         }
@@ -194,8 +243,15 @@ export function useTimerHandlers(
       await win.setAlwaysOnTop(false);
     }, "Cancel window always on top");
 
+    // 记录休息执行情况
+    logBreak(
+      "countdown",
+      minutesSecondsToMs(settings.breakDurationMinutes, settings.breakDurationSeconds),
+      timer.breakElapsedMs.value
+    );
+
     // 结束休息（休息记录已在 onBreakEnd 中自动保存）
-    timer.skipBreak(true);
+    await timer.skipBreak(true);
 
     // 根据设置决定是否自动开始下一次工作
     if (!settings.autoStartNextCountdown) {
@@ -206,6 +262,24 @@ export function useTimerHandlers(
     }
   }
 
+  /**
+   * 处理倒计时休息跳过（用户在计时页点击“跳过休息”）
+   */
+  async function handleCountdownBreakSkip(): Promise<void> {
+    if (timer.mode.value !== "break") return;
+
+    // 记录休息被跳过
+    logBreak(
+      "countdown",
+      minutesSecondsToMs(settings.breakDurationMinutes, settings.breakDurationSeconds),
+      timer.breakElapsedMs.value,
+      "skipped"
+    );
+
+    // 结束休息（休息记录在 onBreakEnd 中自动保存）
+    await timer.skipBreak();
+  }
+
   /**
    * 处理正计时休息结束（用户手动点击按钮）
    */
@@ -229,6 +303,7 @@ export function useTimerHandlers(
       category: "break",
     });
     console.log("[Stopwatch] Break record saved:", breakDuration, "ms");
+    logBreak("stopwatch", stopwatch.breakTargetMs.value, breakDuration, undefined, endTime);
 
     stopwatch.endBreak();
   }
@@ -240,6 +315,8 @@ export function useTimerHandlers(
     name: string;
     takeBreak: boolean;
     category: string;
+    note?: string;
+    focusRating?: number;
   }): void {
     showStopwatchComplete.value = false;
 
@@ -255,15 +332,28 @@ export function useTimerHandlers(
       endTime: endTime,
       duration: workDuration,
       category: data.category,
+      reminderTimes: stopwatchReminderTimes.value,
+      note: data.note,
+      focusRating: data.focusRating,
+      taskId: currentTaskId.value ?? undefined,
     });
+    currentTaskId.value = null;
     console.log("[Stopwatch] Work record saved:", data.name, workDuration, "ms");
 
+    const breakDuration = minutesSecondsToMs(
+      settings.stopwatchBreakMinutes,
+      settings.stopwatchBreakSeconds
+    );
+
+    // 除最后一次外，提醒后继续工作的都视为推迟了休息
+    stopwatchReminderTimes.value.slice(0, -1).forEach((time) => {
+      logBreak("stopwatch", breakDuration, 0, "snoozed", time);
+    });
+
     // 如果需要休息，开始休息倒计时
-    if (data.takeBreak) {
-      const breakDuration = minutesSecondsToMs(
-        settings.stopwatchBreakMinutes,
-        settings.stopwatchBreakSeconds
-      );
+    if (!data.takeBreak) {
+      logBreak("stopwatch", breakDuration, 0, "skipped", endTime);
+    } else {
       stopwatch.startBreak(breakDuration);
       console.log("[Stopwatch] Break started:", breakDuration, "ms");
     }
@@ -295,6 +385,7 @@ export function useTimerHandlers(
               end_time: now,
               duration: elapsed,
               created_at: now,
+              task_id: currentTaskId.value,
             });
             console.log("[ExitFlush] Saved ongoing countdown work:", elapsed, "ms");
           }
@@ -313,6 +404,13 @@ export function useTimerHandlers(
               created_at: now,
             });
             console.log("[ExitFlush] Saved ongoing countdown break:", elapsedBreak, "ms");
+            await logBreak(
+              "countdown",
+              minutesSecondsToMs(settings.breakDurationMinutes, settings.breakDurationSeconds),
+              elapsedBreak,
+              undefined,
+              now
+            );
           }
         }
       } else {
@@ -326,11 +424,14 @@ export function useTimerHandlers(
             record_type: "stopwatch",
             mode: "work",
             name: null,
-            category: "work",
+            category: currentStopwatchCategory.value || "work",
             start_time: now - elapsed,
             end_time: now,
             duration: elapsed,
             created_at: now,
+            reminder_count: stopwatch.reminderTimes.value.length,
+            reminder_times: stopwatch.reminderTimes.value,
+            task_id: currentTaskId.value,
           });
           console.log("[ExitFlush] Saved ongoing stopwatch work:", elapsed, "ms");
         } else if (stopwatch.mode.value === "break") {
@@ -346,6 +447,7 @@ export function useTimerHandlers(
             created_at: now,
           });
           console.log("[ExitFlush] Saved ongoing stopwatch break:", elapsed, "ms");
+          await logBreak("stopwatch", stopwatch.breakTargetMs.value, elapsed, undefined, now);
         }
       }
     } catch (error) {
@@ -355,12 +457,16 @@ export function useTimerHandlers(
 
   return {
     currentCountdownCategory,
+    currentStopwatchCategory,
+    currentTaskId,
     showStopwatchComplete,
     stopwatchWorkDuration,
+    applyStopwatchReminderSchedule,
     setupTimerCallbacks,
     setupStopwatchWatcher,
     setupStopwatchReminderCallback,
     handleCountdownBreakEnd,
+    handleCountdownBreakSkip,
     handleStopwatchBreakEnd,
     handleStopwatchComplete,
     flushActiveSessionsOnExit,
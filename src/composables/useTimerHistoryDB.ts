@@ -16,9 +16,21 @@ export interface TimerRecord {
   mode?: "work" | "break";
   name?: string;
   category?: string;
+  reminderTimes?: number[];
+  note?: string;
+  focusRating?: number;
+  taskId?: number;
+  projectId?: number;
+  billable?: boolean;
+  tags?: string[];
+  source?: "timer" | "manual";
+  /** 记录所属日期（YYYY-MM-DD），按记录时区和一天开始的时刻划分 */
+  day?: string;
 }
 
 const records = ref<TimerRecord[]>([]);
+/** 每次记录成功写入或删除后递增，供依赖记录的状态刷新 */
+const savedVersion = ref(0);
 let initialized = false;
 
 /**
@@ -35,6 +47,15 @@ async function loadRecords(): Promise<void> {
       mode: row.mode as "work" | "break" | undefined,
       name: row.name || undefined,
       category: row.category || undefined,
+      reminderTimes: row.reminder_times?.length ? row.reminder_times : undefined,
+      note: row.note || undefined,
+      focusRating: row.focus_rating ?? undefined,
+      taskId: row.task_id ?? undefined,
+      projectId: row.project_id ?? undefined,
+      billable: row.billable ?? false,
+      tags: row.tags?.length ? row.tags : undefined,
+      source: row.source ?? "timer",
+      day: row.day,
       startTime: row.start_time,
       endTime: row.end_time,
       duration: row.duration,
@@ -85,8 +106,17 @@ export function useTimerHistory() {
       end_time: newRecord.endTime,
       duration: newRecord.duration,
       created_at: Date.now(),
+      reminder_count: newRecord.reminderTimes?.length ?? 0,
+      reminder_times: newRecord.reminderTimes ?? [],
+      note: newRecord.note || null,
+      focus_rating: newRecord.focusRating ?? null,
+      task_id: newRecord.taskId ?? null,
+      project_id: newRecord.projectId ?? null,
+      billable: newRecord.billable ?? false,
+      tags: newRecord.tags ?? [],
     }).then(() => {
       console.log("[useTimerHistory] Record saved to database successfully");
+      savedVersion.value++;
     }).catch(error => {
       console.error("[useTimerHistory] Failed to save record to database:", error);
     });
@@ -99,7 +129,9 @@ export function useTimerHistory() {
     const index = records.value.findIndex((r) => r.id === id);
     if (index !== -1) {
       records.value.splice(index, 1);
-      deleteRecordDB(id).catch(error => {
+      deleteRecordDB(id).then(() => {
+        savedVersion.value++;
+      }).catch(error => {
         console.error("Failed to delete record from database:", error);
       });
     }
@@ -152,6 +184,7 @@ export function useTimerHistory() {
 
   return {
     records,
+    savedVersion,
     addRecord,
     deleteRecord,
     clearRecords,
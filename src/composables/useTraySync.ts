@@ -8,7 +8,7 @@ import type { useStopwatch } from "./useStopwatch";
 
 /**
  * 托盘菜单同步管理
- * - 监听托盘菜单事件（start/pause/reset/settings/quit）
+ * - 监听托盘菜单事件（start/pause/reset/task/settings/quit）
  * - 同步托盘图标状态（idle/working/paused/break）
  */
 export function useTraySync(
@@ -17,6 +17,7 @@ export function useTraySync(
   stopwatch: ReturnType<typeof useStopwatch>,
   handlers: {
     onReset: () => void;
+    onStartTask: (taskId: number) => void;
     onSettings: () => void;
     onQuit: () => Promise<void>;
   }
@@ -62,6 +63,13 @@ export function useTraySync(
       })
     );
 
+    unlistenFns.push(
+      await listen<number>("tray-start-task", (event) => {
+        console.log("[Tray] Start task event received:", event.payload);
+        handlers.onStartTask(event.payload);
+      })
+    );
+
     unlistenFns.push(
       await listen("tray-settings", async () => {
         console.log("[Tray] Settings event received");
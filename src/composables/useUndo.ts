@@ -0,0 +1,77 @@
+import { message } from "@tauri-apps/plugin-dialog";
+import { undo, redo, type UndoEntry } from "../utils/database";
+import { useSettings } from "./useSettingsDB";
+import { useTimerHistory } from "./useTimerHistoryDB";
+
+/**
+ * 全局撤销 / 重做快捷键
+ * - Ctrl/Cmd+Z 撤销，Ctrl/Cmd+Shift+Z 或 Ctrl+Y 重做
+ * - 焦点在输入框内时保留浏览器自带的文本撤销
+ * - 撤销栈保存在数据库中，重启应用后仍然可用
+ * - 相关数据之后又被修改过时拒绝撤销并提示
+ */
+export function useUndo() {
+  const { reload: reloadSettings } = useSettings();
+  const { loadRecords } = useTimerHistory();
+
+  function isEditable(target: EventTarget | null): boolean {
+    if (!(target instanceof HTMLElement)) return false;
+    return (
+      target.isContentEditable ||
+      ["INPUT", "TEXTAREA", "SELECT"].includes(target.tagName)
+    );
+  }
+
+  /**
+   * 刷新受影响的数据
+   */
+  async function refresh(entry: UndoEntry): Promise<void> {
+    if (entry.tables.includes("settings")) {
+      await reloadSettings();
+    }
+    if (entry.tables.some((t) => t !== "settings")) {
+      await loadRecords();
+    }
+  }
+
+  async function run(isRedo: boolean): Promise<void> {
+    try {
+      const entry = isRedo ? await redo() : await undo();
+      if (entry) {
+        console.log(`✓ ${isRedo ? "Redo" : "Undo"}: ${entry.label}`);
+        await refresh(entry);
+      }
+    } catch (error) {
+      console.error(`Failed to ${isRedo ? "redo" : "undo"}:`, error);
+      await message(String(error), { title: isRedo ? "重做失败" : "撤销失败", kind: "warning" });
+    }
+  }
+
+  function handleKeydown(event: KeyboardEvent) {
+    if (!(event.ctrlKey || event.metaKey) || event.altKey) return;
+    if (isEditable(event.target)) return;
+
+    const key = event.key.toLowerCase();
+    if (key === "z") {
+      event.preventDefault();
+      run(event.shiftKey);
+    } else if (key === "y" && !event.shiftKey) {
+      event.preventDefault();
+      run(true);
+    }
+  }
+
+  /**
+   * 注册快捷键，返回清理函数
+   */
+  function setupUndoShortcuts(): () => void {
+    window.addEventListener("keydown", handleKeydown);
+    return () => window.removeEventListener("keydown", handleKeydown);
+  }
+
+  return {
+    undo: () => run(false),
+    redo: () => run(true),
+    setupUndoShortcuts,
+  };
+}
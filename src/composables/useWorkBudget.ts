@@ -0,0 +1,126 @@
+import { ref, watch } from "vue";
+import { sendNotification } from "@tauri-apps/plugin-notification";
+import { safeInvoke, safeExecute } from "../utils/errorHandler";
+import type { AppSettings } from "./useSettingsDB";
+import type { useTimer } from "./useTimer";
+import type { useStopwatch } from "./useStopwatch";
+import { useTimerHistory } from "./useTimerHistoryDB";
+
+export interface BudgetPeriod {
+  cap_ms: number;
+  used_ms: number;
+  remaining_ms: number;
+  ratio: number;
+  period_start: number;
+  period_end: number;
+  reached_thresholds: number[];
+}
+
+export interface WorkBudget {
+  daily: BudgetPeriod | null;
+  weekly: BudgetPeriod | null;
+  live_session_ms: number;
+  block_on_cap: boolean;
+  blocked: boolean;
+  blocked_until: number | null;
+}
+
+/** 工作中刷新预算的间隔 */
+const CHECK_INTERVAL_MS = 60 * 1000;
+
+/**
+ * 工作时长上限（防过劳）
+ * - 定期把进行中的工作时长交给后端计算日/周预算，由后端发出阈值提醒
+ * - 记录保存后立即刷新；开始新一轮工作前由后端按最新记录重新判断是否达到上限
+ */
+export function useWorkBudget(
+  settings: AppSettings,
+  timer: ReturnType<typeof useTimer>,
+  stopwatch: ReturnType<typeof useStopwatch>
+) {
+  const budget = ref<WorkBudget | null>(null);
+  const { savedVersion } = useTimerHistory();
+
+  /**
+   * 当前进行中（尚未保存）的工作时长
+   */
+  function liveSessionMs(): number {
+    if (settings.timerMode === "countdown") {
+      if (timer.mode.value !== "work") return 0;
+      return Math.max(0, timer.totalDurationMs.value - timer.remainingMs.value);
+    }
+    return stopwatch.mode.value === "work" ? stopwatch.elapsedMs.value : 0;
+  }
+
+  /**
+   * 刷新预算状态（后端会对新达到的阈值发送提醒）
+   */
+  async function refresh(): Promise<void> {
+    const result = await safeInvoke<WorkBudget>("check_work_budget", {
+      liveSessionMs: liveSessionMs(),
+    });
+    if (result) {
+      budget.value = result;
+    }
+  }
+
+  /**
+   * 是否允许开始新一轮工作（不使用缓存，由后端按最新记录重新计算）
+   */
+  async function canStartWork(): Promise<boolean> {
+    const result = await safeInvoke<WorkBudget>("get_work_budget", {
+      liveSessionMs: 0,
+    });
+    if (result) {
+      budget.value = result;
+    }
+    const current = budget.value;
+    if (!current?.blocked) return true;
+
+    console.log("[WorkBudget] Work start blocked until", current.blocked_until);
+    if (settings.enableNotification) {
+      safeExecute(async () => {
+        const until = current.blocked_until ? new Date(current.blocked_until).toLocaleString("zh-CN") : "";
+        sendNotification({
+          title: "已达到工作时长上限",
+          body: `为避免过度劳累，${until} 前不能开始新的工作。`,
+        });
+      }, "Send work cap notification");
+    }
+    return false;
+  }
+
+  /**
+   * 启动预算监控，返回停止函数
+   */
+  function startBudgetMonitor(): () => void {
+    timer.updateCallbacks({ canStartWork });
+    stopwatch.updateCallbacks({ canStartWork });
+
+    refresh();
+    // 记录保存或删除后已用时长随之变化
+    const stopWatch = watch(savedVersion, () => refresh());
+    const intervalId = window.setInterval(() => {
+      const working =
+        settings.timerMode === "countdown"
+          ? timer.mode.value === "work" && timer.isRunning.value
+          : stopwatch.mode.value === "work" && stopwatch.isRunning.value;
+      // 空闲时也偶尔刷新，以便跨越日边界后解除限制
+      if (working || budget.value?.blocked) {
+        refresh();
+      }
+    }, CHECK_INTERVAL_MS);
+
+    return () => {
+      clearInterval(intervalId);
+      stopWatch();
+    };
+  }
+
+  return {
+    budget,
+    refresh,
+    canStartWork,
+    startBudgetMonitor,
+  };
+}
@@ -27,6 +27,23 @@ export interface TimerRecord {
   end_time: number;
   duration: number;
   created_at: number;
+  reminder_count?: number;
+  reminder_times?: number[];
+  note?: string | null;
+  focus_rating?: number | null;
+  task_id?: number | null;
+  project_id?: number | null;
+  billable?: boolean;
+  /** 标签名称 */
+  tags?: string[];
+  /** 记录来源：timer（计时产生）/ manual（手动补录） */
+  source?: "timer" | "manual";
+  /** 记录时相对 UTC 的偏移（分钟），保存时为空则由后端按本机时区补上 */
+  tz_offset_minutes?: number | null;
+  /** 拆分出的后半段指向最初的记录，不单独计为番茄 */
+  split_from?: string | null;
+  /** 记录所属日期（YYYY-MM-DD），由后端按记录时区和一天开始的时刻计算，只读 */
+  day?: string;
 }
 
 let currentUser: User | null = null;
@@ -102,10 +119,10 @@ export async function saveSetting(key: string, value: string): Promise<void> {
 }
 
 /**
- * 批量保存设置
+ * 批量保存设置；undoable 为 true 时记录为一次可撤销的操作（只包含值发生变化的设置）
  */
-export async function saveSettingsBatch(settings: Array<[string, string]>): Promise<void> {
-  await invoke("db_save_settings_batch", { settings });
+export async function saveSettingsBatch(settings: Array<[string, string]>, undoable = false): Promise<void> {
+  await invoke("db_save_settings_batch", { settings, undoable });
 }
 
 /**
@@ -115,6 +132,68 @@ export async function getTimerRecords(limit: number = 100): Promise<TimerRecord[
   return await invoke<TimerRecord[]>("db_get_timer_records", { limit });
 }
 
+/** 计时记录查询条件，时间为结束时间 [start_time, end_time) */
+export interface RecordFilter {
+  start_time?: number;
+  end_time?: number;
+  category?: string;
+  record_type?: "countdown" | "stopwatch";
+  mode?: "work" | "break";
+  /** 名称模糊匹配 */
+  name_pattern?: string;
+  tag_ids?: number[];
+  /** true 时需包含全部标签，否则包含任一标签即可 */
+  match_all_tags?: boolean;
+  source?: "timer" | "manual";
+}
+
+/**
+ * 按条件查询计时记录
+ */
+export async function queryTimerRecords(filter: RecordFilter, limit?: number): Promise<TimerRecord[]> {
+  return await invoke<TimerRecord[]>("db_query_timer_records", { filter, limit });
+}
+
+export interface RecordSearchHit {
+  record: TimerRecord;
+  /** 匹配附近的片段，匹配内容用 <mark></mark> 包裹；原文未转义，渲染时需按标记拆分为文本节点 */
+  snippet: string;
+  /** 越小越相关 */
+  rank: number;
+}
+
+/**
+ * 在名称、备注和标签中全文搜索（空格分隔的关键词需全部匹配）
+ * startTime / endTime 按记录结束时间过滤
+ */
+export async function searchRecords(
+  query: string,
+  options: { startTime?: number; endTime?: number; limit?: number } = {}
+): Promise<RecordSearchHit[]> {
+  return await invoke("db_search_records", { query, ...options });
+}
+
+/** 按查询条件批量修改记录的操作 */
+export type BulkOperation =
+  | { action: "recategorize"; category: string | null }
+  | { action: "rename"; name: string | null }
+  | { action: "tag"; add?: number[]; remove?: number[] }
+  | { action: "delete" };
+
+/**
+ * 预览批量操作将影响的记录数（不做修改）
+ */
+export async function previewBulkUpdate(filter: RecordFilter, operation: BulkOperation): Promise<number> {
+  return await invoke("db_bulk_update_records", { filter, operation, dryRun: true });
+}
+
+/**
+ * 按条件批量修改记录，返回受影响的记录数
+ */
+export async function bulkUpdateRecords(filter: RecordFilter, operation: BulkOperation): Promise<number> {
+  return await invoke("db_bulk_update_records", { filter, operation, dryRun: false });
+}
+
 /**
  * 添加计时记录
  */
@@ -127,15 +206,80 @@ export async function addTimerRecord(record: Omit<TimerRecord, "user_id">): Prom
   await invoke("db_add_timer_record", { record: fullRecord });
 }
 
+/** 手动补录的时间段，end_time 与 duration 二选一 */
+export interface ManualEntry {
+  mode?: "work" | "break";
+  name?: string | null;
+  category?: string | null;
+  start_time: number;
+  end_time?: number;
+  duration?: number;
+  note?: string | null;
+  focus_rating?: number | null;
+  task_id?: number | null;
+  project_id?: number | null;
+  billable?: boolean;
+  tags?: string[];
+  /** 与已有记录时间重叠时仍然保存 */
+  force?: boolean;
+}
+
+export interface ManualEntryResult {
+  /** 存在时间冲突且未强制保存时为 null */
+  record: TimerRecord | null;
+  conflicts: TimerRecord[];
+}
+
+/**
+ * 手动补录一段过去的时间
+ */
+export async function addManualRecord(entry: ManualEntry): Promise<ManualEntryResult> {
+  return await invoke("db_add_manual_record", { entry });
+}
+
+/** 拆分后每一段的名称和分类，不填则沿用原记录 */
+export interface RecordPart {
+  name?: string;
+  category?: string;
+}
+
+/**
+ * 在 at 处把一条记录拆成两条，返回前后两段
+ */
+export async function splitTimerRecord(
+  recordId: string,
+  at: number,
+  first?: RecordPart,
+  second?: RecordPart
+): Promise<[TimerRecord, TimerRecord]> {
+  return await invoke("db_split_timer_record", { recordId, at, first: first ?? null, second: second ?? null });
+}
+
+/**
+ * 合并同一模式下相邻的多条记录（备注拼接，标签合并）
+ */
+export async function mergeTimerRecords(recordIds: string[]): Promise<TimerRecord> {
+  return await invoke("db_merge_timer_records", { recordIds });
+}
+
+export interface TimerRecordUpdateResult {
+  updated: boolean;
+  conflicts: TimerRecord[];
+}
+
 /**
  * 更新计时记录
+ * 修改 start_time / end_time 时会重新计算时长；与同类记录时间重叠时返回冲突且不保存，传 force: true 仍然保存
  */
-export async function updateTimerRecord(recordId: string, updates: Partial<Pick<TimerRecord, "name" | "category">>): Promise<void> {
-  await invoke("db_update_timer_record", { recordId, updates });
+export async function updateTimerRecord(
+  recordId: string,
+  updates: Partial<Pick<TimerRecord, "name" | "category" | "note" | "focus_rating" | "task_id" | "project_id" | "billable" | "start_time" | "end_time">> & { force?: boolean }
+): Promise<TimerRecordUpdateResult> {
+  return await invoke("db_update_timer_record", { recordId, updates });
 }
 
 /**
- * 删除计时记录
+ * 删除计时记录（移入回收站）
  */
 export async function deleteTimerRecord(recordId: string): Promise<void> {
   await invoke("db_delete_timer_record", { recordId });
@@ -157,10 +301,33 @@ export interface CustomCategory {
   label: string;
   icon: string;
   created_at: number;
+  /** 父分类的 value，null 表示顶级分类 */
+  parent_value?: string | null;
+  /** 图表颜色（#RRGGBB） */
+  color?: string | null;
+  sort_order?: number;
+  pinned?: boolean;
+  /** 归档后不再出现在分类选择中，但保留在历史统计里 */
+  archived?: boolean;
+}
+
+export type CategoryMetaUpdate = Partial<Pick<CustomCategory, "parent_value" | "color" | "pinned" | "archived">>;
+
+export interface CategoryRollup {
+  category: string | null;
+  label: string;
+  parent_value: string | null;
+  color: string | null;
+  archived: boolean;
+  /** 在树中的层级，顶级为 0 */
+  depth: number;
+  own_ms: number;
+  /** 包含所有子分类的工作时长 */
+  total_ms: number;
 }
 
 /**
- * 获取所有自定义分类
+ * 获取所有自定义分类（包括已归档），按置顶、排序值排列
  */
 export async function getCustomCategories(): Promise<CustomCategory[]> {
   return await invoke("db_get_custom_categories");
@@ -181,9 +348,763 @@ export async function updateCustomCategory(value: string, label: string, icon: s
 }
 
 /**
- * 删除自定义分类
+ * 更新分类的父分类、颜色、置顶和归档状态
+ */
+export async function updateCategoryMeta(value: string, updates: CategoryMetaUpdate): Promise<void> {
+  await invoke("db_update_category_meta", { value, updates });
+}
+
+/**
+ * 按给定顺序保存分类排序
+ */
+export async function reorderCustomCategories(values: string[]): Promise<void> {
+  await invoke("db_reorder_custom_categories", { values });
+}
+
+/**
+ * 获取分类工作时长（子分类汇总到父分类）
+ */
+export async function getCategoryRollup(startTime: number, endTime: number, includeManual = true): Promise<CategoryRollup[]> {
+  return await invoke("db_get_category_rollup", { startTime, endTime, includeManual });
+}
+
+/** 一年中每天的工作分钟数（贡献图） */
+export interface YearHeatmap {
+  year: number;
+  /** 1 月 1 日是星期几（1 = 周一 … 7 = 周日） */
+  first_weekday: number;
+  /** 下标 0 为 1 月 1 日 */
+  minutes: number[];
+  max_minutes: number;
+  active_days: number;
+}
+
+/** 每日趋势，各数组从 start_date 开始逐日对应 */
+export interface Trends {
+  start_date: string;
+  work_minutes: number[];
+  work_avg_7: number[];
+  work_avg_30: number[];
+  /** 没有休息的日期为 null */
+  compliance: (number | null)[];
+  compliance_avg_7: (number | null)[];
+  compliance_avg_30: (number | null)[];
+}
+
+export interface CategoryMonthSeries {
+  category: string | null;
+  /** 与 CategoryMonthly.months 一一对应 */
+  minutes: number[];
+  /** 相对上月的变化比例，上月为 0 时为 null */
+  change: (number | null)[];
+}
+
+export interface CategoryMonthly {
+  /** 月份键（YYYY-MM） */
+  months: string[];
+  categories: CategoryMonthSeries[];
+}
+
+/**
+ * 获取某一年的工作热力图数据
+ */
+export async function getYearlyHeatmap(year: number, includeManual = true): Promise<YearHeatmap> {
+  return await invoke("db_get_yearly_heatmap", { year, includeManual });
+}
+
+/**
+ * 获取时间范围内每日工作时长、休息执行率及 7 天 / 30 天滚动平均
+ */
+export async function getTrends(startTime: number, endTime: number, includeManual = true): Promise<Trends> {
+  return await invoke("db_get_trends", { startTime, endTime, includeManual });
+}
+
+/**
+ * 获取时间范围内各分类逐月工作时长及环比
+ */
+export async function getCategoryMonthly(startTime: number, endTime: number, includeManual = true): Promise<CategoryMonthly> {
+  return await invoke("db_get_category_monthly", { startTime, endTime, includeManual });
+}
+
+export type ChartKind = "category_pie" | "daily_bars" | "heatmap";
+
+export interface ChartOptions {
+  /** category_pie / daily_bars 的时间范围，默认为本周 */
+  startTime?: number;
+  endTime?: number;
+  /** heatmap 的年份，默认为今年 */
+  year?: number;
+  includeManual?: boolean;
+}
+
+/**
+ * 将统计图表保存到 path，格式由扩展名（.svg / .png）决定
+ */
+export async function exportChart(kind: ChartKind, path: string, options: ChartOptions = {}): Promise<void> {
+  return await invoke("db_export_chart", { kind, path, ...options });
+}
+
+/**
+ * 生成 startDate ~ endDate（YYYY-MM-DD，含首尾两天）的 PDF 报告并保存到 path
+ */
+export async function exportReport(startDate: string, endDate: string, path: string): Promise<void> {
+  return await invoke("db_export_report", { startDate, endDate, path });
+}
+
+/**
+ * 修改分类的值，已有记录随之更新，返回受影响的记录数
+ */
+export async function renameCustomCategory(oldValue: string, newValue: string): Promise<number> {
+  return await invoke("db_rename_custom_category", { oldValue, newValue });
+}
+
+/**
+ * 将 source 分类合并到 target，返回受影响的记录数
+ */
+export async function mergeCustomCategories(source: string, target: string): Promise<number> {
+  return await invoke("db_merge_custom_categories", { source, target });
+}
+
+/**
+ * 删除自定义分类，reassignTo 为空时移入回收站，否则合并到 reassignTo；返回引用该分类的记录数
+ */
+export async function deleteCustomCategory(value: string, reassignTo?: string | null): Promise<number> {
+  return await invoke("db_delete_custom_category", { value, reassignTo: reassignTo ?? null });
+}
+
+// ==================== 回收站 ====================
+
+export interface TrashedRecord {
+  record: TimerRecord;
+  deleted_at: number;
+}
+
+export interface TrashedCategory {
+  category: CustomCategory;
+  deleted_at: number;
+}
+
+export interface Trash {
+  records: TrashedRecord[];
+  categories: TrashedCategory[];
+}
+
+/** 要恢复或彻底删除的条目 */
+export interface TrashSelection {
+  record_ids?: string[];
+  category_values?: string[];
+}
+
+/**
+ * 获取回收站内容（会先清除超过保留期限的条目）
+ */
+export async function getTrash(): Promise<Trash> {
+  return await invoke("db_get_trash");
+}
+
+/**
+ * 从回收站恢复，返回恢复的条目数
+ */
+export async function restoreFromTrash(selection: TrashSelection): Promise<number> {
+  return await invoke("db_restore_from_trash", { selection });
+}
+
+/**
+ * 彻底删除回收站中的条目，返回删除的条目数
+ */
+export async function purgeFromTrash(selection: TrashSelection): Promise<number> {
+  return await invoke("db_purge_from_trash", { selection });
+}
+
+/**
+ * 清空回收站
+ */
+export async function emptyTrash(): Promise<number> {
+  return await invoke("db_empty_trash");
+}
+
+// ==================== 标签 ====================
+
+export interface Tag {
+  id: number;
+  user_id: number;
+  name: string;
+  color: string | null;
+  created_at: number;
+}
+
+export interface TagStat {
+  tag_id: number;
+  name: string;
+  color: string | null;
+  session_count: number;
+  total_work_ms: number;
+}
+
+/**
+ * 获取所有标签
+ */
+export async function getTags(): Promise<Tag[]> {
+  return await invoke("db_get_tags");
+}
+
+/**
+ * 添加标签
+ */
+export async function addTag(name: string, color?: string | null): Promise<number> {
+  return await invoke("db_add_tag", { name, color });
+}
+
+/**
+ * 更新标签
+ */
+export async function updateTag(tagId: number, name: string, color?: string | null): Promise<void> {
+  await invoke("db_update_tag", { tagId, name, color });
+}
+
+/**
+ * 删除标签（同时移除与记录的关联）
+ */
+export async function deleteTag(tagId: number): Promise<void> {
+  await invoke("db_delete_tag", { tagId });
+}
+
+/**
+ * 替换记录的全部标签
+ */
+export async function setRecordTags(recordId: string, tagIds: number[]): Promise<void> {
+  await invoke("db_set_record_tags", { recordId, tagIds });
+}
+
+/**
+ * 按标签汇总工作时长
+ */
+export async function getTagStats(startTime: number, endTime: number, includeManual = true): Promise<TagStat[]> {
+  return await invoke("db_get_tag_stats", { startTime, endTime, includeManual });
+}
+
+// ==================== 休息执行记录 ====================
+
+export type BreakOutcome = "completed" | "cut_short" | "skipped" | "snoozed";
+
+export interface BreakLog {
+  id: number;
+  user_id: number;
+  timer_mode: "countdown" | "stopwatch";
+  planned_ms: number;
+  actual_ms: number;
+  overtime_ms: number;
+  outcome: BreakOutcome;
+  start_time: number;
+  end_time: number;
+  /** 记录时相对 UTC 的偏移（分钟），保存时为空则由后端按本机时区补上 */
+  tz_offset_minutes?: number | null;
+  created_at: number;
+}
+
+export interface ComplianceBucket {
+  period: string;
+  total: number;
+  completed: number;
+  cut_short: number;
+  skipped: number;
+  snoozed: number;
+  planned_ms: number;
+  actual_ms: number;
+  overtime_ms: number;
+  rate: number;
+}
+
+export interface BreakCompliance {
+  daily: ComplianceBucket[];
+  weekly: ComplianceBucket[];
+  overall: ComplianceBucket;
+}
+
+/**
+ * 记录一次休息的执行情况
+ */
+export async function addBreakLog(log: Omit<BreakLog, "id" | "user_id" | "created_at">): Promise<number> {
+  return await invoke("db_add_break_log", { log });
+}
+
+/**
+ * 获取时间范围内的休息执行记录
+ */
+export async function getBreakLogs(startTime: number, endTime: number): Promise<BreakLog[]> {
+  return await invoke("db_get_break_logs", { startTime, endTime });
+}
+
+/**
+ * 获取按日/按周汇总的休息执行率
+ */
+export async function getBreakCompliance(startTime: number, endTime: number): Promise<BreakCompliance> {
+  return await invoke("db_get_break_compliance", { startTime, endTime });
+}
+
+// ==================== 打断记录 ====================
+
+export type InterruptionKind = "internal" | "external";
+
+export interface Interruption {
+  id: number;
+  user_id: number;
+  record_id: string | null;
+  kind: InterruptionKind;
+  reason: string | null;
+  occurred_at: number;
+  created_at: number;
+}
+
+export interface SessionInterruptions {
+  record_id: string;
+  name: string | null;
+  category: string | null;
+  start_time: number;
+  duration: number;
+  internal: number;
+  external: number;
+}
+
+export interface InterruptionStats {
+  sessions: SessionInterruptions[];
+  total_internal: number;
+  total_external: number;
+  per_session: number;
+  categories: Array<{
+    category: string | null;
+    total: number;
+    top_reasons: Array<{ category: string | null; reason: string; count: number }>;
+  }>;
+}
+
+/**
+ * 记录一次打断（工作结束保存记录时自动关联到该记录）
+ */
+export async function logInterruption(kind: InterruptionKind, reason: string | null = null): Promise<Interruption> {
+  return await invoke("db_log_interruption", { kind, reason });
+}
+
+/**
+ * 获取打断记录（指定 recordId 时只返回该记录的打断）
+ */
+export async function getInterruptions(options: { recordId?: string; startTime?: number; endTime?: number } = {}): Promise<Interruption[]> {
+  return await invoke("db_get_interruptions", {
+    recordId: options.recordId ?? null,
+    startTime: options.startTime ?? null,
+    endTime: options.endTime ?? null,
+  });
+}
+
+/**
+ * 删除打断记录
+ */
+export async function deleteInterruption(id: number): Promise<void> {
+  await invoke("db_delete_interruption", { id });
+}
+
+/**
+ * 获取打断统计（每次工作的打断次数、各分类常见原因）
+ */
+export async function getInterruptionStats(startTime: number, endTime: number, includeManual = true): Promise<InterruptionStats> {
+  return await invoke("db_get_interruption_stats", { startTime, endTime, includeManual });
+}
+
+// ==================== 专注度统计 ====================
+
+export interface FocusAverage {
+  count: number;
+  average: number;
+}
+
+export interface FocusStats {
+  overall: FocusAverage;
+  by_category: Array<{ category: string | null; count: number; average: number }>;
+  /** 下标为本地小时 0-23 */
+  by_hour: FocusAverage[];
+}
+
+/**
+ * 获取按分类、按小时的平均专注度
+ */
+export async function getFocusStats(startTime: number, endTime: number, includeManual = true): Promise<FocusStats> {
+  return await invoke("db_get_focus_stats", { startTime, endTime, includeManual });
+}
+
+// ==================== 任务 ====================
+
+export type TaskStatus = "todo" | "doing" | "done";
+
+export interface Task {
+  id: number;
+  user_id?: number;
+  title: string;
+  category: string | null;
+  /** 预估番茄数 */
+  estimated_pomodoros: number;
+  status: TaskStatus;
+  /** 截止日期（毫秒时间戳） */
+  due_date: number | null;
+  created_at?: number;
+  updated_at?: number;
+}
+
+export interface TaskProgress {
+  task: Task;
+  /** 已完成番茄数（倒计时工作记录数） */
+  completed_pomodoros: number;
+  session_count: number;
+  total_work_ms: number;
+}
+
+/**
+ * 获取任务列表
+ */
+export async function getTasks(includeDone: boolean = true): Promise<Task[]> {
+  return await invoke("db_get_tasks", { includeDone });
+}
+
+/**
+ * 添加任务，返回新任务 id
+ */
+export async function addTask(task: Omit<Task, "id">): Promise<number> {
+  return await invoke("db_add_task", { task: { id: 0, ...task } });
+}
+
+/**
+ * 更新任务
+ */
+export async function updateTask(task: Task): Promise<void> {
+  await invoke("db_update_task", { task });
+}
+
+/**
+ * 删除任务（已关联的记录会解除关联）
+ */
+export async function deleteTask(taskId: number): Promise<void> {
+  await invoke("db_delete_task", { taskId });
+}
+
+/**
+ * 获取任务预估与实际完成情况
  */
-export async function deleteCustomCategory(value: string): Promise<void> {
-  await invoke("db_delete_custom_category", { value });
+export async function getTaskReport(includeDone: boolean = true): Promise<TaskProgress[]> {
+  return await invoke("db_get_task_report", { includeDone });
+}
+
+// ==================== 项目与计费 ====================
+
+export interface Project {
+  id: number;
+  user_id?: number;
+  name: string;
+  client: string | null;
+  /** 每小时费率 */
+  hourly_rate: number;
+  /** 币种代码，例如 CNY / USD */
+  currency: string;
+  created_at?: number;
+  updated_at?: number;
+}
+
+export type RoundingMode = "none" | "nearest" | "up" | "down";
+
+/** 计费时长取整规则，按每条记录分别取整 */
+export interface RoundingRule {
+  mode: RoundingMode;
+  increment_minutes: number;
+}
+
+export interface ProjectBilling {
+  project_id: number;
+  project_name: string;
+  hourly_rate: number;
+  currency: string;
+  entries: number;
+  raw_ms: number;
+  billed_ms: number;
+  hours: number;
+  amount: number;
 }
 
+export interface ClientBilling {
+  client: string | null;
+  currency: string;
+  projects: ProjectBilling[];
+  raw_ms: number;
+  billed_ms: number;
+  hours: number;
+  amount: number;
+}
+
+export interface BillingReport {
+  start_time: number;
+  end_time: number;
+  rounding: RoundingRule;
+  clients: ClientBilling[];
+}
+
+/**
+ * 获取所有项目
+ */
+export async function getProjects(): Promise<Project[]> {
+  return await invoke("db_get_projects");
+}
+
+/**
+ * 添加项目，返回新项目 id
+ */
+export async function addProject(project: Omit<Project, "id">): Promise<number> {
+  return await invoke("db_add_project", { project: { id: 0, ...project } });
+}
+
+/**
+ * 更新项目
+ */
+export async function updateProject(project: Project): Promise<void> {
+  await invoke("db_update_project", { project });
+}
+
+/**
+ * 删除项目（已关联的记录会解除关联）
+ */
+export async function deleteProject(projectId: number): Promise<void> {
+  await invoke("db_delete_project", { projectId });
+}
+
+/**
+ * 获取按客户汇总的计费报表
+ */
+export async function getBillingReport(
+  startTime: number,
+  endTime: number,
+  rounding?: RoundingRule
+): Promise<BillingReport> {
+  return await invoke("db_get_billing_report", { startTime, endTime, rounding });
+}
+
+/**
+ * 获取计费报表的 CSV 文本
+ */
+export async function getBillingCsv(
+  startTime: number,
+  endTime: number,
+  rounding?: RoundingRule
+): Promise<string> {
+  return await invoke("db_export_billing_csv", { startTime, endTime, rounding });
+}
+
+// ==================== 目标 ====================
+
+export type GoalMetric = "work_minutes" | "work_sessions" | "break_count";
+export type GoalPeriod = "daily" | "weekly";
+
+export interface Goal {
+  id: number;
+  user_id?: number;
+  title: string;
+  /** null 表示所有分类（仅对工作类指标生效） */
+  category: string | null;
+  metric: GoalMetric;
+  period: GoalPeriod;
+  target: number;
+  /** 生效的星期（1 = 周一 … 7 = 周日），为空表示每天；仅对每日目标生效 */
+  weekdays?: number[];
+  /** 只统计在该时间（HH:MM）之前结束的记录 */
+  before_time?: string | null;
+  active?: boolean;
+  created_at?: number;
+  updated_at?: number;
+}
+
+export interface GoalProgress {
+  id: number;
+  goal_id: number;
+  user_id: number;
+  /** 每日目标为 YYYY-MM-DD，每周目标为该周周一 */
+  period_key: string;
+  period_start: number;
+  period_end: number;
+  value: number;
+  target: number;
+  achieved_at: number | null;
+  updated_at: number;
+}
+
+/** `goal-progress` / `goal-achieved` 事件的载荷 */
+export interface GoalEvaluation {
+  goal: Goal;
+  progress: GoalProgress;
+  ratio: number;
+  achieved: boolean;
+  newly_achieved: boolean;
+  changed: boolean;
+}
+
+/**
+ * 获取所有目标
+ */
+export async function getGoals(): Promise<Goal[]> {
+  return await invoke("db_get_goals");
+}
+
+/**
+ * 添加目标，返回新目标 id
+ */
+export async function addGoal(goal: Omit<Goal, "id">): Promise<number> {
+  return await invoke("db_add_goal", { goal: { id: 0, ...goal } });
+}
+
+/**
+ * 更新目标
+ */
+export async function updateGoal(goal: Goal): Promise<void> {
+  await invoke("db_update_goal", { goal });
+}
+
+/**
+ * 删除目标及其历史进度
+ */
+export async function deleteGoal(goalId: number): Promise<void> {
+  await invoke("db_delete_goal", { goalId });
+}
+
+/**
+ * 获取当前周期内所有启用目标的进度
+ */
+export async function getGoalStatus(includeManual = true): Promise<GoalEvaluation[]> {
+  return await invoke("db_get_goal_status", { includeManual });
+}
+
+/**
+ * 获取时间范围内的目标进度历史
+ */
+export async function getGoalHistory(
+  startTime: number,
+  endTime: number,
+  goalId?: number
+): Promise<GoalProgress[]> {
+  return await invoke("db_get_goal_history", { goalId, startTime, endTime });
+}
+
+/** 每周目标在本周的预测 */
+export interface WeeklyForecast {
+  goal: Goal;
+  current: number;
+  /** 按历史同一星期几的平均值推算的本周最终值 */
+  projected: number;
+  target: number;
+  /** 按历史节奏此时应有的进度 */
+  expected_by_now: number;
+  status: "achieved" | "ahead" | "behind";
+  /** 周一到周日的历史平均值 */
+  weekday_averages: number[];
+  history_weeks: number;
+  /** 本周剩余天数（含今天） */
+  remaining_days: number;
+  required_per_day: number;
+}
+
+/**
+ * 按最近 weeks 周（默认 4 周）同一星期几的平均值预测本周各每周目标的结果
+ */
+export async function getWeeklyForecast(weeks?: number): Promise<WeeklyForecast[]> {
+  return await invoke("db_get_weekly_forecast", { weeks });
+}
+
+// ==================== 连续记录与成就 ====================
+
+export interface Streak {
+  kind: "work_days" | "break_compliance" | "goal";
+  label: string;
+  goal_id: number | null;
+  /** 截至今天（或昨天）的连续天数 */
+  current: number;
+  longest: number;
+  last_date: string | null;
+}
+
+/** 也是 `achievement-unlocked` 事件的载荷 */
+export interface AchievementStatus {
+  key: string;
+  title: string;
+  description: string;
+  threshold: number;
+  progress: number;
+  unlocked_at: number | null;
+}
+
+/**
+ * 获取连续工作、连续按时休息以及每日目标的连续达成天数
+ */
+export async function getStreaks(includeManual = true): Promise<Streak[]> {
+  return await invoke("db_get_streaks", { includeManual });
+}
+
+/**
+ * 获取所有成就及进度（会顺带解锁新达成的成就）
+ */
+export async function getAchievements(): Promise<AchievementStatus[]> {
+  return await invoke("db_get_achievements");
+}
+
+// ==================== 撤销 / 重做 ====================
+
+/** 也是 `undo-applied` 事件的载荷 */
+export interface UndoEntry {
+  id: number;
+  label: string;
+  created_at: number;
+  /** 受影响的表，用于决定需要刷新哪些数据 */
+  tables: string[];
+}
+
+export interface UndoState {
+  undo: string | null;
+  redo: string | null;
+}
+
+/**
+ * 撤销最近一次可撤销的操作（编辑记录、批量修改、合并分类、保存设置）
+ * 没有可撤销的操作时返回 null
+ */
+export async function undo(): Promise<UndoEntry | null> {
+  return await invoke("db_undo");
+}
+
+/**
+ * 重做最近一次撤销的操作
+ */
+export async function redo(): Promise<UndoEntry | null> {
+  return await invoke("db_redo");
+}
+
+/**
+ * 获取当前可撤销 / 可重做的操作名称
+ */
+export async function getUndoState(): Promise<UndoState> {
+  return await invoke("db_get_undo_state");
+}
+
+// ==================== 审计日志 ====================
+
+export interface AuditEntry {
+  id: number;
+  table_name: string;
+  row_key: string;
+  action: "insert" | "update" | "delete";
+  /** 变更前的完整行，新增时为 null */
+  old_values: Record<string, unknown> | null;
+  /** 变更后的完整行，删除时为 null */
+  new_values: Record<string, unknown> | null;
+  /** 触发变更的命令，后台任务为 "system" */
+  command: string;
+  created_at: number;
+}
+
+/**
+ * 获取某条记录的完整变更历史（按时间先后）
+ */
+export async function getRecordHistory(recordId: string): Promise<AuditEntry[]> {
+  return await invoke("db_get_record_history", { recordId });
+}
@@ -1,6 +1,6 @@
 import { save, open } from '@tauri-apps/plugin-dialog';
 import { writeTextFile, readTextFile } from '@tauri-apps/plugin-fs';
-import { getSettings, getTimerRecords, getCustomCategories, CustomCategory, TimerRecord } from './database';
+import { getSettings, getTimerRecords, getCustomCategories, getTags, getBillingCsv, exportChart, exportReport, CustomCategory, TimerRecord, Tag, RoundingRule, ChartKind, ChartOptions } from './database';
 
 export interface ExportData {
   version: string;
@@ -8,6 +8,8 @@ export interface ExportData {
   settings: Record<string, any>;
   records: TimerRecord[];
   categories: CustomCategory[];
+  /** 标签定义（记录中的 tags 为标签名称） */
+  tags?: Tag[];
 }
 
 /**
@@ -19,6 +21,7 @@ export async function exportUserData(): Promise<boolean> {
     const settings = await getSettings();
     const records = await getTimerRecords(1000); // 导出最近 1000 条记录
     const categories = await getCustomCategories();
+    const tags = await getTags();
 
     // 2. 构建导出数据
     const exportData: ExportData = {
@@ -27,6 +30,7 @@ export async function exportUserData(): Promise<boolean> {
       settings: settings,
       records: records,
       categories: categories,
+      tags: tags,
     };
 
     // 3. 转换为 JSON 字符串
@@ -128,7 +132,91 @@ export function validateImportData(data: ExportData): { valid: boolean; errors:
 export function getImportSummary(data: ExportData): string {
   const recordCount = data.records?.length || 0;
   const categoryCount = data.categories?.length || 0;
+  const tagCount = data.tags?.length || 0;
   const exportDate = data.exportTime ? new Date(data.exportTime).toLocaleString('zh-CN') : '未知';
 
-  return `导出时间: ${exportDate}\n设置项: ${Object.keys(data.settings || {}).length} 项\n工作记录: ${recordCount} 条\n自定义分类: ${categoryCount} 个`;
+  return `导出时间: ${exportDate}\n设置项: ${Object.keys(data.settings || {}).length} 项\n工作记录: ${recordCount} 条\n自定义分类: ${categoryCount} 个\n标签: ${tagCount} 个`;
+}
+
+/**
+ * 导出计费报表为 CSV 文件
+ */
+export async function exportBillingCsv(startTime: number, endTime: number, rounding?: RoundingRule): Promise<boolean> {
+  try {
+    const csvContent = await getBillingCsv(startTime, endTime, rounding);
+
+    const defaultFileName = `lpe-reminder-billing-${new Date(startTime).toISOString().split('T')[0]}.csv`;
+    const filePath = await save({
+      defaultPath: defaultFileName,
+      filters: [{
+        name: 'CSV',
+        extensions: ['csv']
+      }]
+    });
+
+    if (!filePath) {
+      return false;
+    }
+
+    // 添加 BOM，便于 Excel 正确识别中文
+    await writeTextFile(filePath as string, '\uFEFF' + csvContent);
+
+    return true;
+  } catch (error) {
+    console.error('导出计费报表失败:', error);
+    throw new Error(`导出计费报表失败: ${error}`);
+  }
+}
+
+/**
+ * 选择保存位置后导出统计图表（PNG 中的中文显示为方框，需要完整文字时请选择 SVG）
+ */
+export async function exportChartImage(kind: ChartKind, options: ChartOptions = {}): Promise<boolean> {
+  try {
+    const defaultFileName = `lpe-reminder-${kind.replace('_', '-')}-${new Date().toISOString().split('T')[0]}.png`;
+    const filePath = await save({
+      defaultPath: defaultFileName,
+      filters: [
+        { name: 'PNG', extensions: ['png'] },
+        { name: 'SVG', extensions: ['svg'] }
+      ]
+    });
+
+    if (!filePath) {
+      return false;
+    }
+
+    await exportChart(kind, filePath as string, options);
+
+    return true;
+  } catch (error) {
+    console.error('导出图表失败:', error);
+    throw new Error(`导出图表失败: ${error}`);
+  }
+}
+
+/**
+ * 选择保存位置后导出 startDate ~ endDate（YYYY-MM-DD）的 PDF 报告
+ */
+export async function exportReportPdf(startDate: string, endDate: string): Promise<boolean> {
+  try {
+    const filePath = await save({
+      defaultPath: `lpe-reminder-report-${startDate}.pdf`,
+      filters: [{
+        name: 'PDF',
+        extensions: ['pdf']
+      }]
+    });
+
+    if (!filePath) {
+      return false;
+    }
+
+    await exportReport(startDate, endDate, filePath as string);
+
+    return true;
+  } catch (error) {
+    console.error('导出报告失败:', error);
+    throw new Error(`导出报告失败: ${error}`);
+  }
 }
@@ -0,0 +1,72 @@
+import type { AppSettings, ReminderRule } from "../composables/useSettingsDB";
+import { minutesSecondsToMs } from "./timeUtils";
+
+/**
+ * 获取指定分类生效的提醒规则
+ * - 分类有单独配置时使用分类规则，否则使用全局规则
+ */
+export function getReminderRules(settings: AppSettings, category: string): ReminderRule[] {
+  const rules = settings.categoryReminderRules?.[category] ?? settings.stopwatchReminderRules ?? [];
+  return rules
+    .filter((r) => r.everyMinutes > 0 && r.afterMinutes >= 0)
+    .sort((a, b) => a.afterMinutes - b.afterMinutes);
+}
+
+/**
+ * 计算下一次提醒的已计时时长（毫秒）
+ * @param rules 已排序的提醒规则
+ * @param lastReminderMs 上一次提醒时的已计时时长，尚未提醒时为 0
+ * @param fallbackMs 无规则时的单次提醒时间
+ * @returns 下一次提醒时间，0 表示不再提醒
+ */
+export function nextReminderMs(rules: ReminderRule[], lastReminderMs: number, fallbackMs: number): number {
+  if (rules.length === 0) {
+    return lastReminderMs === 0 ? fallbackMs : 0;
+  }
+
+  const minute = 60 * 1000;
+  // 当前所处的规则阶段（尚未进入任何阶段时，以第一条规则的起点为准）
+  const active = [...rules].reverse().find((r) => r.afterMinutes * minute <= lastReminderMs);
+  const base = active ? lastReminderMs : rules[0].afterMinutes * minute;
+  let next = base + (active ?? rules[0]).everyMinutes * minute;
+
+  // 若下一阶段在此之前开始，则按新阶段的间隔提前提醒
+  for (const rule of rules) {
+    const start = rule.afterMinutes * minute;
+    if (start > lastReminderMs && start < next) {
+      next = Math.min(next, start + rule.everyMinutes * minute);
+    }
+  }
+  return next;
+}
+
+/**
+ * 解析规则文本，例如 "0:25, 120:10" 表示开始后每 25 分钟提醒，2 小时后改为每 10 分钟
+ */
+export function parseReminderRules(text: string): ReminderRule[] {
+  return text
+    .split(/[,，]/)
+    .map((part) => part.trim())
+    .filter(Boolean)
+    .map((part) => {
+      const [after, every] = part.split(/[:：]/).map((v) => Number(v.trim()));
+      return { afterMinutes: after, everyMinutes: every };
+    })
+    .filter((r) => Number.isFinite(r.afterMinutes) && Number.isFinite(r.everyMinutes) && r.everyMinutes > 0);
+}
+
+/**
+ * 将规则格式化为文本
+ */
+export function formatReminderRules(rules: ReminderRule[]): string {
+  return rules.map((r) => `${r.afterMinutes}:${r.everyMinutes}`).join(", ");
+}
+
+/**
+ * 根据设置和分类生成提醒调度函数，供 useStopwatch 使用
+ */
+export function createReminderResolver(settings: AppSettings, category: string): (lastReminderMs: number) => number {
+  const rules = getReminderRules(settings, category);
+  const fallbackMs = minutesSecondsToMs(settings.stopwatchReminderMinutes, settings.stopwatchReminderSeconds);
+  return (lastReminderMs: number) => nextReminderMs(rules, lastReminderMs, fallbackMs);
+}
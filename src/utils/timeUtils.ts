@@ -34,3 +34,18 @@ export function formatTime(ms: number): string {
   const seconds = (totalSeconds % 60).toString().padStart(2, "0");
   return `${minutes}:${seconds}`;
 }
+
+/**
+ * 时间戳所属日期的键（YYYY-MM-DD），按本地时区和一天开始的时刻划分
+ * 与后端为记录计算的 day 字段一致，用于尚未从数据库重新加载的记录
+ * @param timestamp 毫秒时间戳
+ * @param dayStartTime 一天开始的时刻（HH:MM）
+ */
+export function dayKey(timestamp: number, dayStartTime = "00:00"): string {
+  const [h, m] = dayStartTime.split(":").map(Number);
+  const date = new Date(timestamp);
+  date.setHours(date.getHours() - (h || 0), date.getMinutes() - (m || 0));
+  const month = (date.getMonth() + 1).toString().padStart(2, "0");
+  const day = date.getDate().toString().padStart(2, "0");
+  return `${date.getFullYear()}-${month}-${day}`;
+}